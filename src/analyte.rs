@@ -0,0 +1,376 @@
+//! Catalogue of common clinical laboratory analytes.
+//!
+//! Units of mass, amount of substance and international units are not
+//! commensurable in UCUM; converting between them needs a property of the
+//! measured substance. Each [`Analyte`] carries the molar mass, ionic charge
+//! and mass per `[IU]` that apply, along with its conventional and SI
+//! reporting units, and [`convert_for_analyte`] uses them to convert, for
+//! example, glucose from `mg/dL` to `mmol/L`.
+
+use crate::{
+    ast::{OwnedUnitExpr, OwnedUnitFactor},
+    engine::{UcumEngine, default_engine},
+    error::UcumError,
+    precision::{Number, checked_pow},
+    special_units::{ConversionContext, SubstanceConversion},
+};
+
+/// A laboratory analyte and the factors that convert its units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analyte {
+    /// Identifier used by [`find_analyte`], e.g. `glucose`.
+    pub code: &'static str,
+    /// Human-readable name.
+    pub name: &'static str,
+    /// Molar mass in g/mol.
+    pub molar_mass: Option<Number>,
+    /// Charge of the ion, relating `mol` to `eq`.
+    pub charge: Option<i8>,
+    /// Mass in grams of one `[IU]`.
+    pub grams_per_iu: Option<Number>,
+    /// Conventional (US) reporting unit.
+    pub conventional_unit: &'static str,
+    /// SI reporting unit.
+    pub si_unit: &'static str,
+}
+
+/// `mantissa` × 10<sup>-`scale`</sup>.
+const fn decimal(mantissa: u32, scale: u32) -> Number {
+    Number::from_parts(mantissa, 0, 0, false, scale)
+}
+
+const fn analyte(
+    code: &'static str,
+    name: &'static str,
+    molar_mass: Option<Number>,
+    conventional_unit: &'static str,
+    si_unit: &'static str,
+) -> Analyte {
+    Analyte {
+        code,
+        name,
+        molar_mass,
+        charge: None,
+        grams_per_iu: None,
+        conventional_unit,
+        si_unit,
+    }
+}
+
+const fn ion(
+    code: &'static str,
+    name: &'static str,
+    molar_mass: Number,
+    charge: i8,
+    conventional_unit: &'static str,
+) -> Analyte {
+    Analyte {
+        charge: Some(charge),
+        ..analyte(code, name, Some(molar_mass), conventional_unit, "mmol/L")
+    }
+}
+
+static ANALYTES: &[Analyte] = &[
+    analyte(
+        "glucose",
+        "Glucose",
+        Some(decimal(18016, 2)),
+        "mg/dL",
+        "mmol/L",
+    ),
+    analyte(
+        "cholesterol",
+        "Cholesterol",
+        Some(decimal(38665, 2)),
+        "mg/dL",
+        "mmol/L",
+    ),
+    // As triolein, the convention for triglyceride conversions
+    analyte(
+        "triglycerides",
+        "Triglycerides",
+        Some(decimal(8857, 1)),
+        "mg/dL",
+        "mmol/L",
+    ),
+    analyte(
+        "creatinine",
+        "Creatinine",
+        Some(decimal(11312, 2)),
+        "mg/dL",
+        "umol/L",
+    ),
+    analyte("urea", "Urea", Some(decimal(6006, 2)), "mg/dL", "mmol/L"),
+    // Two nitrogen atoms per urea molecule, so BUN in mg/dL gives urea in mmol/L
+    analyte(
+        "urea-nitrogen",
+        "Urea nitrogen",
+        Some(decimal(28014, 3)),
+        "mg/dL",
+        "mmol/L",
+    ),
+    analyte(
+        "uric-acid",
+        "Uric acid",
+        Some(decimal(16811, 2)),
+        "mg/dL",
+        "umol/L",
+    ),
+    analyte(
+        "bilirubin",
+        "Bilirubin",
+        Some(decimal(58466, 2)),
+        "mg/dL",
+        "umol/L",
+    ),
+    analyte(
+        "lactate",
+        "Lactate",
+        Some(decimal(8907, 2)),
+        "mg/dL",
+        "mmol/L",
+    ),
+    analyte(
+        "phosphorus",
+        "Phosphorus",
+        Some(decimal(30974, 3)),
+        "mg/dL",
+        "mmol/L",
+    ),
+    analyte("iron", "Iron", Some(decimal(55845, 3)), "ug/dL", "umol/L"),
+    ion("sodium", "Sodium", decimal(22990, 3), 1, "meq/L"),
+    ion("potassium", "Potassium", decimal(39098, 3), 1, "meq/L"),
+    ion("chloride", "Chloride", decimal(3545, 2), -1, "meq/L"),
+    ion("bicarbonate", "Bicarbonate", decimal(61017, 3), -1, "meq/L"),
+    ion("calcium", "Calcium", decimal(40078, 3), 2, "mg/dL"),
+    ion("magnesium", "Magnesium", decimal(24305, 3), 2, "mg/dL"),
+    analyte(
+        "cortisol",
+        "Cortisol",
+        Some(decimal(36246, 2)),
+        "ug/dL",
+        "nmol/L",
+    ),
+    analyte(
+        "testosterone",
+        "Testosterone",
+        Some(decimal(28842, 2)),
+        "ng/dL",
+        "nmol/L",
+    ),
+    analyte(
+        "25-hydroxyvitamin-d",
+        "25-Hydroxyvitamin D",
+        Some(decimal(40064, 2)),
+        "ng/mL",
+        "nmol/L",
+    ),
+    // 1 [IU] is 0.025 ug of cholecalciferol
+    Analyte {
+        grams_per_iu: Some(decimal(25, 9)),
+        ..analyte(
+            "vitamin-d3",
+            "Vitamin D3 (cholecalciferol)",
+            Some(decimal(38464, 2)),
+            "[IU]",
+            "ug",
+        )
+    },
+    // 1 [IU] is 0.0347 mg of human insulin (WHO international standard)
+    Analyte {
+        grams_per_iu: Some(decimal(347, 7)),
+        ..analyte(
+            "insulin",
+            "Insulin",
+            Some(decimal(5808, 0)),
+            "u[IU]/mL",
+            "pmol/L",
+        )
+    },
+    // Conventional 100 [IU] per mg of unfractionated heparin; the potency of
+    // actual preparations varies
+    Analyte {
+        grams_per_iu: Some(decimal(1, 5)),
+        ..analyte("heparin", "Heparin", None, "[IU]/mL", "[IU]/mL")
+    },
+    analyte("hemoglobin", "Hemoglobin", None, "g/dL", "g/L"),
+    analyte("albumin", "Albumin", None, "g/dL", "g/L"),
+];
+
+/// Atoms of the international unit.
+const INTERNATIONAL_UNITS: [&str; 2] = ["[IU]", "[iU]"];
+
+/// All analytes in the catalogue.
+pub fn analytes() -> &'static [Analyte] {
+    ANALYTES
+}
+
+/// Find an analyte by its code or name, ignoring case.
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::find_analyte;
+///
+/// let glucose = find_analyte("Glucose").unwrap();
+/// assert_eq!(glucose.si_unit, "mmol/L");
+/// ```
+pub fn find_analyte(code: &str) -> Option<&'static Analyte> {
+    ANALYTES.iter().find(|analyte| {
+        analyte.code.eq_ignore_ascii_case(code) || analyte.name.eq_ignore_ascii_case(code)
+    })
+}
+
+impl Analyte {
+    /// The molar mass and charge of this analyte as [`ConversionContext`]
+    /// parameters.
+    pub fn conversion_context(&self) -> ConversionContext {
+        let mut context = ConversionContext::new();
+        if let Some(molar_mass) = self.molar_mass {
+            context = context.with_molar_mass(molar_mass);
+        }
+        if let Some(charge) = self.charge {
+            context = context.with_charge(Number::from(charge));
+        }
+        context
+    }
+
+    /// Convert a value of this analyte with the default engine, see
+    /// [`convert_for_analyte`] and [`UcumEngine::convert_analyte`].
+    #[allow(clippy::result_large_err)]
+    pub fn convert(
+        &self,
+        value: Number,
+        from: &str,
+        to: &str,
+    ) -> Result<SubstanceConversion, UcumError> {
+        default_engine().convert_analyte(self, value, from, to)
+    }
+}
+
+/// Convert a value of `analyte` with the engine's units.
+#[allow(clippy::result_large_err)]
+pub(crate) fn convert_analyte_with(
+    engine: &UcumEngine,
+    analyte: &Analyte,
+    value: Number,
+    from: &str,
+    to: &str,
+) -> Result<SubstanceConversion, UcumError> {
+    let (source, target) = (engine.parse(from)?, engine.parse(to)?);
+    let international = |expr| -> i32 {
+        INTERNATIONAL_UNITS
+            .iter()
+            .map(|code| engine.atom_power(expr, code))
+            .sum()
+    };
+    let power = international(&source) - international(&target);
+    if power == 0 {
+        return engine.convert_substance_expr(
+            value,
+            &source,
+            &target,
+            &analyte.conversion_context(),
+        );
+    }
+
+    let grams_per_iu = analyte.grams_per_iu.ok_or_else(|| {
+        UcumError::conversion_error(
+            from,
+            to,
+            &format!("{} has no mass per international unit", analyte.name),
+        )
+    })?;
+    let grams = checked_pow(grams_per_iu, power)
+        .and_then(|scale| value.checked_mul(scale))
+        .ok_or_else(|| UcumError::precision_overflow("analyte conversion", &value.to_string()))?;
+    let mut result = engine.convert_substance_expr(
+        grams,
+        &in_grams(engine, &source),
+        &in_grams(engine, &target),
+        &analyte.conversion_context(),
+    )?;
+    result.grams_per_iu = Some(grams_per_iu);
+    Ok(result)
+}
+
+/// Convert a value of a catalogued analyte between units of mass, amount of
+/// substance, equivalents and international units, with the default engine;
+/// see [`UcumEngine::convert_for_analyte`].
+///
+/// Conversions use the analyte's molar mass, charge and mass per `[IU]`,
+/// and the result records which of them were applied. Commensurable units
+/// convert as usual.
+///
+/// # Errors
+///
+/// Returns a conversion error for an unknown analyte or when the analyte
+/// lacks a factor the conversion needs, such as the mass of an `[IU]` of
+/// glucose.
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::{convert_for_analyte, precision::Number};
+///
+/// let glucose = convert_for_analyte(Number::new(9008, 2), "mg/dL", "mmol/L", "glucose").unwrap();
+/// assert_eq!(glucose.value, Number::from(5));
+/// assert!(glucose.is_substance_specific());
+/// ```
+#[allow(clippy::result_large_err)]
+pub fn convert_for_analyte(
+    value: Number,
+    from: &str,
+    to: &str,
+    analyte: &str,
+) -> Result<SubstanceConversion, UcumError> {
+    default_engine().convert_for_analyte(value, from, to, analyte)
+}
+
+#[allow(clippy::result_large_err)]
+pub(crate) fn convert_for_analyte_with(
+    engine: &UcumEngine,
+    value: Number,
+    from: &str,
+    to: &str,
+    analyte: &str,
+) -> Result<SubstanceConversion, UcumError> {
+    let analyte = find_analyte(analyte).ok_or_else(|| {
+        UcumError::conversion_error(from, to, &format!("Unknown analyte '{analyte}'"))
+    })?;
+    convert_analyte_with(engine, analyte, value, from, to)
+}
+
+/// `expr` with each, possibly prefixed, international unit replaced by the
+/// same prefix on grams.
+fn in_grams(engine: &UcumEngine, expr: &OwnedUnitExpr) -> OwnedUnitExpr {
+    match expr {
+        OwnedUnitExpr::Symbol(symbol) => match engine.resolve_symbol(symbol) {
+            Some((_, unit)) if INTERNATIONAL_UNITS.contains(&unit.code.as_ref()) => {
+                let prefix = &symbol[..symbol.len() - unit.code.len()];
+                OwnedUnitExpr::Symbol(format!("{prefix}g"))
+            }
+            _ => expr.clone(),
+        },
+        OwnedUnitExpr::Product(factors) => OwnedUnitExpr::Product(
+            factors
+                .iter()
+                .map(|factor| OwnedUnitFactor {
+                    expr: in_grams(engine, &factor.expr),
+                    exponent: factor.exponent,
+                })
+                .collect(),
+        ),
+        OwnedUnitExpr::Quotient(num, den) => OwnedUnitExpr::Quotient(
+            Box::new(in_grams(engine, num)),
+            Box::new(in_grams(engine, den)),
+        ),
+        OwnedUnitExpr::Power(inner, power) => {
+            OwnedUnitExpr::Power(Box::new(in_grams(engine, inner)), *power)
+        }
+        OwnedUnitExpr::Annotated(inner, annotation) => {
+            OwnedUnitExpr::Annotated(Box::new(in_grams(engine, inner)), annotation.clone())
+        }
+        OwnedUnitExpr::Numeric(_) | OwnedUnitExpr::Annotation(_) => expr.clone(),
+    }
+}
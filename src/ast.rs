@@ -5,6 +5,9 @@
 //! `nom` parsing machinery.
 
 use core::fmt;
+use core::ops::Range;
+
+use crate::error::{ErrorKind, Span, UcumError};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -24,6 +27,10 @@ pub enum UnitExpr<'a> {
     Symbol(&'a str),
     /// A symbol that requires ownership (normalization, etc).
     SymbolOwned(String),
+    /// A standalone annotation such as `{cells}`; evaluates to unity.
+    Annotation(&'a str),
+    /// An expression followed by an annotation, e.g. `mg{creat}`.
+    Annotated(Box<UnitExpr<'a>>, &'a str),
 }
 
 /// Owned version of UnitExpr for final results.
@@ -40,46 +47,28 @@ pub enum OwnedUnitExpr {
     Numeric(f64),
     /// A single symbol (possibly prefix+code) referring to a registry unit.
     Symbol(String),
+    /// A standalone annotation such as `{cells}` (text without braces); evaluates to unity.
+    Annotation(String),
+    /// An expression followed by an annotation, e.g. `mg{creat}`.
+    ///
+    /// Annotations carry no meaning in UCUM, but are kept so that the
+    /// original code can be reproduced and compared.
+    Annotated(Box<OwnedUnitExpr>, String),
 }
 
+/// Written as a UCUM code, see [`OwnedUnitExpr::to_ucum`].
 impl<'a> fmt::Display for UnitExpr<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            UnitExpr::Product(factors) => {
-                for (idx, fac) in factors.iter().enumerate() {
-                    if idx > 0 {
-                        write!(f, ".")?;
-                    }
-                    write!(f, "{fac}")?;
-                }
-                Ok(())
-            }
-            UnitExpr::Quotient(num, den) => write!(f, "{num}/{den}"),
-            UnitExpr::Power(expr, exp) => write!(f, "({expr})^{exp}"),
-            UnitExpr::Numeric(val) => write!(f, "{val}"),
-            UnitExpr::Symbol(sym) => write!(f, "{sym}"),
-            UnitExpr::SymbolOwned(sym) => write!(f, "{sym}"),
-        }
+        write!(f, "{}", self.clone().to_owned())
     }
 }
 
+/// Written as a UCUM code in the [`SerializeStyle::Preserving`] style.
+///
+/// [`SerializeStyle::Preserving`]: crate::SerializeStyle::Preserving
 impl fmt::Display for OwnedUnitExpr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            OwnedUnitExpr::Product(factors) => {
-                for (idx, fac) in factors.iter().enumerate() {
-                    if idx > 0 {
-                        write!(f, ".")?;
-                    }
-                    write!(f, "{fac}")?;
-                }
-                Ok(())
-            }
-            OwnedUnitExpr::Quotient(num, den) => write!(f, "{num}/{den}"),
-            OwnedUnitExpr::Power(expr, exp) => write!(f, "({expr})^{exp}"),
-            OwnedUnitExpr::Numeric(val) => write!(f, "{val}"),
-            OwnedUnitExpr::Symbol(sym) => write!(f, "{sym}"),
-        }
+        f.write_str(&self.to_ucum(crate::SerializeStyle::Preserving))
     }
 }
 
@@ -100,21 +89,13 @@ pub struct OwnedUnitFactor {
 
 impl<'a> fmt::Display for UnitFactor<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.exponent == 1 {
-            write!(f, "{}", self.expr)
-        } else {
-            write!(f, "{}^{}", self.expr, self.exponent)
-        }
+        write!(f, "{}", self.clone().to_owned())
     }
 }
 
 impl fmt::Display for OwnedUnitFactor {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.exponent == 1 {
-            write!(f, "{}", self.expr)
-        } else {
-            write!(f, "{}^{}", self.expr, self.exponent)
-        }
+        f.write_str(&self.to_ucum(crate::SerializeStyle::Preserving))
     }
 }
 
@@ -135,6 +116,11 @@ impl<'a> UnitExpr<'a> {
             UnitExpr::Numeric(val) => OwnedUnitExpr::Numeric(val),
             UnitExpr::Symbol(sym) => OwnedUnitExpr::Symbol(sym.to_string()),
             UnitExpr::SymbolOwned(sym) => OwnedUnitExpr::Symbol(sym),
+            UnitExpr::Annotation(text) => OwnedUnitExpr::Annotation(text.to_string()),
+            UnitExpr::Annotated(expr, text) => OwnedUnitExpr::Annotated(
+                Box::new(expr.as_ref().clone().to_owned()),
+                text.to_string(),
+            ),
         }
     }
 }
@@ -147,3 +133,204 @@ impl<'a> UnitFactor<'a> {
         }
     }
 }
+
+impl OwnedUnitExpr {
+    /// Return a copy of this expression with every annotation removed.
+    ///
+    /// Standalone annotations become the unity `1`, annotated terms lose their
+    /// annotation, so `{cells}/uL` becomes `1/uL` and `mg{creat}` becomes `mg`.
+    /// Unity factors left behind in a product are dropped: `{a}.mg` becomes `mg`.
+    pub fn strip_annotations(&self) -> OwnedUnitExpr {
+        match self {
+            OwnedUnitExpr::Product(factors) => {
+                let mut factors: Vec<OwnedUnitFactor> = factors
+                    .iter()
+                    .map(|f| OwnedUnitFactor {
+                        expr: f.expr.strip_annotations(),
+                        exponent: f.exponent,
+                    })
+                    .filter(|f| !matches!(f.expr, OwnedUnitExpr::Numeric(v) if v == 1.0))
+                    .collect();
+                match factors.len() {
+                    0 => OwnedUnitExpr::Numeric(1.0),
+                    1 => {
+                        let factor = factors.remove(0);
+                        match factor.exponent {
+                            1 => factor.expr,
+                            exponent => OwnedUnitExpr::Power(Box::new(factor.expr), exponent),
+                        }
+                    }
+                    _ => OwnedUnitExpr::Product(factors),
+                }
+            }
+            OwnedUnitExpr::Quotient(num, den) => OwnedUnitExpr::Quotient(
+                Box::new(num.strip_annotations()),
+                Box::new(den.strip_annotations()),
+            ),
+            OwnedUnitExpr::Power(expr, exp) => {
+                OwnedUnitExpr::Power(Box::new(expr.strip_annotations()), *exp)
+            }
+            OwnedUnitExpr::Numeric(v) => OwnedUnitExpr::Numeric(*v),
+            OwnedUnitExpr::Symbol(sym) => OwnedUnitExpr::Symbol(sym.clone()),
+            OwnedUnitExpr::Annotation(_) => OwnedUnitExpr::Numeric(1.0),
+            OwnedUnitExpr::Annotated(expr, _) => expr.strip_annotations(),
+        }
+    }
+
+    /// Check whether this expression contains any annotation.
+    pub fn has_annotations(&self) -> bool {
+        match self {
+            OwnedUnitExpr::Product(factors) => factors.iter().any(|f| f.expr.has_annotations()),
+            OwnedUnitExpr::Quotient(num, den) => num.has_annotations() || den.has_annotations(),
+            OwnedUnitExpr::Power(expr, _) => expr.has_annotations(),
+            OwnedUnitExpr::Numeric(_) | OwnedUnitExpr::Symbol(_) => false,
+            OwnedUnitExpr::Annotation(_) | OwnedUnitExpr::Annotated(_, _) => true,
+        }
+    }
+
+    /// Compare two expressions structurally, optionally ignoring annotations.
+    ///
+    /// `==` always considers annotations; UCUM says annotations carry no
+    /// meaning, so use [`AnnotationMode::Ignore`] to compare per the spec.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::{AnnotationMode, parse_expression};
+    ///
+    /// let cells = parse_expression("{cells}/uL").unwrap();
+    /// let plain = parse_expression("/uL").unwrap();
+    /// assert!(!cells.eq_with(&plain, AnnotationMode::Consider));
+    /// assert!(cells.eq_with(&plain, AnnotationMode::Ignore));
+    /// ```
+    pub fn eq_with(&self, other: &OwnedUnitExpr, mode: AnnotationMode) -> bool {
+        match mode {
+            AnnotationMode::Consider => self == other,
+            AnnotationMode::Ignore => self.strip_annotations() == other.strip_annotations(),
+        }
+    }
+}
+
+/// Byte ranges of an [`OwnedUnitExpr`] node and of its parts in the source
+/// it was parsed from.
+///
+/// The spans form a tree beside the expression rather than part of it, so
+/// that equal units compare equal wherever they were written. `children`
+/// follows the node: the factors of a product, the numerator and
+/// denominator of a quotient, and the inner expression of a power or an
+/// annotated term. A product factor is a node of its own, spanning the
+/// factor with its exponent, whose one child is the factor's expression.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeSpans {
+    /// The whole node, e.g. `mg2{x}`.
+    pub range: Range<usize>,
+    /// The prefix of a symbol, `m` in `mg`.
+    pub prefix: Option<Range<usize>>,
+    /// The exponent of a power or a factor, `-2` in `s-2` or `s^-2`.
+    pub exponent: Option<Range<usize>>,
+    /// The annotation of an annotation or an annotated term, with its braces.
+    pub annotation: Option<Range<usize>>,
+    /// Spans of the node's parts.
+    pub children: Vec<NodeSpans>,
+}
+
+impl NodeSpans {
+    /// A leaf spanning `range`.
+    pub fn new(range: Range<usize>) -> Self {
+        Self {
+            range,
+            ..Self::default()
+        }
+    }
+
+    /// Move every range `offset` bytes to the right.
+    pub(crate) fn shift(&mut self, offset: usize) {
+        let shift = |range: &mut Range<usize>| *range = range.start + offset..range.end + offset;
+        shift(&mut self.range);
+        self.prefix.iter_mut().for_each(shift);
+        self.exponent.iter_mut().for_each(shift);
+        self.annotation.iter_mut().for_each(shift);
+        for child in &mut self.children {
+            child.shift(offset);
+        }
+    }
+}
+
+/// A parsed expression with its source and the location of each node.
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::parse_expression_spanned;
+///
+/// let spanned = parse_expression_spanned("mg/dL").unwrap();
+/// let dl = &spanned.spans.children[1];
+/// assert_eq!(dl.range, 3..5);
+/// assert_eq!(dl.prefix, Some(3..4));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpannedExpr {
+    /// The parsed text.
+    pub source: String,
+    /// The expression.
+    pub expr: OwnedUnitExpr,
+    /// Spans of the expression's nodes.
+    pub spans: NodeSpans,
+}
+
+impl SpannedExpr {
+    /// A [`Span`] of `range` in the source.
+    pub fn span(&self, range: Range<usize>) -> Span {
+        Span::new(range.start, range.end, self.source.clone())
+    }
+
+    /// The range of the first occurrence of `symbol`, spelt as in the
+    /// expression (`ug` for a source `µg`).
+    pub fn symbol_range(&self, symbol: &str) -> Option<Range<usize>> {
+        fn find(expr: &OwnedUnitExpr, spans: &NodeSpans, symbol: &str) -> Option<Range<usize>> {
+            match expr {
+                OwnedUnitExpr::Symbol(s) if s == symbol => Some(spans.range.clone()),
+                OwnedUnitExpr::Product(factors) => {
+                    factors
+                        .iter()
+                        .zip(&spans.children)
+                        .find_map(|(factor, spans)| {
+                            find(&factor.expr, spans.children.first()?, symbol)
+                        })
+                }
+                OwnedUnitExpr::Quotient(num, den) => find(num, spans.children.first()?, symbol)
+                    .or_else(|| find(den, spans.children.get(1)?, symbol)),
+                OwnedUnitExpr::Power(inner, _) | OwnedUnitExpr::Annotated(inner, _) => {
+                    find(inner, spans.children.first()?, symbol)
+                }
+                _ => None,
+            }
+        }
+        find(&self.expr, &self.spans, symbol)
+    }
+
+    /// Attach the location of an evaluation error: the unknown symbol of a
+    /// [`ErrorKind::UnitNotFound`], otherwise the whole expression. Errors
+    /// that already have a span are returned as they are.
+    pub fn locate(&self, error: UcumError) -> UcumError {
+        if error.span.is_some() {
+            return error;
+        }
+        let range = match &error.kind {
+            ErrorKind::UnitNotFound { unit, .. } => self.symbol_range(unit),
+            _ => None,
+        };
+        let span = self.span(range.unwrap_or_else(|| self.spans.range.clone()));
+        error.with_span(span)
+    }
+}
+
+/// Whether annotations take part in a comparison of unit expressions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnnotationMode {
+    /// Annotations must match exactly (`mg{creat}` != `mg`).
+    Consider,
+    /// Annotations are ignored, as UCUM prescribes (`mg{creat}` == `mg`).
+    Ignore,
+}
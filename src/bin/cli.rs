@@ -5,8 +5,7 @@
 
 use clap::{Parser, Subcommand};
 use octofhir_ucum::{
-    analyse, evaluate_owned, get_canonical_units, is_comparable, parse_expression,
-    precision::to_f64, search_units, validate,
+    ErrorKind, analyse, default_engine, get_canonical_units, is_comparable, search_units, validate,
 };
 
 #[derive(Parser, Debug)]
@@ -33,7 +32,7 @@ pub enum Commands {
         /// UCUM expression to analyze
         expression: String,
     },
-    /// Get the canonical (base SI units) form of an expression.
+    /// Get the canonical (UCUM base units) form of an expression.
     Canonical {
         /// UCUM expression to convert to canonical form
         expression: String,
@@ -113,8 +112,12 @@ fn handle_canonical(expr: &str) -> anyhow::Result<()> {
         Ok(canonical) => {
             println!("📐 Canonical form of '{}':", expr);
             println!("   Unit: {}", canonical.unit);
-            println!("   Factor: {:.6}", canonical.factor);
-            if canonical.offset != 0.0 {
+            if canonical.is_nonlinear {
+                println!("   Factor: none (non-linear unit)");
+            } else {
+                println!("   Factor: {:.6}", canonical.factor);
+            }
+            if canonical.offset != 0.0 && !canonical.is_nonlinear {
                 println!("   Offset: {:.6}", canonical.offset);
             }
             println!("   Dimension: {:?}", canonical.dimension);
@@ -128,18 +131,13 @@ fn handle_canonical(expr: &str) -> anyhow::Result<()> {
 }
 
 fn handle_convert(value: f64, from: String, to: String) -> anyhow::Result<()> {
-    let from_expr = parse_expression(&from)?;
-    let to_expr = parse_expression(&to)?;
-
-    let from_res = evaluate_owned(&from_expr)?;
-    let to_res = evaluate_owned(&to_expr)?;
-
-    if from_res.dim != to_res.dim {
-        anyhow::bail!("Incompatible dimensions between '{}' and '{}'", from, to);
-    }
-
-    let canonical = value * to_f64(from_res.factor) + to_f64(from_res.offset);
-    let result = (canonical - to_f64(to_res.offset)) / to_f64(to_res.factor);
+    let result = match default_engine().convert(value, &from, &to) {
+        Ok(result) => result,
+        Err(e) if matches!(e.kind, ErrorKind::DimensionMismatch { .. }) => {
+            anyhow::bail!("Incompatible dimensions between '{}' and '{}'", from, to);
+        }
+        Err(e) => return Err(e.into()),
+    };
     println!("{} {} = {} {}", value, from, result, to);
     Ok(())
 }
@@ -0,0 +1,299 @@
+//! Case-insensitive (c/i) UCUM parsing.
+//!
+//! UCUM defines a second, case-insensitive code for every prefix and unit
+//! (the `CODE` attribute in `ucum-essence.xml`), intended for systems that can
+//! only transmit upper-case text, such as legacy HL7 v2 feeds. For example
+//! `MG/DL` is the c/i form of `mg/dL` and `PAL` is the c/i form of `Pa`.
+//!
+//! This module translates a c/i expression back to case-sensitive UCUM so it
+//! can be handled by the regular parser and evaluator. Each symbol is matched
+//! against the c/i atom codes first and then against prefix + metric atom
+//! combinations. When a symbol has several case-sensitive readings the exact
+//! atom (or, failing that, the reading that matches the input text verbatim)
+//! is chosen and the others are reported. Most such collisions are harmless
+//! spellings of the same unit, e.g. `L` and `l` for litre; those are flagged
+//! as `equivalent`.
+
+use crate::{
+    ast::OwnedUnitExpr,
+    engine::{UcumEngine, default_engine},
+    error::{Span, UcumError},
+    precision::to_f64,
+};
+
+/// A c/i symbol that could stand for more than one case-sensitive unit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaseInsensitiveAmbiguity {
+    /// The symbol as written in the input (e.g. `PAL`).
+    pub symbol: String,
+    /// Byte offset of the symbol within the input.
+    pub start: usize,
+    /// Byte offset just past the end of the symbol.
+    pub end: usize,
+    /// The case-sensitive code that was chosen.
+    pub chosen: String,
+    /// Other case-sensitive readings of the symbol.
+    pub alternatives: Vec<String>,
+    /// Whether every alternative denotes the same unit as `chosen`.
+    pub equivalent: bool,
+}
+
+/// Result of parsing a case-insensitive UCUM expression.
+#[derive(Clone, Debug)]
+pub struct CaseInsensitiveParse {
+    /// The expression rewritten with case-sensitive codes (e.g. `mg/dL`).
+    pub code: String,
+    /// Parsed AST of [`Self::code`].
+    pub expression: OwnedUnitExpr,
+    /// Symbols that had more than one possible reading.
+    pub ambiguities: Vec<CaseInsensitiveAmbiguity>,
+}
+
+impl CaseInsensitiveParse {
+    /// Whether any symbol in the input had readings with different meanings.
+    pub fn is_ambiguous(&self) -> bool {
+        self.ambiguities.iter().any(|a| !a.equivalent)
+    }
+}
+
+/// Parse a case-insensitive UCUM expression with the default engine; see
+/// [`UcumEngine::parse_ci`].
+///
+/// The input is translated to case-sensitive UCUM and then parsed. Operators,
+/// numbers and annotations are copied unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::parse_expression_ci;
+///
+/// let parsed = parse_expression_ci("MG/DL").unwrap();
+/// assert_eq!(parsed.code, "mg/dL");
+///
+/// let parsed = parse_expression_ci("[IU]/ML").unwrap();
+/// assert_eq!(parsed.code, "[IU]/mL");
+/// assert!(!parsed.is_ambiguous());
+/// ```
+#[allow(clippy::result_large_err)]
+pub fn parse_expression_ci(input: &str) -> Result<CaseInsensitiveParse, UcumError> {
+    default_engine().parse_ci(input)
+}
+
+/// Parse a case-insensitive expression against the engine's registry.
+#[allow(clippy::result_large_err)]
+pub(crate) fn parse_ci_with(
+    engine: &UcumEngine,
+    input: &str,
+) -> Result<CaseInsensitiveParse, UcumError> {
+    let (code, ambiguities) = to_case_sensitive(engine, input)?;
+    let expression = engine
+        .parse(&code)
+        .map_err(|e| e.with_context(format!("In case-insensitive expression: '{input}'")))?;
+    Ok(CaseInsensitiveParse {
+        code,
+        expression,
+        ambiguities,
+    })
+}
+
+/// Validate a case-insensitive UCUM expression with the default engine; see
+/// [`UcumEngine::validate_ci`].
+///
+/// Like [`parse_expression_ci`], but also evaluates the result so that
+/// structurally valid but meaningless expressions are rejected.
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::validate_ci;
+///
+/// assert_eq!(validate_ci("KG.M/S2").unwrap().code, "kg.m/s2");
+/// assert!(validate_ci("FOO").is_err());
+/// ```
+#[allow(clippy::result_large_err)]
+pub fn validate_ci(input: &str) -> Result<CaseInsensitiveParse, UcumError> {
+    default_engine().validate_ci(input)
+}
+
+/// Validate a case-insensitive expression against the engine's registry.
+#[allow(clippy::result_large_err)]
+pub(crate) fn validate_ci_with(
+    engine: &UcumEngine,
+    input: &str,
+) -> Result<CaseInsensitiveParse, UcumError> {
+    let parsed = parse_ci_with(engine, input)?;
+    engine
+        .evaluate_owned(&parsed.expression)
+        .map_err(|e| e.with_context(format!("In case-insensitive expression: '{input}'")))?;
+    Ok(parsed)
+}
+
+/// Rewrite a c/i expression with case-sensitive codes.
+#[allow(clippy::result_large_err)]
+fn to_case_sensitive(
+    engine: &UcumEngine,
+    input: &str,
+) -> Result<(String, Vec<CaseInsensitiveAmbiguity>), UcumError> {
+    let bytes = input.as_bytes();
+    let mut out = String::with_capacity(input.len());
+    let mut ambiguities = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let b = bytes[pos];
+        match b {
+            b'{' => {
+                // Annotations are copied verbatim
+                let end = input[pos..]
+                    .find('}')
+                    .map(|i| pos + i + 1)
+                    .unwrap_or(bytes.len());
+                out.push_str(&input[pos..end]);
+                pos = end;
+            }
+            b'.' | b'/' | b'(' | b')' | b' ' | b'\t' => {
+                out.push(b as char);
+                pos += 1;
+            }
+            b'0'..=b'9' => {
+                // Numeric factor, including `10*n` / `10^n`
+                let start = pos;
+                while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                    pos += 1;
+                }
+                if &input[start..pos] == "10" && matches!(bytes.get(pos), Some(b'*' | b'^')) {
+                    pos += 1;
+                    if matches!(bytes.get(pos), Some(b'+' | b'-')) {
+                        pos += 1;
+                    }
+                    while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                        pos += 1;
+                    }
+                }
+                out.push_str(&input[start..pos]);
+            }
+            _ => {
+                let start = pos;
+                let mut depth = 0usize;
+                while pos < bytes.len() {
+                    match bytes[pos] {
+                        b'[' => depth += 1,
+                        b']' => depth = depth.saturating_sub(1),
+                        b'.' | b'/' | b'(' | b')' | b'{' | b' ' | b'\t' if depth == 0 => break,
+                        _ => {}
+                    }
+                    pos += 1;
+                }
+                let run = &input[start..pos];
+                let symbol_len = symbol_length(run);
+                let symbol = &run[..symbol_len];
+                let end = start + symbol_len;
+
+                let (chosen, alternatives, equivalent) = resolve_symbol(engine, symbol)
+                    .ok_or_else(|| {
+                        UcumError::unit_not_found(symbol)
+                            .with_span(Span::new(start, end, input.to_string()))
+                            .with_context(format!("In case-insensitive expression: '{input}'"))
+                    })?;
+                if !alternatives.is_empty() {
+                    ambiguities.push(CaseInsensitiveAmbiguity {
+                        symbol: symbol.to_string(),
+                        start,
+                        end,
+                        chosen: chosen.clone(),
+                        alternatives,
+                        equivalent,
+                    });
+                }
+                out.push_str(&chosen);
+                out.push_str(&run[symbol_len..]);
+            }
+        }
+    }
+
+    Ok((out, ambiguities))
+}
+
+/// Length of the symbol part of a run, excluding a trailing exponent such as `2` or `-1`.
+fn symbol_length(run: &str) -> usize {
+    let digits_start = run.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    if digits_start == run.len() || digits_start == 0 {
+        return run.len();
+    }
+    let sign_start = run[..digits_start]
+        .strip_suffix(['+', '-'])
+        .map_or(digits_start, str::len);
+    if sign_start == 0 {
+        run.len()
+    } else {
+        sign_start
+    }
+}
+
+/// Map a c/i symbol to its case-sensitive code, the other readings, and
+/// whether those readings all mean the same unit.
+fn resolve_symbol(engine: &UcumEngine, symbol: &str) -> Option<(String, Vec<String>, bool)> {
+    let registry = engine.registry();
+    let upper = symbol.to_ascii_uppercase();
+
+    // Exact atoms come first so that they win over prefix splits.
+    let mut candidates: Vec<String> = registry
+        .find_units_ci(&upper)
+        .iter()
+        .map(|u| u.code.to_string())
+        .collect();
+    let atom_count = candidates.len();
+
+    for prefix in registry.prefixes() {
+        if let Some(rest) = upper.strip_prefix(prefix.ci_symbol.as_ref()) {
+            for unit in registry.find_units_ci(rest).iter().filter(|u| u.is_metric) {
+                candidates.push(format!("{}{}", prefix.symbol, unit.code));
+            }
+        }
+    }
+
+    if candidates.is_empty() {
+        return None;
+    }
+
+    let preferred = if atom_count > 0 {
+        &candidates[..atom_count]
+    } else {
+        &candidates[..]
+    };
+    let chosen = preferred
+        .iter()
+        .find(|c| c.as_str() == symbol)
+        .unwrap_or(&preferred[0])
+        .clone();
+
+    let mut alternatives: Vec<String> = Vec::new();
+    for candidate in &candidates {
+        if *candidate != chosen && !alternatives.contains(candidate) {
+            alternatives.push(candidate.clone());
+        }
+    }
+    let all_equivalent = alternatives
+        .iter()
+        .all(|alt| equivalent(engine, &chosen, alt));
+    Some((chosen, alternatives, all_equivalent))
+}
+
+/// Whether two case-sensitive codes denote the same unit (e.g. `L` and `l`).
+fn equivalent(engine: &UcumEngine, a: &str, b: &str) -> bool {
+    let eval = |code: &str| {
+        engine
+            .parse(code)
+            .and_then(|e| engine.evaluate_owned(&e))
+            .ok()
+    };
+    match (eval(a), eval(b)) {
+        (Some(a), Some(b)) => {
+            let (fa, fb) = (to_f64(a.factor), to_f64(b.factor));
+            a.dim == b.dim
+                && a.offset == b.offset
+                && ((fa - fb).abs() <= 1e-12 * fa.abs().max(fb.abs()))
+        }
+        _ => false,
+    }
+}
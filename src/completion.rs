@@ -0,0 +1,482 @@
+//! Completion of partially typed UCUM expressions.
+//!
+//! [`complete`] looks at the symbol or annotation under the cursor and
+//! offers ranked candidates to replace it with: unit atoms, prefixed atoms,
+//! prefixes and common annotations. Candidates that start with the typed
+//! text rank first, closer matches ahead of longer ones; when no atom starts
+//! with it, similar atoms are offered as corrections. [`complete_as`] also
+//! takes the unit the expression must be commensurable with and, once the
+//! rest of the expression pins down the dimension of the symbol under the
+//! cursor, keeps only the candidates that fit.
+
+use core::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::{UcumEngine, default_engine},
+    error::UcumError,
+    performance::find_prefixes_with_trie,
+    suggestions::SuggestionEngine,
+    tokens::{Token, TokenKind},
+    types::{Dimension, UnitRecord},
+};
+
+/// What a [`Completion`] inserts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CompletionKind {
+    /// A prefix to be followed by an atom, `m` (milli).
+    Prefix,
+    /// A unit atom, possibly prefixed, `mg`.
+    Atom,
+    /// A unit atom written in square brackets, possibly prefixed, `[IU]`.
+    BracketedAtom,
+    /// An annotation with its braces, `{cells}`.
+    Annotation,
+}
+
+/// A candidate for the text under the cursor.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Completion {
+    /// What the candidate is.
+    pub kind: CompletionKind,
+    /// The text to insert.
+    pub text: String,
+    /// Human-readable name, e.g. `milligram`.
+    pub display: String,
+    /// Byte range of the expression that the text replaces.
+    pub range: Range<usize>,
+    /// Ranking score between 0 and 1, higher is better.
+    pub score: f64,
+}
+
+/// Annotations in common use, with what they count.
+const ANNOTATIONS: &[(&str, &str)] = &[
+    ("{cells}", "cells"),
+    ("{copies}", "copies"),
+    ("{beats}", "heart beats"),
+    ("{breaths}", "breaths"),
+    ("{titer}", "titer"),
+    ("{score}", "score"),
+    ("{count}", "count"),
+    ("{creat}", "per creatinine"),
+    ("{RBCs}", "red blood cells"),
+    ("{WBCs}", "white blood cells"),
+    ("{spermatozoa}", "spermatozoa"),
+    ("{tot}", "total"),
+    ("{ratio}", "ratio"),
+    ("{index}", "index"),
+];
+
+/// Candidates for the symbol or annotation at byte offset `cursor` of a
+/// partially typed expression, best first, using the default engine; see
+/// [`UcumEngine::complete`].
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::{CompletionKind, complete};
+///
+/// let candidates = complete("mg/d", 4);
+/// assert_eq!(candidates[0].text, "d");
+/// assert!(candidates.iter().any(|c| c.text == "dL" && c.kind == CompletionKind::Atom));
+/// assert_eq!(candidates[0].range, 3..4);
+/// ```
+pub fn complete(expression: &str, cursor: usize) -> Vec<Completion> {
+    default_engine().complete(expression, cursor)
+}
+
+/// Candidates that keep `expression` commensurable with `unit`, using the
+/// default engine; see [`UcumEngine::complete_as`].
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::{CompletionKind, complete_as};
+///
+/// // Only a volume completes a mass concentration: deciliter, not day
+/// let candidates = complete_as("mg/d", 4, "g/L").unwrap();
+/// assert!(candidates.iter().any(|c| c.text == "dL"));
+/// assert!(!candidates.iter().any(|c| c.text == "d" && c.kind == CompletionKind::Atom));
+/// ```
+#[allow(clippy::result_large_err)]
+pub fn complete_as(
+    expression: &str,
+    cursor: usize,
+    unit: &str,
+) -> Result<Vec<Completion>, UcumError> {
+    default_engine().complete_as(expression, cursor, unit)
+}
+
+/// Complete at `cursor`, keeping candidates of the `expected` dimension.
+pub(crate) fn complete_with(
+    engine: &UcumEngine,
+    expression: &str,
+    cursor: usize,
+    expected: Option<Dimension>,
+) -> Vec<Completion> {
+    let mut cursor = cursor.min(expression.len());
+    while !expression.is_char_boundary(cursor) {
+        cursor -= 1;
+    }
+    let tokens = engine.tokenize(expression);
+    let Some(target) = Target::find(expression, &tokens, cursor) else {
+        return Vec::new();
+    };
+    let fit = expected.and_then(|expected| Fit::new(engine, expression, &target, expected));
+
+    let mut candidates = Candidates {
+        engine,
+        typed: &target.typed,
+        range: target.range.clone(),
+        fit,
+        list: Vec::new(),
+    };
+    match target.context {
+        Context::Symbol => {
+            candidates.atoms();
+            candidates.prefixed_atoms();
+            candidates.prefixes();
+            if candidates
+                .list
+                .iter()
+                .all(|c| c.kind == CompletionKind::Prefix)
+            {
+                candidates.corrections();
+            }
+            if target.typed.is_empty() {
+                candidates.annotations("");
+            }
+        }
+        Context::Annotation => candidates.annotations(&target.typed),
+    }
+
+    let mut list = candidates.list;
+    list.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a.text.len().cmp(&b.text.len()))
+            .then_with(|| a.text.cmp(&b.text))
+    });
+    list
+}
+
+enum Context {
+    /// A unit symbol, possibly not typed yet.
+    Symbol,
+    /// An annotation after its opening brace.
+    Annotation,
+}
+
+/// The text under the cursor.
+struct Target {
+    context: Context,
+    /// Byte range that a candidate replaces.
+    range: Range<usize>,
+    /// What was typed before the cursor, without an annotation's brace.
+    typed: String,
+}
+
+impl Target {
+    fn find(input: &str, tokens: &[Token], cursor: usize) -> Option<Self> {
+        let symbol = |token: &Token| match token.kind {
+            TokenKind::Prefix | TokenKind::Atom | TokenKind::BracketedAtom => true,
+            TokenKind::Error => token
+                .text
+                .starts_with(|c: char| c.is_ascii_alphabetic() || "['%µ".contains(c)),
+            _ => false,
+        };
+        let annotation = |token: &Token| {
+            token.kind == TokenKind::Annotation
+                || (token.kind == TokenKind::Error && token.text.starts_with('{'))
+        };
+
+        let under = tokens.iter().position(|t| {
+            (symbol(t) || annotation(t)) && t.range.start < cursor && cursor <= t.range.end
+        });
+        let Some(mut index) = under else {
+            if tokens
+                .iter()
+                .any(|t| t.range.start < cursor && cursor < t.range.end)
+            {
+                return None;
+            }
+            // Nothing typed yet: a symbol may start here unless it would
+            // run into the term before the cursor
+            let before = tokens.iter().rev().find(|t| t.range.end <= cursor);
+            return match before.map(|t| t.kind) {
+                None | Some(TokenKind::Operator | TokenKind::OpenParen) => Some(Self {
+                    context: Context::Symbol,
+                    range: cursor..cursor,
+                    typed: String::new(),
+                }),
+                _ => Some(Self {
+                    context: Context::Annotation,
+                    range: cursor..cursor,
+                    typed: String::new(),
+                }),
+            };
+        };
+
+        let token = &tokens[index];
+        if annotation(token) {
+            // An unterminated annotation runs to the end; replace up to the cursor
+            let end = if token.text.ends_with('}') && cursor < token.range.end {
+                token.range.end
+            } else {
+                cursor
+            };
+            return Some(Self {
+                context: Context::Annotation,
+                range: token.range.start..end,
+                typed: input[token.range.start + 1..cursor]
+                    .trim_end_matches('}')
+                    .to_string(),
+            });
+        }
+        // A prefix and its atom are one symbol
+        let mut end = token.range.end;
+        if token.kind == TokenKind::Prefix
+            && let Some(next) = tokens.get(index + 1)
+            && next.range.start == end
+        {
+            end = next.range.end;
+        }
+        while index > 0
+            && tokens[index - 1].kind == TokenKind::Prefix
+            && tokens[index - 1].range.end == tokens[index].range.start
+        {
+            index -= 1;
+        }
+        let start = tokens[index].range.start;
+        // An unterminated bracket runs to the end; replace up to the cursor
+        if token.kind == TokenKind::Error && token.text.contains('[') && !token.text.contains(']') {
+            end = cursor;
+        }
+        Some(Self {
+            context: Context::Symbol,
+            range: start..end,
+            typed: input[start..cursor].replace('µ', "u"),
+        })
+    }
+}
+
+/// The dimension a candidate must have to give the expected dimension.
+struct Fit {
+    /// Dimension of the expression without the candidate.
+    rest: Dimension,
+    /// Power the candidate is raised to, negative in a denominator.
+    power: i32,
+    expected: Dimension,
+}
+
+impl Fit {
+    /// `None` unless the rest of the expression is typed and valid.
+    fn new(engine: &UcumEngine, input: &str, target: &Target, expected: Dimension) -> Option<Self> {
+        let (before, after) = (&input[..target.range.start], &input[target.range.end..]);
+        if !matches!(target.context, Context::Symbol)
+            || (before.trim().is_empty() && after.trim().is_empty())
+        {
+            return None;
+        }
+        let dimension = |replacement: &str| {
+            let expr = engine
+                .parse(&format!("{before}{replacement}{after}"))
+                .ok()?;
+            engine.evaluate_owned(&expr).ok().map(|result| result.dim)
+        };
+
+        // Substitute a unit with a single base dimension to find the power
+        let rest = dimension("1")?;
+        let meter = engine.registry().dimension_of(engine.find_unit("m")?);
+        let axis = meter.0.iter().position(|&d| d != 0)?;
+        let power = i32::from(dimension("m")?.0[axis] - rest.0[axis]) / i32::from(meter.0[axis]);
+        Some(Self {
+            rest,
+            power,
+            expected,
+        })
+    }
+
+    fn accepts(&self, dimension: Dimension) -> bool {
+        (0..7).all(|i| {
+            i32::from(self.rest.0[i]) + self.power * i32::from(dimension.0[i])
+                == i32::from(self.expected.0[i])
+        })
+    }
+}
+
+struct Candidates<'e, 't> {
+    engine: &'e UcumEngine,
+    typed: &'t str,
+    range: Range<usize>,
+    fit: Option<Fit>,
+    list: Vec<Completion>,
+}
+
+impl Candidates<'_, '_> {
+    fn fits(&self, unit: &UnitRecord) -> bool {
+        self.fit
+            .as_ref()
+            .is_none_or(|fit| fit.accepts(self.engine.registry().dimension_of(unit)))
+    }
+
+    fn push(&mut self, kind: CompletionKind, text: String, display: String, score: f64) {
+        let duplicate = self.list.iter().any(|c| {
+            c.text == text && (c.kind == CompletionKind::Prefix) == (kind == CompletionKind::Prefix)
+        });
+        if !duplicate {
+            self.list.push(Completion {
+                kind,
+                text,
+                display,
+                range: self.range.clone(),
+                score,
+            });
+        }
+    }
+
+    /// Unprefixed atoms starting with the typed text, then those starting
+    /// with it ignoring case.
+    fn atoms(&mut self) {
+        let typed = self.typed;
+        let lower = typed.to_lowercase();
+        for unit in self.engine.units() {
+            if !self.fits(unit) {
+                continue;
+            }
+            let score = if unit.code.starts_with(typed) {
+                0.5 + 0.5 * SuggestionEngine::string_similarity(typed, &unit.code)
+            } else if unit.code.to_lowercase().starts_with(&lower) {
+                0.25 + 0.25 * SuggestionEngine::string_similarity(&lower, &unit.code.to_lowercase())
+            } else {
+                continue;
+            };
+            self.push(
+                atom_kind(unit),
+                unit.code.to_string(),
+                unit.display_name.to_string(),
+                score,
+            );
+        }
+    }
+
+    /// Atoms, unprefixed or after a prefix the typed text starts with, that
+    /// are similar to the typed text; for when no atom starts with it.
+    fn corrections(&mut self) {
+        let typed = self.typed;
+        if typed.len() < 2 {
+            return;
+        }
+        let prefixes: Vec<_> = find_prefixes_with_trie(typed)
+            .into_iter()
+            .filter(|prefix| self.engine.find_prefix(&prefix.symbol).is_some())
+            .map(Some)
+            .chain([None])
+            .collect();
+        for unit in self.engine.units() {
+            if !self.fits(unit) {
+                continue;
+            }
+            for prefix in &prefixes {
+                let (symbol, name) = match prefix {
+                    Some(prefix) if unit.is_metric => {
+                        (prefix.symbol.as_ref(), prefix.display_name.as_ref())
+                    }
+                    Some(_) => continue,
+                    None => ("", ""),
+                };
+                let text = format!("{symbol}{}", unit.code);
+                let similarity = SuggestionEngine::string_similarity(typed, &text);
+                if similarity > 0.5 {
+                    let display = format!("{name}{}", unit.display_name);
+                    self.push(atom_kind(unit), text, display, 0.5 * similarity);
+                }
+            }
+        }
+    }
+
+    /// Metric atoms after each prefix the typed text starts with.
+    fn prefixed_atoms(&mut self) {
+        let typed = self.typed;
+        for prefix in find_prefixes_with_trie(typed) {
+            if self.engine.find_prefix(&prefix.symbol).is_none() {
+                continue;
+            }
+            let rest = &typed[prefix.symbol.len()..];
+            for unit in self.engine.units() {
+                if !unit.is_metric || !unit.code.starts_with(rest) || !self.fits(unit) {
+                    continue;
+                }
+                let text = format!("{}{}", prefix.symbol, unit.code);
+                // A prefixed atom ranks just below an atom of the same length
+                let score = 0.9 * (0.5 + 0.5 * SuggestionEngine::string_similarity(typed, &text));
+                let display = format!("{}{}", prefix.display_name, unit.display_name);
+                self.push(atom_kind(unit), text, display, score);
+            }
+        }
+    }
+
+    /// Prefixes starting with the typed text that some atom can follow.
+    fn prefixes(&mut self) {
+        let typed = self.typed;
+        let fitting = self
+            .engine
+            .units()
+            .iter()
+            .any(|unit| unit.is_metric && self.fits(unit));
+        if !fitting {
+            return;
+        }
+        for prefix in self.engine.prefixes() {
+            if prefix.symbol.starts_with(typed) {
+                let score =
+                    0.8 * (0.5 + 0.5 * SuggestionEngine::string_similarity(typed, &prefix.symbol));
+                self.push(
+                    CompletionKind::Prefix,
+                    prefix.symbol.to_string(),
+                    prefix.display_name.to_string(),
+                    score,
+                );
+            }
+        }
+    }
+
+    /// Common annotations starting with the typed text, ignoring case.
+    fn annotations(&mut self, typed: &str) {
+        // Annotations are dimensionless
+        if self
+            .fit
+            .as_ref()
+            .is_some_and(|fit| !fit.accepts(Dimension::zero()))
+        {
+            return;
+        }
+        let lower = typed.to_lowercase();
+        for (text, display) in ANNOTATIONS {
+            let name = &text[1..text.len() - 1];
+            if name.to_lowercase().starts_with(&lower) {
+                let score = 0.8
+                    * (0.5
+                        + 0.5 * SuggestionEngine::string_similarity(&lower, &name.to_lowercase()));
+                self.push(
+                    CompletionKind::Annotation,
+                    text.to_string(),
+                    display.to_string(),
+                    score,
+                );
+            }
+        }
+    }
+}
+
+fn atom_kind(unit: &UnitRecord) -> CompletionKind {
+    if unit.code.starts_with('[') {
+        CompletionKind::BracketedAtom
+    } else {
+        CompletionKind::Atom
+    }
+}
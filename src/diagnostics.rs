@@ -0,0 +1,414 @@
+//! Error-recovering parsing that reports every problem in an expression.
+//!
+//! [`parse_lenient`] splits an expression at its top-level `.` and `/`
+//! operators and parses each term on its own, so that a malformed term does
+//! not hide the problems after it. Characters that cannot start a token are
+//! reported and skipped, unknown atoms are reported where they occur, and
+//! the terms that parse are joined back into a partial expression.
+
+use core::ops::Range;
+
+use crate::{
+    ast::{NodeSpans, OwnedUnitExpr, OwnedUnitFactor, SpannedExpr},
+    engine::{UcumEngine, default_engine},
+    error::{ErrorKind, Span, UcumError},
+    parser,
+    tokens::{Token, TokenKind},
+};
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Severity {
+    /// The expression is not valid UCUM.
+    Error,
+    /// The expression is accepted, but is not written as UCUM prescribes.
+    Warning,
+}
+
+/// A problem found by [`parse_lenient`].
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// Where the problem is.
+    pub span: Span,
+    /// The problem, with its message and suggestions; its `span` is set as
+    /// well.
+    pub error: UcumError,
+}
+
+/// The result of [`parse_lenient`].
+#[derive(Clone, Debug)]
+pub struct LenientParse {
+    /// The expression built from the terms that parsed, if any did.
+    pub expr: Option<SpannedExpr>,
+    /// Every problem found, in the order of the source.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl LenientParse {
+    /// Whether no diagnostic is an error.
+    pub fn is_valid(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.severity != Severity::Error)
+    }
+}
+
+/// Parse a UCUM expression, recovering from errors, with the default
+/// engine; see [`UcumEngine::parse_lenient`].
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::parse_lenient;
+///
+/// let parsed = parse_lenient("mg/dll.sec2/");
+/// let problems: Vec<_> = parsed.diagnostics.iter().map(|d| d.span.text()).collect();
+/// assert_eq!(problems, ["dll", "sec", "/"]);
+/// // The terms that parsed remain
+/// assert_eq!(parsed.expr.unwrap().expr.to_string(), "mg/(dll.sec2)");
+/// ```
+pub fn parse_lenient(input: &str) -> LenientParse {
+    default_engine().parse_lenient(input)
+}
+
+/// Parse `input` leniently against the engine's registry.
+pub(crate) fn parse_lenient_with(engine: &UcumEngine, input: &str) -> LenientParse {
+    let mut parse = Lenient {
+        engine,
+        input,
+        diagnostics: Vec::new(),
+    };
+    let expr = parse.run();
+    let mut diagnostics = parse.diagnostics;
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.start);
+    LenientParse { expr, diagnostics }
+}
+
+struct Lenient<'e, 'a> {
+    engine: &'e UcumEngine,
+    input: &'a str,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// A group of factors joined by `.`, divided into the result so far when it
+/// follows a `/` (whose position it keeps).
+type Group = (Option<usize>, Vec<(OwnedUnitFactor, NodeSpans)>);
+
+impl Lenient<'_, '_> {
+    fn run(&mut self) -> Option<SpannedExpr> {
+        let tokens = self.engine.tokenize(self.input);
+        self.whitespace(&tokens);
+        if self.input.trim().is_empty() {
+            return parser::parse_expression_spanned(self.input).ok();
+        }
+
+        // The text the parser sees, with malformed tokens blanked out
+        let mut masked = self.input.as_bytes().to_vec();
+        let mut separators = Vec::new();
+        let mut depth = 0usize;
+        for token in &tokens {
+            let reason = match token.kind {
+                TokenKind::Error => malformed(token.text),
+                TokenKind::OpenParen => {
+                    depth += 1;
+                    None
+                }
+                TokenKind::CloseParen if depth == 0 => Some("Unmatched ')'".to_string()),
+                TokenKind::CloseParen => {
+                    depth -= 1;
+                    None
+                }
+                TokenKind::Operator if depth == 0 && token.text != "^" => {
+                    separators.push(token);
+                    None
+                }
+                _ => None,
+            };
+            if let Some(reason) = reason {
+                self.error(UcumError::invalid_expression(&reason), token.range.clone());
+                masked[token.range.clone()].fill(b' ');
+            }
+        }
+        // Only whole tokens were blanked, so character boundaries are intact
+        let masked = String::from_utf8(masked).unwrap_or_default();
+
+        let mut groups: Vec<Group> = Vec::new();
+        let mut start = 0;
+        for index in 0..=separators.len() {
+            let before = index.checked_sub(1).map(|i| separators[i]);
+            let end = separators
+                .get(index)
+                .map_or(self.input.len(), |t| t.range.start);
+            match before {
+                Some(slash) if slash.text == "/" => groups.push((Some(slash.range.start), vec![])),
+                _ if groups.is_empty() => groups.push((None, vec![])),
+                _ => {}
+            }
+            let after = separators.get(index).copied();
+            if let Some(factor) = self.term(&masked, start..end, before, after)
+                && let Some((_, factors)) = groups.last_mut()
+            {
+                factors.push(factor);
+            }
+            start = separators.get(index).map_or(end, |t| t.range.end);
+        }
+
+        let (expr, spans) = join(groups)?;
+        let mut spanned = SpannedExpr {
+            source: self.input.to_string(),
+            expr,
+            spans,
+        };
+        self.engine
+            .resolve_prefixes(&spanned.source, &spanned.expr, &mut spanned.spans);
+        if self
+            .diagnostics
+            .iter()
+            .all(|d| d.severity != Severity::Error)
+            && let Err(error) = self.engine.evaluate_spanned(&spanned)
+        {
+            let range = error
+                .span
+                .as_ref()
+                .map_or(spanned.spans.range.clone(), |span| span.start..span.end);
+            self.error(error, range);
+        }
+        Some(spanned)
+    }
+
+    /// Warn about whitespace, which the parser skips but UCUM does not allow.
+    fn whitespace(&mut self, tokens: &[Token]) {
+        let mut end = 0;
+        let gaps: Vec<Range<usize>> = tokens
+            .iter()
+            .map(|token| token.range.clone())
+            .chain(std::iter::once(self.input.len()..self.input.len()))
+            .filter_map(|range| {
+                let gap = end..range.start;
+                end = range.end;
+                (!gap.is_empty()).then_some(gap)
+            })
+            .collect();
+        for gap in gaps {
+            self.push(
+                Severity::Warning,
+                UcumError::invalid_expression("UCUM codes contain no whitespace"),
+                gap,
+            );
+        }
+    }
+
+    /// Parse the term in `range` of the masked input, reporting its
+    /// problems; `before` and `after` are the operators around it.
+    fn term(
+        &mut self,
+        masked: &str,
+        range: Range<usize>,
+        before: Option<&Token>,
+        after: Option<&Token>,
+    ) -> Option<(OwnedUnitFactor, NodeSpans)> {
+        let text = &masked[range.clone()];
+        if text.trim().is_empty() {
+            // Blanked text was reported already; a leading `/` needs no term
+            let missing = self.input[range].trim().is_empty()
+                && !(before.is_none() && after.is_some_and(|t| t.text == "/"));
+            match (before, after) {
+                (Some(op), _) if missing => self.error(
+                    UcumError::invalid_expression(&format!("Missing term after '{}'", op.text)),
+                    op.range.clone(),
+                ),
+                (None, Some(op)) if missing => self.error(
+                    UcumError::invalid_expression(&format!("Missing term before '{}'", op.text)),
+                    op.range.clone(),
+                ),
+                _ => {}
+            }
+            return None;
+        }
+
+        let start = range.start + (text.len() - text.trim_start().len());
+        let term = start..range.end - (text.len() - text.trim_end().len());
+        let parsed = match parser::parse_expression_spanned(&masked[term.clone()]) {
+            Ok(parsed) => parsed,
+            Err(error) => {
+                let error = match error.kind {
+                    ErrorKind::InvalidPercentPlacement { position } => {
+                        UcumError::invalid_percent_placement(position + start)
+                    }
+                    _ => error,
+                };
+                let suggestions = self.suggestions(&self.input[term.clone()]);
+                self.error(error.with_suggestions(suggestions), term);
+                return None;
+            }
+        };
+        let (expr, mut spans) = (parsed.expr, parsed.spans);
+        spans.shift(start);
+        self.unknown_symbols(&expr, &spans);
+
+        Some(match expr {
+            OwnedUnitExpr::Power(inner, exponent) => (
+                OwnedUnitFactor {
+                    expr: *inner,
+                    exponent,
+                },
+                spans,
+            ),
+            expr => (
+                OwnedUnitFactor { expr, exponent: 1 },
+                NodeSpans {
+                    children: vec![spans],
+                    ..NodeSpans::new(term)
+                },
+            ),
+        })
+    }
+
+    /// Report each symbol of `expr` that is not a unit.
+    fn unknown_symbols(&mut self, expr: &OwnedUnitExpr, spans: &NodeSpans) {
+        match expr {
+            OwnedUnitExpr::Symbol(symbol) => {
+                if let Err(error) = self.engine.evaluate_owned(expr)
+                    && matches!(error.kind, ErrorKind::UnitNotFound { .. })
+                {
+                    let suggestions = self.suggestions(symbol);
+                    self.error(error.with_suggestions(suggestions), spans.range.clone());
+                }
+            }
+            OwnedUnitExpr::Product(factors) => {
+                for (factor, spans) in factors.iter().zip(&spans.children) {
+                    if let Some(spans) = spans.children.first() {
+                        self.unknown_symbols(&factor.expr, spans);
+                    }
+                }
+            }
+            OwnedUnitExpr::Quotient(num, den) => {
+                for (expr, spans) in [num, den].into_iter().zip(&spans.children) {
+                    self.unknown_symbols(expr, spans);
+                }
+            }
+            OwnedUnitExpr::Power(inner, _) | OwnedUnitExpr::Annotated(inner, _) => {
+                if let Some(spans) = spans.children.first() {
+                    self.unknown_symbols(inner, spans);
+                }
+            }
+            OwnedUnitExpr::Numeric(_) | OwnedUnitExpr::Annotation(_) => {}
+        }
+    }
+
+    fn suggestions(&self, text: &str) -> Vec<String> {
+        if self.engine.config().enable_suggestions {
+            self.engine.suggestion_engine().suggest_corrections(text)
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn error(&mut self, error: UcumError, range: Range<usize>) {
+        self.push(Severity::Error, error, range);
+    }
+
+    fn push(&mut self, severity: Severity, error: UcumError, range: Range<usize>) {
+        let span = Span::new(range.start, range.end, self.input.to_string());
+        self.diagnostics.push(Diagnostic {
+            severity,
+            error: error.with_span(span.clone()),
+            span,
+        });
+    }
+}
+
+/// Why an error token cannot be parsed, or `None` for a well-formed symbol
+/// that is merely unknown.
+fn malformed(text: &str) -> Option<String> {
+    Some(if text.starts_with('{') {
+        "Unterminated annotation".to_string()
+    } else if text.starts_with('[') && !text.contains(']') {
+        "Unterminated square bracket".to_string()
+    } else if text == "^" || text.starts_with("10") {
+        format!("Missing exponent after '{text}'")
+    } else if text.starts_with(|c: char| c.is_ascii_alphabetic() || "[%'µ".contains(c)) {
+        return None;
+    } else {
+        format!("Unexpected '{text}'")
+    })
+}
+
+/// Join the groups of factors into a single expression, dropping empty
+/// groups along with their division.
+fn join(groups: Vec<Group>) -> Option<(OwnedUnitExpr, NodeSpans)> {
+    let mut result: Option<(OwnedUnitExpr, NodeSpans)> = None;
+    for (slash, factors) in groups {
+        let Some((group, group_spans)) = product(factors) else {
+            continue;
+        };
+        let (num, num_spans) = match (result, slash) {
+            (Some(result), Some(_)) => result,
+            // A leading slash divides one, with an empty range at the slash
+            (None, Some(slash)) => (OwnedUnitExpr::Numeric(1.0), NodeSpans::new(slash..slash)),
+            (_, None) => {
+                result = Some((group, group_spans));
+                continue;
+            }
+        };
+        let range = num_spans.range.start..group_spans.range.end;
+        result = Some((
+            OwnedUnitExpr::Quotient(Box::new(num), Box::new(group)),
+            NodeSpans {
+                children: vec![num_spans, group_spans],
+                ..NodeSpans::new(range)
+            },
+        ));
+    }
+    result
+}
+
+/// The product of `factors`, shaped as the parser shapes it.
+fn product(mut factors: Vec<(OwnedUnitFactor, NodeSpans)>) -> Option<(OwnedUnitExpr, NodeSpans)> {
+    match factors.len() {
+        0 => None,
+        1 => {
+            let (factor, mut spans) = factors.remove(0);
+            Some(if factor.exponent == 1 {
+                (factor.expr, spans.children.remove(0))
+            } else {
+                (
+                    OwnedUnitExpr::Power(Box::new(factor.expr), factor.exponent),
+                    spans,
+                )
+            })
+        }
+        _ => {
+            // A term that is itself a product, such as `4[pi]`, is spliced in
+            let mut flat = Vec::with_capacity(factors.len());
+            for (factor, mut spans) in factors {
+                match factor.expr {
+                    OwnedUnitExpr::Product(inner)
+                        if factor.exponent == 1 && spans.children[0].range == spans.range =>
+                    {
+                        let children = spans.children.remove(0).children;
+                        flat.extend(inner.into_iter().zip(children));
+                    }
+                    expr => flat.push((
+                        OwnedUnitFactor {
+                            expr,
+                            exponent: factor.exponent,
+                        },
+                        spans,
+                    )),
+                }
+            }
+            let range = flat[0].1.range.start..flat[flat.len() - 1].1.range.end;
+            let (factors, children) = flat.into_iter().unzip();
+            Some((
+                OwnedUnitExpr::Product(factors),
+                NodeSpans {
+                    children,
+                    ..NodeSpans::new(range)
+                },
+            ))
+        }
+    }
+}
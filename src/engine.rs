@@ -0,0 +1,1383 @@
+//! Instance-based UCUM engine.
+//!
+//! A [`UcumEngine`] owns everything needed to interpret UCUM codes: a
+//! [`UnitRegistry`], an [`EvaluationCache`], a [`SpecialUnitRegistry`] and an
+//! [`EngineConfig`]. Engines share no state, so differently configured engines
+//! (for example one per tenant) can run side by side.
+//!
+//! The crate's free functions (`validate`, `analyse`, `search_units`, ...) are
+//! thin wrappers around a shared engine returned by [`default_engine`].
+
+use core::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+use once_cell::sync::{Lazy, OnceCell};
+use regex::Regex;
+
+use crate::{
+    CanonicalUnit, ConceptKind, UnitAnalysis,
+    analyte::{Analyte, convert_analyte_with, convert_for_analyte_with},
+    ast::{NodeSpans, OwnedUnitExpr, SpannedExpr, UnitExpr},
+    build_canonical_unit_string,
+    case_insensitive::{CaseInsensitiveParse, parse_ci_with, validate_ci_with},
+    completion::{Completion, complete_with},
+    diagnostics::{LenientParse, parse_lenient_with},
+    error::{ErrorKind, UcumError},
+    evaluator::{EvalResult, evaluate_owned_with, evaluate_with, owned_to_borrowed},
+    parser,
+    performance::{CacheStats, EvaluationCache},
+    precision::{Number, NumericOps, checked_pow, to_f64, try_from_f64_shortest},
+    quantity::{
+        add_with, compare_with, convert_to_with, convert_value, div_with, mul_with, quantity_with,
+        sub_with,
+    },
+    registry::{CustomUnit, UnitRegistry},
+    special_units::{
+        ConversionContext, SpecialUnitHandler, SpecialUnitRegistry, SubstanceConversion,
+    },
+    suggestions::SuggestionEngine,
+    tokens::{Token, tokenize_with},
+    types::{Dimension, DimensionModel, Prefix, Quantity, SpecialKind, UnitRecord},
+};
+
+/// Byte offset in `symbol` of the atom `code` that ends it. The symbol may
+/// spell `u` as `µ`, so the atom is found by characters rather than bytes.
+pub(crate) fn atom_start(symbol: &str, code: &str) -> usize {
+    symbol
+        .char_indices()
+        .rev()
+        .nth(code.chars().count().saturating_sub(1))
+        .map_or(0, |(index, _)| index)
+}
+
+static DEFAULT_ENGINE: Lazy<UcumEngine> = Lazy::new(UcumEngine::new);
+
+/// The shared engine used by the crate's free functions.
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::default_engine;
+///
+/// assert!(default_engine().validate("mg/dL").is_ok());
+/// ```
+pub fn default_engine() -> &'static UcumEngine {
+    &DEFAULT_ENGINE
+}
+
+/// Behavioural switches for a [`UcumEngine`].
+#[derive(Debug, Clone)]
+pub struct EngineConfig {
+    /// Cache evaluation results in the engine's [`EvaluationCache`].
+    pub enable_cache: bool,
+    /// Attach "did you mean" suggestions to validation errors.
+    pub enable_suggestions: bool,
+    /// Base quantities that evaluation, canonical units and commensurability
+    /// checks use; applied to the engine's registry.
+    pub dimension_model: DimensionModel,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            enable_cache: true,
+            enable_suggestions: true,
+            dimension_model: DimensionModel::default(),
+        }
+    }
+}
+
+/// A self-contained UCUM context with its own registry, cache and handlers.
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::{EngineConfig, UcumEngine};
+///
+/// let engine = UcumEngine::new().with_config(EngineConfig {
+///     enable_cache: false,
+///     ..Default::default()
+/// });
+///
+/// assert!(engine.validate("kg.m/s2").is_ok());
+/// assert!((engine.convert(1.0, "km", "m").unwrap() - 1000.0).abs() < 1e-9);
+/// ```
+pub struct UcumEngine {
+    registry: UnitRegistry,
+    cache: Mutex<EvaluationCache>,
+    special_units: SpecialUnitRegistry,
+    config: EngineConfig,
+    /// Built on first use; the similarity map is expensive to compute.
+    suggestions: OnceCell<SuggestionEngine>,
+}
+
+impl Default for UcumEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UcumEngine {
+    /// Create an engine with the built-in registry and default configuration.
+    pub fn new() -> Self {
+        Self {
+            registry: UnitRegistry::builtin(),
+            cache: Mutex::new(EvaluationCache::new()),
+            special_units: SpecialUnitRegistry::default(),
+            config: EngineConfig::default(),
+            suggestions: OnceCell::new(),
+        }
+    }
+
+    /// Replace the unit registry.
+    ///
+    /// The engine adopts the registry's dimension model, so
+    /// [`EngineConfig::dimension_model`] always matches [`UnitRegistry::dimension_model`].
+    pub fn with_registry(mut self, registry: UnitRegistry) -> Self {
+        self.config.dimension_model = registry.dimension_model();
+        self.registry = registry;
+        self.invalidate();
+        self
+    }
+
+    /// Replace the special unit handlers.
+    pub fn with_special_units(mut self, special_units: SpecialUnitRegistry) -> Self {
+        self.special_units = special_units;
+        self
+    }
+
+    /// Replace the configuration.
+    pub fn with_config(mut self, config: EngineConfig) -> Self {
+        if config.dimension_model != self.registry.dimension_model() {
+            let registry = std::mem::take(&mut self.registry);
+            self.registry = registry.with_dimension_model(config.dimension_model);
+            self.invalidate();
+        }
+        self.config = config;
+        self
+    }
+
+    /// Switch the base quantities dimensions are expressed in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::{DimensionModel, UcumEngine};
+    ///
+    /// let engine = UcumEngine::new().with_dimension_model(DimensionModel::Ucum);
+    /// assert!(!engine.is_comparable("rad", "%").unwrap());
+    /// assert!(engine.is_comparable("mol", "1").unwrap());
+    /// assert_eq!(engine.get_canonical_units("N").unwrap().unit, "g.m.s-2");
+    /// ```
+    pub fn with_dimension_model(self, dimension_model: DimensionModel) -> Self {
+        let config = EngineConfig {
+            dimension_model,
+            ..self.config.clone()
+        };
+        self.with_config(config)
+    }
+
+    /// The engine's unit registry.
+    pub fn registry(&self) -> &UnitRegistry {
+        &self.registry
+    }
+
+    /// Register a custom unit; see [`UnitRegistry::register_unit`].
+    ///
+    /// The unit immediately takes part in evaluation, conversion, search and
+    /// suggestions of this engine (and only this engine).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::{CustomUnit, UcumEngine};
+    ///
+    /// let mut engine = UcumEngine::new();
+    /// engine
+    ///     .register_unit(CustomUnit::new("[tbsp_loc]", "15.mL").with_property("volume"))
+    ///     .unwrap();
+    /// assert!((engine.convert(2.0, "[tbsp_loc]", "mL").unwrap() - 30.0).abs() < 1e-9);
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn register_unit(&mut self, unit: CustomUnit) -> Result<&UnitRecord, UcumError> {
+        self.invalidate();
+        self.registry.register_unit(unit)
+    }
+
+    /// Register a custom prefix; see [`UnitRegistry::register_prefix`].
+    #[allow(clippy::result_large_err)]
+    pub fn register_prefix(
+        &mut self,
+        symbol: &str,
+        factor: f64,
+        display_name: &str,
+    ) -> Result<&Prefix, UcumError> {
+        self.invalidate();
+        self.registry.register_prefix(symbol, factor, display_name)
+    }
+
+    /// The engine's special unit handlers.
+    pub fn special_units(&self) -> &SpecialUnitRegistry {
+        &self.special_units
+    }
+
+    /// The engine's configuration.
+    pub fn config(&self) -> &EngineConfig {
+        &self.config
+    }
+
+    /// The suggestion engine built from this engine's registry.
+    pub fn suggestion_engine(&self) -> &SuggestionEngine {
+        self.suggestions
+            .get_or_init(|| SuggestionEngine::from_units(self.registry.units()))
+    }
+
+    // ------------------------------------------------------------------------
+    // Registry access
+    // ------------------------------------------------------------------------
+
+    /// Lookup a unit by code (prefixed codes resolve to their atom).
+    pub fn find_unit(&self, code: &str) -> Option<&UnitRecord> {
+        self.registry.find_unit(code)
+    }
+
+    /// Lookup a prefix by symbol.
+    pub fn find_prefix(&self, sym: &str) -> Option<&Prefix> {
+        self.registry.find_prefix(sym)
+    }
+
+    /// All units known to this engine.
+    pub fn units(&self) -> &[UnitRecord] {
+        self.registry.units()
+    }
+
+    /// All prefixes known to this engine.
+    pub fn prefixes(&self) -> &[Prefix] {
+        self.registry.prefixes()
+    }
+
+    // ------------------------------------------------------------------------
+    // Parsing and evaluation
+    // ------------------------------------------------------------------------
+
+    /// Parse a UCUM expression into an AST.
+    #[allow(clippy::result_large_err)]
+    pub fn parse(&self, input: &str) -> Result<OwnedUnitExpr, UcumError> {
+        parser::parse_expression_optimized(input)
+    }
+
+    /// Parse a UCUM expression, keeping the byte range of every node,
+    /// including the prefix of each symbol.
+    #[allow(clippy::result_large_err)]
+    pub fn parse_spanned(&self, input: &str) -> Result<SpannedExpr, UcumError> {
+        let mut spanned = parser::parse_expression_spanned(input)?;
+        self.resolve_prefixes(&spanned.source, &spanned.expr, &mut spanned.spans);
+        Ok(spanned)
+    }
+
+    /// Parse a UCUM expression, recovering from errors to report every
+    /// problem in it.
+    ///
+    /// Terms are parsed separately between top-level `.` and `/` operators;
+    /// those that parse form a partial expression. Each diagnostic has a
+    /// span and, for unknown atoms, suggestions when they are enabled. An
+    /// expression without errors is also evaluated, as by
+    /// [`Self::validate`].
+    pub fn parse_lenient(&self, input: &str) -> LenientParse {
+        parse_lenient_with(self, input)
+    }
+
+    /// Parse a case-insensitive (c/i) UCUM expression such as `MG/DL`.
+    ///
+    /// Symbols are matched against the c/i codes of the engine's registry,
+    /// so registered units and loaded essence files are recognised too.
+    /// See [`parse_expression_ci`](crate::parse_expression_ci).
+    #[allow(clippy::result_large_err)]
+    pub fn parse_ci(&self, input: &str) -> Result<CaseInsensitiveParse, UcumError> {
+        parse_ci_with(self, input)
+    }
+
+    /// Validate a case-insensitive (c/i) UCUM expression against the
+    /// engine's registry; see [`Self::parse_ci`].
+    #[allow(clippy::result_large_err)]
+    pub fn validate_ci(&self, input: &str) -> Result<CaseInsensitiveParse, UcumError> {
+        validate_ci_with(self, input)
+    }
+
+    /// Split a UCUM expression into labelled tokens for highlighting.
+    ///
+    /// Never fails: invalid text becomes [`TokenKind::Error`] tokens.
+    ///
+    /// [`TokenKind::Error`]: crate::TokenKind::Error
+    pub fn tokenize<'a>(&self, input: &'a str) -> Vec<Token<'a>> {
+        tokenize_with(self, input)
+    }
+
+    /// Candidates for the symbol or annotation at byte offset `cursor` of a
+    /// partially typed expression, best first.
+    ///
+    /// Atoms, prefixed atoms and prefixes come from the registry and are
+    /// ranked by how closely they match the text typed before the cursor,
+    /// which need not be valid yet. Inside braces, or after a complete
+    /// term, common annotations are offered instead. Each candidate
+    /// replaces its `range` of the expression.
+    pub fn complete(&self, expression: &str, cursor: usize) -> Vec<Completion> {
+        complete_with(self, expression, cursor, None)
+    }
+
+    /// Like [`Self::complete`], for an expression that must be
+    /// commensurable with `unit`.
+    ///
+    /// Once the rest of the expression is typed and valid, it fixes the
+    /// dimension of the symbol under the cursor, and only candidates of
+    /// that dimension are kept: after `mg/` in a mass concentration, only
+    /// volumes.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of an invalid `unit`.
+    #[allow(clippy::result_large_err)]
+    pub fn complete_as(
+        &self,
+        expression: &str,
+        cursor: usize,
+        unit: &str,
+    ) -> Result<Vec<Completion>, UcumError> {
+        let expected = self.evaluate_owned(&self.parse(unit)?)?.dim;
+        Ok(complete_with(self, expression, cursor, Some(expected)))
+    }
+
+    /// Fill in the prefix span of each prefixed symbol of `expr`, parsed
+    /// from `source`.
+    pub(crate) fn resolve_prefixes(
+        &self,
+        source: &str,
+        expr: &OwnedUnitExpr,
+        spans: &mut NodeSpans,
+    ) {
+        match expr {
+            OwnedUnitExpr::Symbol(symbol) => {
+                if let Some((_, unit)) = self.resolve_symbol(symbol)
+                    && unit.code != *symbol
+                {
+                    let text = &source[spans.range.clone()];
+                    let end = spans.range.start + atom_start(text, &unit.code);
+                    spans.prefix = Some(spans.range.start..end);
+                }
+            }
+            OwnedUnitExpr::Product(factors) => {
+                for (factor, spans) in factors.iter().zip(&mut spans.children) {
+                    if let Some(spans) = spans.children.first_mut() {
+                        self.resolve_prefixes(source, &factor.expr, spans);
+                    }
+                }
+            }
+            OwnedUnitExpr::Quotient(num, den) => {
+                for (expr, spans) in [num, den].into_iter().zip(&mut spans.children) {
+                    self.resolve_prefixes(source, expr, spans);
+                }
+            }
+            OwnedUnitExpr::Power(inner, _) | OwnedUnitExpr::Annotated(inner, _) => {
+                if let Some(spans) = spans.children.first_mut() {
+                    self.resolve_prefixes(source, inner, spans);
+                }
+            }
+            OwnedUnitExpr::Numeric(_) | OwnedUnitExpr::Annotation(_) => {}
+        }
+    }
+
+    /// Evaluate a parsed expression into canonical factor, dimension and offset.
+    #[allow(clippy::result_large_err)]
+    pub fn evaluate(&self, expr: &UnitExpr) -> Result<EvalResult, UcumError> {
+        if !self.config.enable_cache {
+            return evaluate_with(expr, &self.registry);
+        }
+        // Release the lock while evaluating so other threads are not blocked.
+        if let Some(result) = self.cache_guard().get_expression(expr) {
+            return Ok(result);
+        }
+        let result = evaluate_with(expr, &self.registry)?;
+        self.cache_guard().insert_expression(expr, result.clone());
+        Ok(result)
+    }
+
+    /// Evaluate an owned expression into canonical factor, dimension and offset.
+    #[allow(clippy::result_large_err)]
+    pub fn evaluate_owned(&self, expr: &OwnedUnitExpr) -> Result<EvalResult, UcumError> {
+        if !self.config.enable_cache {
+            return evaluate_owned_with(expr, &self.registry);
+        }
+        self.evaluate(&owned_to_borrowed(expr))
+    }
+
+    /// Evaluate a spanned expression; errors carry the [`Span`] of the node
+    /// that caused them, see [`SpannedExpr::locate`].
+    ///
+    /// [`Span`]: crate::Span
+    #[allow(clippy::result_large_err)]
+    pub fn evaluate_spanned(&self, expr: &SpannedExpr) -> Result<EvalResult, UcumError> {
+        self.evaluate_owned(&expr.expr)
+            .map_err(|error| expr.locate(error))
+    }
+
+    /// Validate a UCUM expression, attaching suggestions to errors.
+    #[allow(clippy::result_large_err)]
+    pub fn validate(&self, expression: &str) -> Result<(), UcumError> {
+        let parsed = match self.parse_spanned(expression) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                let enhanced_error = match &e.kind {
+                    ErrorKind::InvalidExpression { reason } if self.config.enable_suggestions => {
+                        UcumError::invalid_expression(reason)
+                            .with_suggestions(
+                                self.suggestion_engine().suggest_corrections(expression),
+                            )
+                            .with_context(format!("While parsing UCUM expression: '{expression}'"))
+                    }
+                    _ => e,
+                };
+                return Err(enhanced_error);
+            }
+        };
+
+        match self.evaluate_spanned(&parsed) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                let span = e.span.clone();
+                let mut enhanced_error = match &e.kind {
+                    ErrorKind::UnitNotFound { unit, .. } if self.config.enable_suggestions => {
+                        let suggestions = self.suggestion_engine().suggest_corrections(unit);
+                        UcumError::unit_not_found(unit)
+                            .with_suggestions(suggestions)
+                            .with_context(format!("In expression: '{expression}'"))
+                    }
+                    ErrorKind::DimensionMismatch {
+                        expected,
+                        found,
+                        operation,
+                    } => UcumError::dimension_mismatch(*expected, *found, operation)
+                        .with_context(format!("In expression: '{expression}'"))
+                        .with_suggestion(
+                            "Check that all units in the expression have compatible dimensions",
+                        ),
+                    _ => e,
+                };
+                enhanced_error.span = span;
+                Err(enhanced_error)
+            }
+        }
+    }
+
+    /// Analyse a UCUM expression.
+    #[allow(clippy::result_large_err)]
+    pub fn analyse(&self, expression: &str) -> Result<UnitAnalysis, UcumError> {
+        let parsed = self.parse_spanned(expression)?;
+        let result = self.evaluate_spanned(&parsed)?;
+
+        Ok(UnitAnalysis {
+            expression: expression.to_string(),
+            parsed_ast: parsed.expr,
+            dimension: result.dim,
+            factor: to_f64(result.factor),
+            offset: to_f64(result.offset),
+            is_dimensionless: result.dim == Dimension([0; 7]),
+            has_offset: result.offset != Number::zero(),
+        })
+    }
+
+    /// Get the canonical (base) units of an expression.
+    ///
+    /// The unit string is written over the
+    /// [`canonical_units`](DimensionModel::canonical_units) of the engine's
+    /// [`DimensionModel`] and keeps every arbitrary unit as a term of its own,
+    /// so `[IU]/mL` gives `[IU].m-3` and canonical codes can serve as index
+    /// keys. Under [`DimensionModel::Ucum`] this is the spec's canonical form;
+    /// under [`DimensionModel::Si`] the mole is kept, so `mmol/L` gives
+    /// `m-3.mol`. `factor` and `offset` convert into that unit string, so
+    /// `kg` gives `g` with a factor of 1000.
+    ///
+    /// Logarithmic and tangent units such as `[pH]` and `B[W]` have no
+    /// factor: they are flagged with
+    /// [`is_nonlinear`](CanonicalUnit::is_nonlinear) and their values need
+    /// [`convert`](Self::convert) to reach the canonical unit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::default_engine;
+    ///
+    /// let canonical = default_engine().get_canonical_units("[IU]/mL").unwrap();
+    /// assert_eq!(canonical.unit, "[IU].m-3");
+    /// assert_eq!(canonical.factor, 1e6);
+    ///
+    /// let canonical = default_engine().get_canonical_units("[pH]").unwrap();
+    /// assert_eq!(canonical.unit, "m-3.mol");
+    /// assert!(canonical.is_nonlinear);
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn get_canonical_units(&self, expression: &str) -> Result<CanonicalUnit, UcumError> {
+        let analysis = self.analyse(expression)?;
+        let unit = build_canonical_unit_string(&self.canonical_terms(&analysis.parsed_ast)?);
+        let is_nonlinear = self.contains_nonlinear(&analysis.parsed_ast);
+        let (factor, offset) = if is_nonlinear {
+            (f64::NAN, f64::NAN)
+        } else {
+            // The canonical unit string has a factor of its own under the
+            // SI model (`mol`) and for gram-based terms (`g`)
+            let result = self.evaluate_owned(&analysis.parsed_ast)?;
+            let unit_factor = self.evaluate_owned(&self.parse(&unit)?)?.factor;
+            let scale = |value: Number| match value.checked_div(unit_factor) {
+                Some(scaled) => to_f64(scaled),
+                None => to_f64(value) / to_f64(unit_factor),
+            };
+            (scale(result.factor), scale(result.offset))
+        };
+        Ok(CanonicalUnit {
+            unit,
+            factor,
+            offset,
+            dimension: analysis.dimension,
+            is_nonlinear,
+        })
+    }
+
+    /// Canonical terms of a parsed expression: exponents of the canonical
+    /// units of the engine's [`DimensionModel`] and of each arbitrary unit,
+    /// keyed by code.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn canonical_terms(
+        &self,
+        expr: &OwnedUnitExpr,
+    ) -> Result<BTreeMap<String, i32>, UcumError> {
+        let mut terms = BTreeMap::new();
+        self.collect_canonical_terms(expr, 1, &mut terms)?;
+        terms.retain(|_, exp| *exp != 0);
+        Ok(terms)
+    }
+
+    /// Exponents of the arbitrary units of a parsed expression, keyed by code.
+    ///
+    /// Arbitrary units are dimensionless yet only commensurable with
+    /// themselves, which the dimension vector alone cannot express.
+    #[allow(clippy::result_large_err)]
+    fn arbitrary_terms(&self, expr: &OwnedUnitExpr) -> Result<BTreeMap<String, i32>, UcumError> {
+        let base_units = self.registry.dimension_model().canonical_units();
+        let mut terms = self.canonical_terms(expr)?;
+        terms.retain(|code, _| !base_units.contains(&code.as_str()));
+        Ok(terms)
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn collect_canonical_terms(
+        &self,
+        expr: &OwnedUnitExpr,
+        exponent: i32,
+        terms: &mut BTreeMap<String, i32>,
+    ) -> Result<(), UcumError> {
+        match expr {
+            OwnedUnitExpr::Symbol(symbol) => match self.resolve_symbol(symbol) {
+                Some((_, unit)) if unit.special == SpecialKind::Arbitrary => {
+                    *terms.entry(unit.code.to_string()).or_insert(0) += exponent;
+                }
+                // Custom units keep the arbitrary units of their definition
+                Some((_, unit)) if let Some(definition) = self.registry.definition(&unit.code) => {
+                    self.collect_canonical_terms(definition, exponent, terms)?;
+                }
+                Some((_, unit)) => {
+                    let base_units = self.registry.dimension_model().canonical_units();
+                    let dimension = self.registry.dimension_of(unit);
+                    for (code, &exp) in base_units.iter().zip(dimension.0.iter()) {
+                        if exp != 0 {
+                            *terms.entry(code.to_string()).or_insert(0) += exponent * exp as i32;
+                        }
+                    }
+                }
+                // Unregistered bracketed codes evaluate as arbitrary units
+                None if symbol.starts_with('[') && symbol.ends_with(']') => {
+                    *terms.entry(symbol.clone()).or_insert(0) += exponent;
+                }
+                None => return Err(UcumError::unit_not_found(symbol)),
+            },
+            OwnedUnitExpr::Product(factors) => {
+                for factor in factors {
+                    self.collect_canonical_terms(&factor.expr, exponent * factor.exponent, terms)?;
+                }
+            }
+            OwnedUnitExpr::Quotient(num, den) => {
+                self.collect_canonical_terms(num, exponent, terms)?;
+                self.collect_canonical_terms(den, -exponent, terms)?;
+            }
+            OwnedUnitExpr::Power(inner, power) => {
+                self.collect_canonical_terms(inner, exponent * power, terms)?;
+            }
+            OwnedUnitExpr::Annotated(inner, _) => {
+                self.collect_canonical_terms(inner, exponent, terms)?;
+            }
+            OwnedUnitExpr::Numeric(_) | OwnedUnitExpr::Annotation(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Check if two units are commensurable.
+    #[allow(clippy::result_large_err)]
+    pub fn is_comparable(&self, unit1: &str, unit2: &str) -> Result<bool, UcumError> {
+        let (analysis1, analysis2) = (self.analyse(unit1)?, self.analyse(unit2)?);
+        Ok(analysis1.dimension == analysis2.dimension
+            && self.arbitrary_terms(&analysis1.parsed_ast)?
+                == self.arbitrary_terms(&analysis2.parsed_ast)?)
+    }
+
+    /// Convert a value between two commensurable units, honouring offsets.
+    ///
+    /// Special units (temperatures, bels, `[pH]`, `[p'diop]`, ...) go through
+    /// the engine's [`SpecialUnitRegistry`], see [`convert_expr`](Self::convert_expr).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::default_engine;
+    ///
+    /// let engine = default_engine();
+    /// assert_eq!(engine.convert(20.0, "Cel", "[degF]").unwrap(), 68.0);
+    /// assert!((engine.convert(30.0, "dB[W]", "W").unwrap() - 1000.0).abs() < 1e-9);
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn convert(&self, value: f64, from: &str, to: &str) -> Result<f64, UcumError> {
+        self.convert_float(value, &self.parse(from)?, &self.parse(to)?)
+    }
+
+    /// [`convert_expr`](Self::convert_expr) for a floating point value.
+    ///
+    /// Linear conversions of values a [`Number`] cannot hold, or whose result
+    /// overflows it or keeps fewer significant digits than an `f64` below
+    /// 1e-12, are carried out in floating point instead. Non-finite values
+    /// and results are errors, never zero.
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn convert_float(
+        &self,
+        value: f64,
+        from: &OwnedUnitExpr,
+        to: &OwnedUnitExpr,
+    ) -> Result<f64, UcumError> {
+        if !value.is_finite() {
+            return Err(UcumError::conversion_error(
+                &from.to_string(),
+                &to.to_string(),
+                "value must be a finite number",
+            ));
+        }
+        let mut imprecise = None;
+        if let Some(exact) = try_from_f64_shortest(value) {
+            match self.convert_expr(exact, from, to) {
+                Ok(result) if result.abs() >= Number::new(1, 12) || value == 0.0 => {
+                    return Ok(to_f64(result));
+                }
+                Ok(result) => imprecise = Some(to_f64(result)),
+                Err(e) if matches!(e.kind, ErrorKind::PrecisionOverflow { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        // Special units have no floating point path; keep their exact result
+        self.convert_linear_float(value, from, to)
+            .or_else(|error| imprecise.ok_or(error))
+    }
+
+    /// Linear conversion in floating point, for values outside a [`Number`]'s range.
+    #[allow(clippy::result_large_err)]
+    fn convert_linear_float(
+        &self,
+        value: f64,
+        from: &OwnedUnitExpr,
+        to: &OwnedUnitExpr,
+    ) -> Result<f64, UcumError> {
+        let from_result = self.evaluate_owned(from)?;
+        let to_result = self.evaluate_owned(to)?;
+        self.check_commensurable(from, to, &from_result, &to_result)?;
+        if self.special_atom(from)?.is_some() || self.special_atom(to)?.is_some() {
+            return Err(UcumError::precision_overflow(
+                "conversion",
+                &value.to_string(),
+            ));
+        }
+        let canonical = value * to_f64(from_result.factor) + to_f64(from_result.offset);
+        let result = (canonical - to_f64(to_result.offset)) / to_f64(to_result.factor);
+        if result.is_finite() {
+            Ok(result)
+        } else {
+            Err(UcumError::precision_overflow(
+                "conversion",
+                &value.to_string(),
+            ))
+        }
+    }
+
+    /// Convert a value between two parsed, commensurable units.
+    ///
+    /// This is the single conversion path of the crate. Linear units are
+    /// scaled through their canonical factor and offset. A special unit
+    /// (offset, logarithmic or tangent scale) must make up the whole
+    /// expression; its value is passed through the matching handler, which
+    /// maps it onto the handler's [`base_unit`](crate::special_units::SpecialUnitHandler::base_unit).
+    /// A prefix on a special unit scales the value, so `30 dB[W]` is `3 B[W]`.
+    #[allow(clippy::result_large_err)]
+    pub fn convert_expr(
+        &self,
+        value: Number,
+        from: &OwnedUnitExpr,
+        to: &OwnedUnitExpr,
+    ) -> Result<Number, UcumError> {
+        let from_result = self.evaluate_owned(from)?;
+        let to_result = self.evaluate_owned(to)?;
+        let source = self.special_atom(from)?;
+        let target = self.special_atom(to)?;
+        self.check_commensurable(from, to, &from_result, &to_result)?;
+        if source.is_none() && target.is_none() {
+            return convert_value(value, &from_result, &to_result, "conversion");
+        }
+
+        let overflow = || UcumError::precision_overflow("conversion", &value.to_string());
+        let context = ConversionContext::new();
+        let canonical = match source {
+            Some((code, prefix)) => {
+                let handler = self.special_handler(code)?;
+                let base = self.evaluate_owned(&self.parse(handler.base_unit(code))?)?;
+                let scaled = value.checked_mul(prefix).ok_or_else(overflow)?;
+                handler
+                    .convert_from(scaled, code, &context)?
+                    .checked_mul(base.factor)
+                    .and_then(|canonical| canonical.checked_add(base.offset))
+            }
+            None => value
+                .checked_mul(from_result.factor)
+                .and_then(|canonical| canonical.checked_add(from_result.offset)),
+        }
+        .ok_or_else(overflow)?;
+        match target {
+            Some((code, prefix)) => {
+                let handler = self.special_handler(code)?;
+                let base = self.evaluate_owned(&self.parse(handler.base_unit(code))?)?;
+                let value = canonical
+                    .checked_sub(base.offset)
+                    .and_then(|canonical| canonical.checked_div(base.factor))
+                    .ok_or_else(overflow)?;
+                handler
+                    .convert_to(value, code, &context)?
+                    .checked_div(prefix)
+                    .ok_or_else(overflow)
+            }
+            None => canonical
+                .checked_sub(to_result.offset)
+                .and_then(|canonical| canonical.checked_div(to_result.factor))
+                .ok_or_else(overflow),
+        }
+    }
+
+    /// Fail unless two evaluated expressions share their dimension and their
+    /// arbitrary units.
+    #[allow(clippy::result_large_err)]
+    fn check_commensurable(
+        &self,
+        from: &OwnedUnitExpr,
+        to: &OwnedUnitExpr,
+        from_result: &EvalResult,
+        to_result: &EvalResult,
+    ) -> Result<(), UcumError> {
+        if self.arbitrary_terms(from)? != self.arbitrary_terms(to)? {
+            return Err(UcumError::conversion_error(
+                &from.to_string(),
+                &to.to_string(),
+                "arbitrary units are only commensurable with themselves",
+            ));
+        }
+        if from_result.dim != to_result.dim {
+            return Err(UcumError::dimension_mismatch(
+                to_result.dim,
+                from_result.dim,
+                "conversion",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Convert a value of one substance between units of mass, amount of
+    /// substance and equivalents, such as glucose from `mg/dL` to `mmol/L`.
+    ///
+    /// Units that differ by a mass per mole are bridged with the context's
+    /// [molar mass](ConversionContext::molar_mass), and units that differ in
+    /// their power of `eq` with its [charge](ConversionContext::charge). The
+    /// result records the factors that were applied; without them this is
+    /// [`convert_expr`](Self::convert_expr).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::{ConversionContext, default_engine, precision::Number};
+    ///
+    /// let sodium = ConversionContext::new()
+    ///     .with_molar_mass(Number::new(22990, 3))
+    ///     .with_charge(Number::ONE);
+    /// let result = default_engine()
+    ///     .convert_substance(Number::new(2299, 1), "mg/L", "meq/L", &sodium)
+    ///     .unwrap();
+    /// assert_eq!(result.value, Number::TEN);
+    /// assert!(result.is_substance_specific());
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn convert_substance(
+        &self,
+        value: Number,
+        from: &str,
+        to: &str,
+        context: &ConversionContext,
+    ) -> Result<SubstanceConversion, UcumError> {
+        self.convert_substance_expr(value, &self.parse(from)?, &self.parse(to)?, context)
+    }
+
+    /// [`convert_substance`](Self::convert_substance) between parsed units.
+    #[allow(clippy::result_large_err)]
+    pub fn convert_substance_expr(
+        &self,
+        value: Number,
+        source: &OwnedUnitExpr,
+        target: &OwnedUnitExpr,
+        context: &ConversionContext,
+    ) -> Result<SubstanceConversion, UcumError> {
+        let (from, to) = (&source.to_string(), &target.to_string());
+        let overflow = || UcumError::precision_overflow("substance conversion", &value.to_string());
+        let mut result = SubstanceConversion {
+            value,
+            molar_mass: None,
+            charge: None,
+            grams_per_iu: None,
+        };
+
+        let equivalents = self.atom_power(target, "eq") - self.atom_power(source, "eq");
+        if equivalents != 0 {
+            let charge = context
+                .charge()
+                .filter(|charge| !charge.is_zero())
+                .ok_or_else(|| {
+                    UcumError::conversion_error(
+                        from,
+                        to,
+                        "Converting between moles and equivalents needs the charge of the substance",
+                    )
+                })?;
+            result.value = checked_pow(charge.abs(), equivalents)
+                .and_then(|scale| result.value.checked_mul(scale))
+                .ok_or_else(overflow)?;
+            result.charge = Some(charge);
+        }
+
+        let (source_eval, target_eval) =
+            (self.evaluate_owned(source)?, self.evaluate_owned(target)?);
+        if source_eval.dim == target_eval.dim {
+            result.value = self.convert_expr(result.value, source, target)?;
+            return Ok(result);
+        }
+
+        // One mole weighs the molar mass in grams: the amount side times
+        // g/mol must be commensurable with the mass side.
+        let gram = self.evaluate_owned(&self.parse("g")?)?;
+        let mole = self.evaluate_owned(&self.parse("mol")?)?;
+        let molar = Dimension(std::array::from_fn(|i| gram.dim.0[i] - mole.dim.0[i]));
+        let per_mole = |dim: Dimension| Dimension(std::array::from_fn(|i| dim.0[i] + molar.0[i]));
+        let to_amount = if source_eval.dim == per_mole(target_eval.dim) {
+            true
+        } else if target_eval.dim == per_mole(source_eval.dim) {
+            false
+        } else {
+            return Err(UcumError::dimension_mismatch(
+                target_eval.dim,
+                source_eval.dim,
+                "substance conversion",
+            ));
+        };
+        if self.contains_nonlinear(source)
+            || self.contains_nonlinear(target)
+            || !source_eval.offset.is_zero()
+            || !target_eval.offset.is_zero()
+        {
+            return Err(UcumError::conversion_error(
+                from,
+                to,
+                "Substance conversions apply to linear units only",
+            ));
+        }
+        let molar_mass = context
+            .molar_mass()
+            .filter(|molar_mass| *molar_mass > Number::ZERO)
+            .ok_or_else(|| {
+                UcumError::conversion_error(
+                    from,
+                    to,
+                    "Converting between mass and amount of substance needs a positive molar mass",
+                )
+            })?;
+
+        // Factors relative to grams and moles rather than canonical units,
+        // which would set the Avogadro number of `mol` against the small
+        // factor of `g`.
+        let scaled = result.value;
+        result.value = if to_amount {
+            let grams = source_eval.factor.checked_div(gram.factor);
+            let moles = target_eval.factor.checked_div(mole.factor);
+            grams.zip(moles).and_then(|(grams, moles)| {
+                scaled
+                    .checked_mul(grams)?
+                    .checked_div(molar_mass)?
+                    .checked_div(moles)
+            })
+        } else {
+            let moles = source_eval.factor.checked_div(mole.factor);
+            let grams = target_eval.factor.checked_div(gram.factor);
+            moles.zip(grams).and_then(|(moles, grams)| {
+                scaled
+                    .checked_mul(moles)?
+                    .checked_mul(molar_mass)?
+                    .checked_div(grams)
+            })
+        }
+        .ok_or_else(overflow)?;
+        result.molar_mass = Some(molar_mass);
+        Ok(result)
+    }
+
+    /// The net power of the unit atom `code`, with any prefix, in `expr`.
+    pub(crate) fn atom_power(&self, expr: &OwnedUnitExpr, code: &str) -> i32 {
+        match expr {
+            OwnedUnitExpr::Symbol(symbol) => self
+                .resolve_symbol(symbol)
+                .map_or(0, |(_, unit)| i32::from(unit.code == code)),
+            OwnedUnitExpr::Product(factors) => factors
+                .iter()
+                .map(|factor| factor.exponent * self.atom_power(&factor.expr, code))
+                .sum(),
+            OwnedUnitExpr::Quotient(num, den) => {
+                self.atom_power(num, code) - self.atom_power(den, code)
+            }
+            OwnedUnitExpr::Power(inner, power) => power * self.atom_power(inner, code),
+            OwnedUnitExpr::Annotated(inner, _) => self.atom_power(inner, code),
+            OwnedUnitExpr::Numeric(_) | OwnedUnitExpr::Annotation(_) => 0,
+        }
+    }
+
+    /// The atom code and prefix factor of an expression that consists of a
+    /// single special unit, or `None` for linear expressions.
+    #[allow(clippy::result_large_err)]
+    fn special_atom(&self, expr: &OwnedUnitExpr) -> Result<Option<(&str, Number)>, UcumError> {
+        match expr {
+            OwnedUnitExpr::Annotated(inner, _) => self.special_atom(inner),
+            OwnedUnitExpr::Symbol(symbol) => Ok(self
+                .split_special(symbol)
+                .map(|(prefix, unit)| (unit.code.as_ref(), prefix))),
+            _ if self.contains_nonlinear(expr) => Err(UcumError::conversion_error(
+                &expr.to_string(),
+                "conversion",
+                "Special units can only be converted on their own, not as part of a larger expression",
+            )),
+            _ => Ok(None),
+        }
+    }
+
+    /// Resolve a symbol to a special unit that needs a handler, with the
+    /// factor of its prefix (one when unprefixed).
+    fn split_special(&self, symbol: &str) -> Option<(Number, &UnitRecord)> {
+        self.resolve_symbol(symbol).filter(|(_, unit)| {
+            matches!(
+                unit.special,
+                SpecialKind::LinearOffset
+                    | SpecialKind::Log10
+                    | SpecialKind::Ln
+                    | SpecialKind::TanTimes100
+            )
+        })
+    }
+
+    /// Resolve a symbol to its unit and the factor of its prefix (one when
+    /// unprefixed), trying the longest prefix first.
+    pub(crate) fn resolve_symbol(&self, symbol: &str) -> Option<(Number, &UnitRecord)> {
+        if let Some(unit) = self.registry.find_exact_unit(symbol) {
+            return Some((Number::one(), unit));
+        }
+        (1..symbol.len())
+            .rev()
+            .filter(|&len| symbol.is_char_boundary(len))
+            .find_map(|len| {
+                let prefix = self.registry.find_prefix(&symbol[..len])?;
+                let unit = self.registry.find_exact_unit(&symbol[len..])?;
+                Some((prefix.exact_factor, unit))
+            })
+    }
+
+    /// Whether a logarithmic or tangent unit occurs anywhere in `expr`.
+    pub(crate) fn contains_nonlinear(&self, expr: &OwnedUnitExpr) -> bool {
+        match expr {
+            OwnedUnitExpr::Symbol(symbol) => self
+                .split_special(symbol)
+                .is_some_and(|(_, unit)| unit.special != SpecialKind::LinearOffset),
+            OwnedUnitExpr::Product(factors) => {
+                factors.iter().any(|f| self.contains_nonlinear(&f.expr))
+            }
+            OwnedUnitExpr::Quotient(num, den) => {
+                self.contains_nonlinear(num) || self.contains_nonlinear(den)
+            }
+            OwnedUnitExpr::Power(inner, _) | OwnedUnitExpr::Annotated(inner, _) => {
+                self.contains_nonlinear(inner)
+            }
+            OwnedUnitExpr::Numeric(_) | OwnedUnitExpr::Annotation(_) => false,
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn special_handler(&self, code: &str) -> Result<&dyn SpecialUnitHandler, UcumError> {
+        self.special_units.find_handler(code).ok_or_else(|| {
+            UcumError::conversion_error(
+                code,
+                "conversion",
+                "No handler is registered for this special unit",
+            )
+        })
+    }
+
+    // ------------------------------------------------------------------------
+    // Quantities and analytes
+    // ------------------------------------------------------------------------
+
+    /// Create a [`Quantity`] whose unit is parsed and checked against this
+    /// engine's registry, see [`Quantity::new`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::{CustomUnit, UcumEngine};
+    ///
+    /// let mut engine = UcumEngine::new();
+    /// engine.register_unit(CustomUnit::new("dose", "250.mg")).unwrap();
+    /// let dose = engine.quantity(2.0, "dose").unwrap();
+    /// let mg = engine.quantity(500.0, "mg").unwrap();
+    /// let total = engine.add_quantities(&dose, &mg).unwrap();
+    /// assert!((total.value - 4.0).abs() < 1e-9);
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn quantity(&self, value: f64, unit: &str) -> Result<Quantity, UcumError> {
+        quantity_with(self, value, unit)
+    }
+
+    /// Convert a quantity to another unit, see [`Quantity::convert_to`].
+    #[allow(clippy::result_large_err)]
+    pub fn convert_quantity(&self, quantity: &Quantity, unit: &str) -> Result<Quantity, UcumError> {
+        convert_to_with(self, quantity, &self.parse(unit)?)
+    }
+
+    /// Add two quantities in the unit of `lhs`, see [`Quantity::checked_add`].
+    #[allow(clippy::result_large_err)]
+    pub fn add_quantities(&self, lhs: &Quantity, rhs: &Quantity) -> Result<Quantity, UcumError> {
+        add_with(self, lhs, rhs)
+    }
+
+    /// Subtract `rhs` from `lhs` in the unit of `lhs`, see
+    /// [`Quantity::checked_sub`].
+    #[allow(clippy::result_large_err)]
+    pub fn subtract_quantities(
+        &self,
+        lhs: &Quantity,
+        rhs: &Quantity,
+    ) -> Result<Quantity, UcumError> {
+        sub_with(self, lhs, rhs)
+    }
+
+    /// Multiply two quantities, see [`Quantity::checked_mul`].
+    #[allow(clippy::result_large_err)]
+    pub fn multiply_quantities(
+        &self,
+        lhs: &Quantity,
+        rhs: &Quantity,
+    ) -> Result<Quantity, UcumError> {
+        mul_with(self, lhs, rhs)
+    }
+
+    /// Divide `lhs` by `rhs`, see [`Quantity::checked_div`].
+    #[allow(clippy::result_large_err)]
+    pub fn divide_quantities(&self, lhs: &Quantity, rhs: &Quantity) -> Result<Quantity, UcumError> {
+        div_with(self, lhs, rhs)
+    }
+
+    /// Compare two quantities across commensurable units, see
+    /// [`Quantity::compare`].
+    pub fn compare_quantities(&self, lhs: &Quantity, rhs: &Quantity) -> Option<Ordering> {
+        compare_with(self, lhs, rhs)
+    }
+
+    /// Convert a value of `analyte` between units of mass, amount of
+    /// substance, equivalents and international units, see
+    /// [`Analyte::convert`].
+    #[allow(clippy::result_large_err)]
+    pub fn convert_analyte(
+        &self,
+        analyte: &Analyte,
+        value: Number,
+        from: &str,
+        to: &str,
+    ) -> Result<SubstanceConversion, UcumError> {
+        convert_analyte_with(self, analyte, value, from, to)
+    }
+
+    /// Convert a value of the catalogued analyte with the code or name
+    /// `analyte`, see [`convert_for_analyte`](crate::convert_for_analyte).
+    #[allow(clippy::result_large_err)]
+    pub fn convert_for_analyte(
+        &self,
+        value: Number,
+        from: &str,
+        to: &str,
+        analyte: &str,
+    ) -> Result<SubstanceConversion, UcumError> {
+        convert_for_analyte_with(self, value, from, to, analyte)
+    }
+
+    // ------------------------------------------------------------------------
+    // Search
+    // ------------------------------------------------------------------------
+
+    /// Case-insensitive substring search over unit codes, names and properties.
+    pub fn search_units(&self, query: &str) -> Vec<&UnitRecord> {
+        let query_lower = query.to_lowercase();
+        let mut results = Vec::new();
+
+        for unit in self.units() {
+            // Simple substring search (case-insensitive)
+            let matches = unit.code.to_lowercase().contains(&query_lower)
+                || unit.display_name.to_lowercase().contains(&query_lower)
+                || unit.property.to_lowercase().contains(&query_lower);
+
+            if matches {
+                results.push(unit);
+            }
+        }
+
+        // Sort results by relevance (exact matches first, then by code length)
+        results.sort_by(|a, b| {
+            let a_exact = a.code.to_lowercase() == query_lower;
+            let b_exact = b.code.to_lowercase() == query_lower;
+
+            match (a_exact, b_exact) {
+                (true, false) => std::cmp::Ordering::Less,
+                (false, true) => std::cmp::Ordering::Greater,
+                _ => a.code.len().cmp(&b.code.len()),
+            }
+        });
+
+        results
+    }
+
+    /// All units with the given property (case-insensitive).
+    pub fn search_units_by_property(&self, property: &str) -> Vec<&UnitRecord> {
+        let property_lower = property.to_lowercase();
+        self.units()
+            .iter()
+            .filter(|unit| unit.property.to_lowercase() == property_lower)
+            .collect()
+    }
+
+    /// The unit itself plus registry units that are prefixed forms of it.
+    pub fn get_defined_forms(&self, base_code: &str) -> Vec<&UnitRecord> {
+        let mut results = Vec::new();
+
+        // Add exact match if it exists
+        if let Some(unit) = self.find_unit(base_code) {
+            results.push(unit);
+        }
+
+        // Find units that might be prefixed versions
+        for unit in self.units() {
+            if unit.code != base_code && unit.code.ends_with(base_code) {
+                // Check if the prefix part is a valid prefix
+                let prefix_part = &unit.code[..unit.code.len() - base_code.len()];
+                if self.find_prefix(prefix_part).is_some() {
+                    results.push(unit);
+                }
+            }
+        }
+
+        results
+    }
+
+    /// Regular expression search over unit codes, names and properties.
+    #[allow(clippy::result_large_err)]
+    pub fn search_units_regex(
+        &self,
+        pattern: &str,
+        case_sensitive: bool,
+    ) -> Result<Vec<&UnitRecord>, UcumError> {
+        let regex_pattern = if case_sensitive {
+            pattern.to_string()
+        } else {
+            format!("(?i){pattern}")
+        };
+
+        let regex = Regex::new(&regex_pattern)
+            .map_err(|_| UcumError::invalid_expression("Invalid regex pattern"))?;
+
+        let mut results: Vec<&UnitRecord> = self
+            .units()
+            .iter()
+            .filter(|unit| {
+                regex.is_match(&unit.code)
+                    || regex.is_match(&unit.display_name)
+                    || regex.is_match(&unit.property)
+            })
+            .collect();
+
+        // Sort by relevance (code matches first, then display name matches)
+        results.sort_by(|a, b| {
+            let a_code_match = regex.is_match(&a.code);
+            let b_code_match = regex.is_match(&b.code);
+
+            match (a_code_match, b_code_match) {
+                (true, false) => std::cmp::Ordering::Less,
+                (false, true) => std::cmp::Ordering::Greater,
+                _ => a.code.len().cmp(&b.code.len()),
+            }
+        });
+
+        Ok(results)
+    }
+
+    /// Fuzzy search, best matches first.
+    ///
+    /// The query matches as a subsequence of a unit's code, name or
+    /// property, or as a misspelling of its code or name: `metter` finds
+    /// the meter.
+    pub fn search_units_fuzzy(&self, query: &str, threshold: i64) -> Vec<(&UnitRecord, i64)> {
+        let matcher = SkimMatcherV2::default();
+        let query_lower = query.to_lowercase();
+        let mut results = Vec::new();
+
+        for unit in self.units() {
+            // Try matching against code, display name, and property
+            let code_score = matcher.fuzzy_match(&unit.code, query).unwrap_or(0);
+            let display_score = matcher.fuzzy_match(&unit.display_name, query).unwrap_or(0);
+            let property_score = matcher.fuzzy_match(&unit.property, query).unwrap_or(0);
+            let typo_score = [&unit.code, &unit.display_name]
+                .iter()
+                .map(|text| SuggestionEngine::string_similarity(&text.to_lowercase(), &query_lower))
+                .filter(|&similarity| similarity >= 0.75)
+                .map(|similarity| (similarity * 100.0) as i64)
+                .max()
+                .unwrap_or(0);
+
+            // Use the best score among all fields
+            let best_score = code_score
+                .max(display_score)
+                .max(property_score)
+                .max(typo_score);
+
+            if best_score >= threshold {
+                results.push((unit, best_score));
+            }
+        }
+
+        // Sort by score (descending - best matches first)
+        results.sort_by_key(|r| std::cmp::Reverse(r.1));
+
+        results
+    }
+
+    /// Search restricted to the given concept kinds.
+    pub fn search_units_filtered(
+        &self,
+        query: &str,
+        kinds: &[ConceptKind],
+        use_fuzzy: bool,
+    ) -> Vec<&UnitRecord> {
+        let all_results = if use_fuzzy {
+            self.search_units_fuzzy(query, 30)
+                .into_iter()
+                .map(|(unit, _score)| unit)
+                .collect()
+        } else {
+            self.search_units(query)
+        };
+
+        all_results
+            .into_iter()
+            .filter(|unit| kinds.contains(&self.classify_unit(unit)))
+            .collect()
+    }
+
+    /// Classify a unit into its concept kind.
+    fn classify_unit(&self, unit: &UnitRecord) -> ConceptKind {
+        // Check for arbitrary units (square brackets)
+        if unit.code.starts_with('[') && unit.code.ends_with(']') {
+            return ConceptKind::ArbitraryUnit;
+        }
+
+        // Check for special units
+        if unit.special != SpecialKind::None {
+            return ConceptKind::SpecialUnit;
+        }
+
+        // Check for prefixed units by looking for known prefixes
+        for prefix in self.prefixes() {
+            if unit.code.starts_with(&*prefix.symbol) && unit.code.len() > prefix.symbol.len() {
+                let remainder = &unit.code[prefix.symbol.len()..];
+                if self.find_unit(remainder).is_some() {
+                    return ConceptKind::PrefixedUnit;
+                }
+            }
+        }
+
+        // Check if it's a base unit (has simple dimension vector with single 1 or -1)
+        let non_zero_dims = unit.dim.0.iter().filter(|&&x| x != 0).count();
+        if non_zero_dims <= 1 && unit.dim.0.iter().any(|&x| x.abs() == 1) {
+            ConceptKind::BaseUnit
+        } else {
+            ConceptKind::DerivedUnit
+        }
+    }
+
+    // ------------------------------------------------------------------------
+    // Cache
+    // ------------------------------------------------------------------------
+
+    /// Run `f` with exclusive access to the engine's cache.
+    pub fn with_cache<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut EvaluationCache) -> R,
+    {
+        f(&mut self.cache_guard())
+    }
+
+    /// Snapshot of the cache statistics.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache_guard().stats().clone()
+    }
+
+    /// Sizes of the expression, conversion and dimension caches.
+    pub fn cache_sizes(&self) -> (usize, usize, usize) {
+        self.cache_guard().cache_sizes()
+    }
+
+    /// Drop all cached results and reset the statistics.
+    pub fn clear_cache(&self) {
+        self.cache_guard().clear();
+    }
+
+    /// Forget everything derived from the registry.
+    fn invalidate(&mut self) {
+        self.suggestions = OnceCell::new();
+        self.cache
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    fn cache_guard(&self) -> MutexGuard<'_, EvaluationCache> {
+        // The cache holds no invariants a panic could break, so recover from poisoning.
+        self.cache.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -88,6 +88,10 @@ pub enum ErrorKind {
     InvalidPercentPlacement { position: usize },
     /// Special unit handling error
     SpecialUnitError { unit: String, reason: String },
+    /// Invalid unit or prefix definition
+    InvalidDefinition { code: String, reason: String },
+    /// UCUM essence document that cannot be read
+    InvalidEssence { reason: String },
 }
 
 /// Enhanced UCUM error with detailed context and suggestions
@@ -245,6 +249,27 @@ impl UcumError {
             format!("Special unit error for '{unit}': {reason}"),
         )
     }
+
+    /// Create an invalid definition error
+    pub fn invalid_definition(code: &str, reason: &str) -> Self {
+        Self::new(
+            ErrorKind::InvalidDefinition {
+                code: code.to_string(),
+                reason: reason.to_string(),
+            },
+            format!("Invalid definition for '{code}': {reason}"),
+        )
+    }
+
+    /// Create an invalid essence document error
+    pub fn invalid_essence(reason: &str) -> Self {
+        Self::new(
+            ErrorKind::InvalidEssence {
+                reason: reason.to_string(),
+            },
+            format!("Invalid UCUM essence: {reason}"),
+        )
+    }
 }
 
 impl std::fmt::Display for UcumError {
@@ -0,0 +1,945 @@
+//! Parser for the UCUM essence XML (`ucum-essence.xml`).
+//!
+//! This module is compiled twice: by `build.rs`, which bakes the bundled file
+//! into the generated `PREFIXES`/`UNITS` tables, and into the library, where
+//! [`crate::UnitRegistry::from_essence_xml`] loads an essence file at runtime.
+//! Both therefore resolve factors and dimensions identically. It must only
+//! depend on `std`, `quick-xml` and `rust_decimal`.
+//!
+//! Factors are kept exact: each unit's `value` string is read as a decimal and
+//! multiplied through the definitions it refers to with decimal arithmetic.
+//! The same expansion yields each unit's dimension over UCUM's own base units.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+
+/// Prefix definition read from a `<prefix>` element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrefixDef {
+    pub code: String,
+    pub ci_code: String,
+    pub factor: f64,
+    pub exact_factor: Decimal,
+    /// See [`power_of_ten`].
+    pub exponent: Option<i8>,
+    pub name: String,
+}
+
+/// Unit definition read from a `<base-unit>` or `<unit>` element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnitDef {
+    pub code: String,
+    pub ci_code: String,
+    pub is_metric: bool,
+    /// Dimension over the SI base quantities, see [`parse_dim`].
+    pub dim: [i8; 7],
+    /// Dimension over UCUM's base units, see [`ucum_base_dim`].
+    pub ucum_dim: [i8; 7],
+    pub factor: f64,
+    pub exact_factor: Decimal,
+    pub offset: f64,
+    pub exact_offset: Decimal,
+    /// Name of the `SpecialKind` variant, e.g. `"LinearOffset"`.
+    pub special: &'static str,
+    pub property: String,
+    pub display_name: String,
+    /// The `Unit` attribute of the `<value>` child, used to resolve factors.
+    unit_ref: Option<String>,
+    /// Value and unit expression of a regular unit, to be resolved exactly.
+    definition: Option<(Magnitude, String)>,
+    /// The `<function>` child a special unit is defined through.
+    function: Option<Function>,
+    /// The `class` attribute, e.g. `si` or `chemical`.
+    class: String,
+}
+
+/// The `<function>` child of a special unit's `<value>`, e.g. `degF` of
+/// `5 K/9`.
+#[derive(Clone, Debug, PartialEq)]
+struct Function {
+    name: String,
+    value: Magnitude,
+    unit: String,
+}
+
+/// Prefixes and units of an essence file, each sorted by code.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Essence {
+    pub prefixes: Vec<PrefixDef>,
+    pub units: Vec<UnitDef>,
+}
+
+/// A problem found while reading an essence file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EssenceError {
+    /// Element being read, e.g. `unit`; empty for document-level errors.
+    pub element: &'static str,
+    /// Code of the offending definition, when known.
+    pub code: Option<String>,
+    pub reason: String,
+}
+
+impl EssenceError {
+    fn document(reason: impl Into<String>) -> Self {
+        Self {
+            element: "",
+            code: None,
+            reason: reason.into(),
+        }
+    }
+
+    fn definition(element: &'static str, code: Option<&str>, reason: impl Into<String>) -> Self {
+        Self {
+            element,
+            code: code.map(str::to_string),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for EssenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.code, self.element.is_empty()) {
+            (Some(code), _) => write!(f, "<{}> '{}': {}", self.element, code, self.reason),
+            (None, false) => write!(f, "<{}>: {}", self.element, self.reason),
+            (None, true) => f.write_str(&self.reason),
+        }
+    }
+}
+
+/// Parse an essence document into prefix and unit definitions.
+pub fn parse_essence(xml: &str) -> Result<Essence, EssenceError> {
+    let mut prefixes = Vec::new();
+    let mut units = Vec::new();
+
+    let mut reader = Reader::from_str(xml);
+    loop {
+        // Self-closing elements have no children to read
+        let (e, has_children) = match read_event(&mut reader)? {
+            Event::Start(e) => (e, true),
+            Event::Empty(e) => (e, false),
+            Event::Eof => break,
+            _ => continue,
+        };
+        match e.name().as_ref() {
+            b"prefix" => prefixes.push(read_prefix(&mut reader, &e, has_children)?),
+            b"base-unit" => units.push(read_base_unit(&mut reader, &e, has_children)?),
+            b"unit" => units.push(read_unit(&mut reader, &e, has_children)?),
+            _ => {}
+        }
+    }
+
+    if !units.iter().any(|u: &UnitDef| u.unit_ref.is_none()) {
+        return Err(EssenceError::document(
+            "no base units found; not a UCUM essence document",
+        ));
+    }
+
+    prefixes.sort_by(|a, b| a.code.cmp(&b.code));
+    units.sort_by(|a, b| a.code.cmp(&b.code));
+    check_duplicates("prefix", prefixes.iter().map(|p| p.code.as_str()))?;
+    check_duplicates("unit", units.iter().map(|u| u.code.as_str()))?;
+
+    resolve_definitions(&prefixes, &mut units)?;
+
+    Ok(Essence { prefixes, units })
+}
+
+fn read_event<'a>(reader: &mut Reader<&'a [u8]>) -> Result<Event<'a>, EssenceError> {
+    reader.read_event().map_err(|e| {
+        EssenceError::document(format!(
+            "malformed XML at byte {}: {e}",
+            reader.error_position()
+        ))
+    })
+}
+
+fn attr(e: &BytesStart<'_>, key: &[u8]) -> Option<String> {
+    e.attributes()
+        .filter_map(|a| a.ok())
+        .find(|a| a.key.as_ref() == key)
+        .map(|a| String::from_utf8_lossy(&a.value).to_string())
+}
+
+fn required_code(e: &BytesStart<'_>, element: &'static str) -> Result<String, EssenceError> {
+    attr(e, b"Code")
+        .ok_or_else(|| EssenceError::definition(element, None, "missing Code attribute"))
+}
+
+fn parse_number(
+    text: &str,
+    element: &'static str,
+    code: &str,
+    what: &str,
+) -> Result<f64, EssenceError> {
+    text.trim().parse::<f64>().map_err(|_| {
+        EssenceError::definition(element, Some(code), format!("invalid {what} '{text}'"))
+    })
+}
+
+fn parse_decimal(
+    text: &str,
+    element: &'static str,
+    code: &str,
+    what: &str,
+) -> Result<Decimal, EssenceError> {
+    decimal_from_text(text.trim()).ok_or_else(|| {
+        EssenceError::definition(element, Some(code), format!("invalid {what} '{text}'"))
+    })
+}
+
+/// Read a decimal number, rounding digits beyond the precision of `Decimal`.
+fn decimal_from_text(text: &str) -> Option<Decimal> {
+    let (mantissa, exp) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exp)) => (mantissa, exp.parse::<i32>().ok()?),
+        None => (text, 0),
+    };
+    let mut value = Decimal::from_str(mantissa).ok()?;
+    for _ in 0..exp.unsigned_abs() {
+        value = if exp > 0 {
+            value.checked_mul(Decimal::TEN)?
+        } else {
+            value.checked_div(Decimal::TEN)?
+        };
+    }
+    Some(value)
+}
+
+/// Read a unit's value as an exact decimal plus a float, see [`Magnitude`].
+fn parse_magnitude(text: &str, code: &str) -> Result<Magnitude, EssenceError> {
+    let approx = parse_number(text, "unit", code, "value")?;
+    Magnitude::checked(decimal_from_text(text.trim()), approx, false)
+        .ok_or_else(|| out_of_range(code))
+}
+
+fn check_duplicates<'a>(
+    element: &'static str,
+    codes: impl Iterator<Item = &'a str>,
+) -> Result<(), EssenceError> {
+    let mut seen = HashSet::new();
+    for code in codes {
+        if !seen.insert(code) {
+            return Err(EssenceError::definition(
+                element,
+                Some(code),
+                "defined more than once",
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn read_prefix(
+    reader: &mut Reader<&[u8]>,
+    e: &BytesStart<'_>,
+    has_children: bool,
+) -> Result<PrefixDef, EssenceError> {
+    let code = required_code(e, "prefix")?;
+    let ci_code = attr(e, b"CODE").unwrap_or_else(|| code.to_ascii_uppercase());
+    let mut value = None;
+    let mut name = None;
+
+    // value and name elements are children; capture them
+    let mut open = has_children;
+    while open {
+        match read_event(reader)? {
+            Event::Start(ve) if ve.name().as_ref() == b"value" => {
+                if let Some(v) = attr(&ve, b"value") {
+                    value = Some(parse_decimal(&v, "prefix", &code, "value")?);
+                }
+            }
+            Event::Start(ne) if ne.name().as_ref() == b"name" => {
+                if let Event::Text(text) = read_event(reader)? {
+                    name = Some(String::from_utf8_lossy(&text).to_string());
+                }
+            }
+            Event::End(ve) if ve.name().as_ref() == b"prefix" => open = false,
+            Event::Eof => open = false,
+            _ => {}
+        }
+    }
+
+    let exact_factor =
+        value.ok_or_else(|| EssenceError::definition("prefix", Some(&code), "missing value"))?;
+    let name =
+        name.ok_or_else(|| EssenceError::definition("prefix", Some(&code), "missing name"))?;
+    let factor = exact_factor.to_f64().unwrap_or(0.0);
+    Ok(PrefixDef {
+        exponent: power_of_ten(exact_factor),
+        code,
+        ci_code,
+        factor,
+        exact_factor,
+        name,
+    })
+}
+
+/// The exponent of `value` when it is an exact power of ten: `Some(-3)` for
+/// 0.001, `None` for 1024.
+pub fn power_of_ten(value: Decimal) -> Option<i8> {
+    let value = value.normalize();
+    let (mut mantissa, mut exponent) = (value.mantissa(), -(value.scale() as i32));
+    if mantissa <= 0 {
+        return None;
+    }
+    while mantissa % 10 == 0 {
+        mantissa /= 10;
+        exponent += 1;
+    }
+    if mantissa == 1 {
+        i8::try_from(exponent).ok()
+    } else {
+        None
+    }
+}
+
+fn read_base_unit(
+    reader: &mut Reader<&[u8]>,
+    e: &BytesStart<'_>,
+    has_children: bool,
+) -> Result<UnitDef, EssenceError> {
+    let code = required_code(e, "base-unit")?;
+    let ci_code = attr(e, b"CODE").unwrap_or_else(|| code.to_ascii_uppercase());
+    let dim_tag = attr(e, b"dim").unwrap_or_default();
+    let dim = parse_dim(&dim_tag);
+
+    let mut property = String::new();
+    let mut display_name = String::new();
+    let mut in_property_tag = false;
+    let mut in_name_tag = false;
+    let mut open = has_children;
+    while open {
+        match read_event(reader)? {
+            Event::Text(text) => {
+                if in_property_tag {
+                    property = String::from_utf8_lossy(&text).trim().to_string();
+                    in_property_tag = false;
+                }
+                if in_name_tag {
+                    display_name = String::from_utf8_lossy(&text).trim().to_string();
+                    in_name_tag = false;
+                }
+            }
+            Event::Start(ve) if ve.name().as_ref() == b"property" => in_property_tag = true,
+            Event::Start(ve) if ve.name().as_ref() == b"name" => in_name_tag = true,
+            Event::End(ve) if ve.name().as_ref() == b"base-unit" => open = false,
+            Event::Eof => open = false,
+            _ => {}
+        }
+    }
+
+    if display_name.is_empty() {
+        display_name = code.clone();
+    }
+    // Base units are always metric
+    Ok(UnitDef {
+        code,
+        ci_code,
+        is_metric: true,
+        dim,
+        ucum_dim: ucum_base_dim(&dim_tag),
+        factor: 1.0,
+        exact_factor: Decimal::ONE,
+        offset: 0.0,
+        exact_offset: Decimal::ZERO,
+        special: "None",
+        property,
+        display_name,
+        unit_ref: None,
+        definition: None,
+        function: None,
+        class: attr(e, b"class").unwrap_or_default(),
+    })
+}
+
+fn read_unit(
+    reader: &mut Reader<&[u8]>,
+    e: &BytesStart<'_>,
+    has_children: bool,
+) -> Result<UnitDef, EssenceError> {
+    let code = required_code(e, "unit")?;
+    let ci_code = attr(e, b"CODE").unwrap_or_else(|| code.to_ascii_uppercase());
+    let is_metric = attr(e, b"isMetric").is_some_and(|v| v == "yes");
+    let is_arbitrary = attr(e, b"isArbitrary").is_some_and(|v| v == "yes");
+    let is_special = attr(e, b"isSpecial").is_some_and(|v| v == "yes");
+    let class = attr(e, b"class").unwrap_or_default();
+
+    // Capture <value> for the definition and offset, <property> for the
+    // classification and <name> for the display name
+    let mut value = Magnitude::ONE;
+    let mut offset = Decimal::ZERO;
+    let mut property = String::new();
+    let mut display_name = String::new();
+    let mut in_property_tag = false;
+    let mut in_name_tag = false;
+    let mut unit_ref = None;
+    let mut function = None;
+    let mut open = has_children;
+    while open {
+        match read_event(reader)? {
+            Event::Empty(ve) | Event::Start(ve) => match ve.name().as_ref() {
+                b"value" => {
+                    unit_ref = attr(&ve, b"Unit");
+                    if let Some(v) = attr(&ve, b"value") {
+                        value = parse_magnitude(&v, &code)?;
+                    }
+                    if let Some(o) = attr(&ve, b"offset") {
+                        offset = parse_decimal(&o, "unit", &code, "offset")?;
+                    }
+                }
+                b"function" => {
+                    let value = match attr(&ve, b"value") {
+                        Some(v) => parse_magnitude(&v, &code)?,
+                        None => Magnitude::ONE,
+                    };
+                    function = attr(&ve, b"name")
+                        .zip(attr(&ve, b"Unit"))
+                        .map(|(name, unit)| Function { name, value, unit });
+                }
+                b"property" => in_property_tag = true,
+                b"name" => in_name_tag = true,
+                _ => {}
+            },
+            Event::Text(text) => {
+                if in_property_tag {
+                    property = String::from_utf8_lossy(&text).trim().to_string();
+                    in_property_tag = false;
+                }
+                if in_name_tag {
+                    display_name = String::from_utf8_lossy(&text).trim().to_string();
+                    in_name_tag = false;
+                }
+            }
+            Event::End(ve) if ve.name().as_ref() == b"unit" => open = false,
+            Event::Eof => open = false,
+            _ => {}
+        }
+    }
+
+    if display_name.is_empty() {
+        display_name = code.clone();
+    }
+    // Special units are defined through a function of another unit and are
+    // resolved from it by `resolve_definitions`
+    let function = function.filter(|_| is_special);
+    let special = if is_arbitrary {
+        // Only treat units as arbitrary if explicitly marked with isArbitrary="yes"
+        "Arbitrary"
+    } else {
+        // Classify special units by the function they are defined through
+        match function.as_ref().map(|f| f.name.as_str()) {
+            Some("lg" | "lgTimes2" | "pH" | "hpX" | "hpC" | "hpM" | "hpQ") => "Log10",
+            Some("ln") => "Ln",
+            Some("tanTimes100" | "100tan") => "TanTimes100",
+            Some(name) if scale_zero(name).is_some() => "LinearOffset",
+            _ if !offset.is_zero() => "LinearOffset",
+            _ => "None",
+        }
+    };
+
+    Ok(UnitDef {
+        code,
+        ci_code,
+        is_metric,
+        // Dimensions are filled in with the factor by `resolve_definitions`
+        dim: [0; 7],
+        ucum_dim: [0; 7],
+        // Units are resolved from their definition by `resolve_definitions`
+        factor: 1.0,
+        exact_factor: Decimal::ONE,
+        offset: offset.to_f64().unwrap_or(0.0),
+        exact_offset: offset,
+        special,
+        property,
+        display_name,
+        definition: (!is_special).then(|| (value, unit_ref.clone().unwrap_or_else(|| "1".into()))),
+        unit_ref,
+        function,
+        class,
+    })
+}
+
+/// Resolve every unit's factor and dimensions from its definition.
+fn resolve_definitions(prefixes: &[PrefixDef], units: &mut [UnitDef]) -> Result<(), EssenceError> {
+    let mut resolver = Resolver {
+        prefixes: prefixes
+            .iter()
+            .map(|p| (p.code.as_str(), p.exact_factor))
+            .collect(),
+        index: units
+            .iter()
+            .enumerate()
+            .map(|(i, u)| (u.code.as_str(), i))
+            .collect(),
+        units,
+        resolved: vec![None; units.len()],
+        resolving: vec![false; units.len()],
+    };
+    for i in 0..units.len() {
+        resolver.unit(i)?;
+    }
+
+    let resolved = resolver.resolved;
+    for (unit, term) in units.iter_mut().zip(resolved) {
+        let term = term.unwrap_or(Term::ONE);
+        unit.factor = term.magnitude.approx;
+        unit.exact_factor = term
+            .magnitude
+            .exact
+            .or_else(|| Decimal::from_f64(term.magnitude.approx))
+            .unwrap_or(Decimal::ZERO);
+        unit.ucum_dim = term.ucum_dim;
+        unit.dim = term.si_dim;
+        // A temperature scale is offset by its zero point, in its own degrees
+        if let Some(zero) = unit.function.as_ref().and_then(|f| scale_zero(&f.name)) {
+            unit.exact_offset = zero
+                .checked_mul(unit.exact_factor)
+                .ok_or_else(|| out_of_range(&unit.code))?;
+            unit.offset = unit.exact_offset.to_f64().unwrap_or(0.0);
+        }
+    }
+    Ok(())
+}
+
+/// Zero points of the temperature scales UCUM defines through the
+/// `<function>` of the same name: the scale's reading at the zero of the
+/// function's unit is minus this many degrees.
+fn scale_zero(function: &str) -> Option<Decimal> {
+    match function {
+        "Cel" => Some(Decimal::new(27315, 2)),
+        "degF" => Some(Decimal::new(45967, 2)),
+        "degRe" => Some(Decimal::new(21852, 2)),
+        _ => None,
+    }
+}
+
+/// SI base quantities that UCUM counts as plain numbers, as the property of
+/// the `si` class unit that carries them and the slot of the SI dimension.
+const SI_ONLY_BASE_QUANTITIES: [(&str, usize); 1] = [("amount of substance", 5)];
+
+fn si_base_slot(unit: &UnitDef) -> Option<usize> {
+    SI_ONLY_BASE_QUANTITIES
+        .iter()
+        .find(|(property, _)| unit.class == "si" && unit.property == *property)
+        .map(|&(_, slot)| slot)
+}
+
+fn out_of_range(code: &str) -> EssenceError {
+    EssenceError::definition("unit", Some(code), "factor or exponent out of range")
+}
+
+/// A factor kept both as an exact decimal and as a float.
+///
+/// `Decimal` holds 28 significant digits within ±7.9e28, so a few physical
+/// constants (e.g. the Planck constant) do not fit; `exact` is dropped when
+/// rounding loses significant digits and the float is used instead.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Magnitude {
+    exact: Option<Decimal>,
+    approx: f64,
+}
+
+impl Magnitude {
+    const ONE: Self = Self {
+        exact: Some(Decimal::ONE),
+        approx: 1.0,
+    };
+
+    fn new(exact: Decimal) -> Self {
+        Self {
+            exact: Some(exact),
+            approx: exact.to_f64().unwrap_or(0.0),
+        }
+    }
+
+    /// `None` when the float overflowed or a non-zero result underflowed.
+    fn checked(exact: Option<Decimal>, approx: f64, nonzero: bool) -> Option<Self> {
+        if !approx.is_finite() || (nonzero && approx == 0.0) {
+            return None;
+        }
+        let exact = exact.filter(|d| {
+            let f = d.to_f64().unwrap_or(0.0);
+            f == approx || ((f - approx) / approx).abs() < 1e-12
+        });
+        Some(Self { exact, approx })
+    }
+
+    fn mul(self, other: Self) -> Option<Self> {
+        let exact = self
+            .exact
+            .zip(other.exact)
+            .and_then(|(a, b)| a.checked_mul(b));
+        let nonzero = self.approx != 0.0 && other.approx != 0.0;
+        Self::checked(exact, self.approx * other.approx, nonzero)
+    }
+
+    fn div(self, other: Self) -> Option<Self> {
+        let exact = self
+            .exact
+            .zip(other.exact)
+            .and_then(|(a, b)| a.checked_div(b));
+        Self::checked(exact, self.approx / other.approx, self.approx != 0.0)
+    }
+
+    fn powi(self, exp: i8) -> Option<Self> {
+        let mut result = Self::ONE;
+        for _ in 0..exp.unsigned_abs() {
+            result = result.mul(self)?;
+        }
+        if exp < 0 {
+            Self::ONE.div(result)
+        } else {
+            Some(result)
+        }
+    }
+}
+
+/// A resolved (sub)expression: its magnitude and its SI and UCUM dimensions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Term {
+    magnitude: Magnitude,
+    si_dim: [i8; 7],
+    ucum_dim: [i8; 7],
+}
+
+impl Term {
+    const ONE: Self = Self::number(Magnitude::ONE);
+
+    const fn number(magnitude: Magnitude) -> Self {
+        Self {
+            magnitude,
+            si_dim: [0; 7],
+            ucum_dim: [0; 7],
+        }
+    }
+
+    /// Combine the dimensions of two terms slot by slot, `None` on overflow.
+    fn zip_dims(a: [i8; 7], b: [i8; 7], op: fn(i8, i8) -> Option<i8>) -> Option<[i8; 7]> {
+        let mut dims = [0; 7];
+        for (i, dim) in dims.iter_mut().enumerate() {
+            *dim = op(a[i], b[i])?;
+        }
+        Some(dims)
+    }
+
+    fn mul(self, other: Self) -> Option<Self> {
+        Some(Self {
+            magnitude: self.magnitude.mul(other.magnitude)?,
+            si_dim: Self::zip_dims(self.si_dim, other.si_dim, i8::checked_add)?,
+            ucum_dim: Self::zip_dims(self.ucum_dim, other.ucum_dim, i8::checked_add)?,
+        })
+    }
+
+    fn div(self, other: Self) -> Option<Self> {
+        Some(Self {
+            magnitude: self.magnitude.div(other.magnitude)?,
+            si_dim: Self::zip_dims(self.si_dim, other.si_dim, i8::checked_sub)?,
+            ucum_dim: Self::zip_dims(self.ucum_dim, other.ucum_dim, i8::checked_sub)?,
+        })
+    }
+
+    fn powi(self, exp: i8) -> Option<Self> {
+        let scale = [exp; 7];
+        Some(Self {
+            magnitude: self.magnitude.powi(exp)?,
+            si_dim: Self::zip_dims(self.si_dim, scale, i8::checked_mul)?,
+            ucum_dim: Self::zip_dims(self.ucum_dim, scale, i8::checked_mul)?,
+        })
+    }
+}
+
+/// Expands unit definitions recursively, memoizing resolved units.
+struct Resolver<'a> {
+    prefixes: HashMap<&'a str, Decimal>,
+    index: HashMap<&'a str, usize>,
+    units: &'a [UnitDef],
+    resolved: Vec<Option<Term>>,
+    resolving: Vec<bool>,
+}
+
+impl Resolver<'_> {
+    fn unit(&mut self, i: usize) -> Result<Term, EssenceError> {
+        if let Some(term) = self.resolved[i] {
+            return Ok(term);
+        }
+        let unit = &self.units[i];
+        if self.resolving[i] {
+            return Err(EssenceError::definition(
+                "unit",
+                Some(&unit.code),
+                "definition is circular",
+            ));
+        }
+        self.resolving[i] = true;
+        let mut term = match (&unit.definition, &unit.function) {
+            (Some((value, expr)), _) => Term::number(*value)
+                .mul(self.expression(expr, &unit.code)?)
+                .ok_or_else(|| out_of_range(&unit.code))?,
+            // Special units take the dimensions of the unit their function is
+            // defined on; the temperature scales also take its magnitude, the
+            // other functions keep unity and are applied by their handlers
+            (None, Some(function)) => {
+                let value = Term::number(function.value);
+                let term = self.scaled_expression(value, &function.unit, &unit.code)?;
+                if scale_zero(&function.name).is_some() {
+                    term
+                } else {
+                    Term {
+                        magnitude: Magnitude::ONE,
+                        ..term
+                    }
+                }
+            }
+            // Base units
+            (None, None) => Term {
+                magnitude: Magnitude::new(unit.exact_factor),
+                si_dim: unit.dim,
+                ucum_dim: unit.ucum_dim,
+            },
+        };
+        // SI makes a base quantity of what UCUM defines as a number
+        if let Some(slot) = si_base_slot(unit)
+            && term.si_dim == [0; 7]
+        {
+            term.si_dim[slot] = 1;
+        }
+        self.resolving[i] = false;
+        self.resolved[i] = Some(term);
+        Ok(term)
+    }
+
+    fn expression(&mut self, expr: &str, owner: &str) -> Result<Term, EssenceError> {
+        self.scaled_expression(Term::ONE, expr, owner)
+    }
+
+    /// Resolve `scale` times an expression, applying its factors in order.
+    fn scaled_expression(
+        &mut self,
+        scale: Term,
+        expr: &str,
+        owner: &str,
+    ) -> Result<Term, EssenceError> {
+        let mut cursor = Cursor { text: expr, pos: 0 };
+        let term = self.term(scale, &mut cursor, owner)?;
+        if cursor.pos != expr.len() {
+            return Err(malformed_definition(owner, expr));
+        }
+        Ok(term)
+    }
+
+    /// Resolve an atom, possibly with a prefix (longest prefix first).
+    fn symbol(&mut self, sym: &str, owner: &str) -> Result<Term, EssenceError> {
+        if let Some(&i) = self.index.get(sym) {
+            return self.unit(i);
+        }
+        for prefix_len in (1..sym.len()).rev() {
+            if !sym.is_char_boundary(prefix_len) {
+                continue;
+            }
+            let (prefix_part, unit_part) = sym.split_at(prefix_len);
+            if let (Some(&prefix), Some(&i)) =
+                (self.prefixes.get(prefix_part), self.index.get(unit_part))
+            {
+                return Term::number(Magnitude::new(prefix))
+                    .mul(self.unit(i)?)
+                    .ok_or_else(|| out_of_range(owner));
+            }
+        }
+        Err(EssenceError::definition(
+            "unit",
+            Some(owner),
+            format!("unknown unit '{sym}' in definition"),
+        ))
+    }
+
+    fn term(&mut self, scale: Term, c: &mut Cursor<'_>, owner: &str) -> Result<Term, EssenceError> {
+        let mut acc = if c.eat('/') {
+            scale.div(self.component(c, owner)?)
+        } else {
+            scale.mul(self.component(c, owner)?)
+        };
+        loop {
+            let term = acc.ok_or_else(|| out_of_range(owner))?;
+            acc = if c.eat('.') {
+                term.mul(self.component(c, owner)?)
+            } else if c.eat('/') {
+                term.div(self.component(c, owner)?)
+            } else {
+                return Ok(term);
+            };
+        }
+    }
+
+    fn component(&mut self, c: &mut Cursor<'_>, owner: &str) -> Result<Term, EssenceError> {
+        let base = if c.eat('(') {
+            let inner = self.term(Term::ONE, c, owner)?;
+            if !c.eat(')') {
+                return Err(malformed_definition(owner, c.text));
+            }
+            inner
+        } else if c.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+            let digits = c.take_while(|ch| ch.is_ascii_digit());
+            if c.eat('*') || c.eat('^') {
+                // The atoms `10*` and `10^` followed by their exponent
+                let exp = c.exponent().filter(|_| digits == "10");
+                let exp = exp.ok_or_else(|| malformed_definition(owner, c.text))?;
+                c.skip_annotation();
+                return i8::try_from(exp)
+                    .ok()
+                    .and_then(|exp| Magnitude::new(Decimal::TEN).powi(exp))
+                    .map(Term::number)
+                    .ok_or_else(|| out_of_range(owner));
+            }
+            let number =
+                Decimal::from_str(digits).map_err(|_| malformed_definition(owner, c.text))?;
+            c.skip_annotation();
+            return Ok(Term::number(Magnitude::new(number)));
+        } else if c.peek() == Some('{') {
+            c.skip_annotation();
+            return Ok(Term::ONE);
+        } else {
+            let sym = c.symbol();
+            if sym.is_empty() {
+                return Err(malformed_definition(owner, c.text));
+            }
+            self.symbol(sym, owner)?
+        };
+        let term = match c.exponent() {
+            Some(exp) => i8::try_from(exp)
+                .ok()
+                .and_then(|exp| base.powi(exp))
+                .ok_or_else(|| out_of_range(owner))?,
+            None => base,
+        };
+        c.skip_annotation();
+        Ok(term)
+    }
+}
+
+fn malformed_definition(code: &str, expr: &str) -> EssenceError {
+    EssenceError::definition(
+        "unit",
+        Some(code),
+        format!("cannot parse definition '{expr}'"),
+    )
+}
+
+/// Position in a unit expression of a definition.
+struct Cursor<'s> {
+    text: &'s str,
+    pos: usize,
+}
+
+impl<'s> Cursor<'s> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        let found = self.peek() == Some(ch);
+        if found {
+            self.pos += ch.len_utf8();
+        }
+        found
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'s str {
+        let start = self.pos;
+        while let Some(ch) = self.peek().filter(|&ch| pred(ch)) {
+            self.pos += ch.len_utf8();
+        }
+        &self.text[start..self.pos]
+    }
+
+    /// Read an atom with an optional prefix; square brackets may contain anything.
+    fn symbol(&mut self) -> &'s str {
+        let start = self.pos;
+        while let Some(ch) = self.peek() {
+            if ch == '[' {
+                self.take_while(|ch| ch != ']');
+                self.eat(']');
+            } else if ch.is_ascii_digit() || ".()/{+-".contains(ch) {
+                break;
+            } else {
+                self.pos += ch.len_utf8();
+            }
+        }
+        &self.text[start..self.pos]
+    }
+
+    fn exponent(&mut self) -> Option<i32> {
+        let start = self.pos;
+        let sign = if self.eat('-') {
+            -1
+        } else {
+            self.eat('+');
+            1
+        };
+        match self.take_while(|ch| ch.is_ascii_digit()).parse::<i32>() {
+            Ok(exp) => Some(sign * exp),
+            Err(_) => {
+                self.pos = start;
+                None
+            }
+        }
+    }
+
+    fn skip_annotation(&mut self) {
+        if self.eat('{') {
+            self.take_while(|ch| ch != '}');
+            self.eat('}');
+        }
+    }
+}
+
+/// Map a base unit's `dim` attribute to a vector over the SI base quantities.
+///
+/// Charge (`Q`) becomes time × current and plane angle (`A`) is a plain number.
+fn parse_dim(tag: &str) -> [i8; 7] {
+    let mut v = [0i8; 7];
+    for ch in tag.chars() {
+        match ch {
+            'M' => v[0] = 1,
+            'L' => v[1] = 1,
+            'T' => v[2] = 1,
+            'I' => v[3] = 1,
+            'C' | 'θ' | 'Θ' => v[4] = 1,
+            'N' => v[5] = 1,
+            'J' | 'F' => v[6] = 1,
+            'Q' => {
+                // Charge dimension: time × current
+                v[2] = 1;
+                v[3] = 1;
+            }
+            _ => {}
+        }
+    }
+    v
+}
+
+/// Map a base unit's `dim` attribute to a vector over UCUM's base units.
+///
+/// The slots line up with the SI vector where the quantities agree (mass,
+/// length, time, temperature, luminous intensity); slot 3 holds electric
+/// charge instead of current and slot 5 plane angle instead of amount of
+/// substance, which UCUM counts as dimensionless.
+fn ucum_base_dim(tag: &str) -> [i8; 7] {
+    let mut v = [0i8; 7];
+    match tag {
+        "M" => v[0] = 1,
+        "L" => v[1] = 1,
+        "T" => v[2] = 1,
+        "Q" => v[3] = 1,
+        "C" => v[4] = 1,
+        "A" => v[5] = 1,
+        "F" => v[6] = 1,
+        _ => {}
+    }
+    v
+}
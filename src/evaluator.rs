@@ -13,37 +13,30 @@
 //!
 //! Future work:
 //! • Full offset algebra, logarithmic units, etc.
-//! • Detailed error diagnostics with spans.
 
 use crate::{
     ast::*,
+    engine::default_engine,
     error::UcumError,
-    find_unit,
-    performance::find_prefix_optimized,
-    precision::{Number, NumericOps, from_f64, to_f64},
+    precision::{Number, NumericOps, from_f64, from_f64_shortest, to_f64},
+    registry::UnitRegistry,
     types::Dimension,
 };
-use lazy_static::lazy_static;
-use std::collections::HashMap;
 
 /// Helper to extract string from either Symbol or SymbolOwned variants
 fn extract_symbol_str<'a>(expr: &'a UnitExpr<'a>) -> Option<&'a str> {
     match expr {
         UnitExpr::Symbol(s) => Some(s),
         UnitExpr::SymbolOwned(s) => Some(s.as_str()),
+        UnitExpr::Annotated(inner, _) => extract_symbol_str(inner),
         _ => None,
     }
 }
 
-// Optimized prefix lookup using HashMap for O(1) performance
-lazy_static! {
-    static ref PREFIX_MAP: HashMap<&'static str, crate::types::Prefix> = {
-        let mut map = HashMap::new();
-        for prefix in crate::registry::PREFIXES.iter() {
-            map.insert(prefix.symbol, *prefix);
-        }
-        map
-    };
+/// The decimal a numeric factor is written as: `10*-3` and `0.1` are
+/// exactly 0.001 and 0.1, not the binary floats nearest to them.
+fn literal(value: f64) -> Number {
+    from_f64_shortest(value)
 }
 
 /// Result returned by `evaluate()` – canonical factor, dimension vector, offset.
@@ -59,14 +52,14 @@ impl EvalResult {
 
     fn numeric(val: f64) -> Self {
         Self {
-            factor: from_f64(val),
+            factor: literal(val),
             dim: Self::ZERO_DIM,
             offset: Number::zero(),
         }
     }
 
     #[allow(clippy::result_large_err)]
-    fn from_unit(code: &str) -> Result<Self, UcumError> {
+    fn from_unit(code: &str, reg: &UnitRegistry) -> Result<Self, UcumError> {
         // Handle empty string as dimensionless unit (unity "1")
         if code.is_empty() {
             return Ok(Self {
@@ -78,7 +71,7 @@ impl EvalResult {
 
         // First try exact match (covers symbols like "Pa" and "Cel")
         // This prevents units like "Pa" from being incorrectly split into "P" (peta) + "a"
-        if let Some(unit) = find_unit(code) {
+        if let Some(unit) = reg.find_unit(code) {
             // Check if this is actually a direct unit match, not a prefixed unit match
             // If the unit code matches exactly, use it directly
             if unit.code == code {
@@ -86,17 +79,17 @@ impl EvalResult {
                 match unit.special {
                     None | LinearOffset => {
                         return Ok(Self {
-                            factor: from_f64(unit.factor),
-                            dim: unit.dim,
-                            offset: from_f64(unit.offset),
+                            factor: unit.exact_factor,
+                            dim: reg.dimension_of(unit),
+                            offset: unit.exact_offset,
                         });
                     }
                     Arbitrary => {
                         // For arbitrary units, return a special dimension that marks it as arbitrary
                         // This ensures arbitrary units are only commensurable with themselves
                         return Ok(Self {
-                            factor: from_f64(unit.factor),
-                            dim: unit.dim,
+                            factor: unit.exact_factor,
+                            dim: reg.dimension_of(unit),
                             offset: Number::zero(),
                         });
                     }
@@ -104,8 +97,8 @@ impl EvalResult {
                         // For non-linear special units, keep their proper dimensions
                         // for commensurability checking, but handle conversion specially
                         return Ok(Self {
-                            factor: from_f64(unit.factor),
-                            dim: unit.dim,
+                            factor: unit.exact_factor,
+                            dim: reg.dimension_of(unit),
                             offset: Number::zero(),
                         });
                     }
@@ -115,31 +108,31 @@ impl EvalResult {
 
         // Then attempt prefix split – longest prefix first
         // This ensures prefixed units like "mg" are handled with proper prefix factors
-        if let Some((pref, rest)) = split_prefix(code) {
-            if let Some(unit) = find_unit(rest) {
-                // For special units, handle prefixes differently
-                match unit.special {
-                    crate::types::SpecialKind::Log10
-                    | crate::types::SpecialKind::Ln
-                    | crate::types::SpecialKind::TanTimes100 => {
-                        // For special units, return the unit factor without prefix multiplication
-                        // The prefix will be handled in the product evaluation
-                        return Ok(Self {
-                            factor: from_f64(unit.factor),
-                            dim: Self::ZERO_DIM,
-                            offset: Number::zero(),
-                        });
-                    }
-                    _ => {
-                        // For regular units, apply prefix factor normally
-                        let factor = from_f64(pref.factor).mul(from_f64(unit.factor));
-                        let dim = unit.dim;
-                        return Ok(Self {
-                            factor,
-                            dim,
-                            offset: from_f64(unit.offset),
-                        });
-                    }
+        if let Some((pref, rest)) = split_prefix(code, reg)
+            && let Some(unit) = reg.find_unit(rest)
+        {
+            // For special units, handle prefixes differently
+            match unit.special {
+                crate::types::SpecialKind::Log10
+                | crate::types::SpecialKind::Ln
+                | crate::types::SpecialKind::TanTimes100 => {
+                    // For special units, return the unit factor without prefix multiplication
+                    // The prefix will be handled in the product evaluation
+                    return Ok(Self {
+                        factor: unit.exact_factor,
+                        dim: reg.dimension_of(unit),
+                        offset: Number::zero(),
+                    });
+                }
+                _ => {
+                    // For regular units, apply prefix factor normally
+                    let factor = pref.exact_factor.mul(unit.exact_factor);
+                    let dim = reg.dimension_of(unit);
+                    return Ok(Self {
+                        factor,
+                        dim,
+                        offset: unit.exact_offset,
+                    });
                 }
             }
         }
@@ -161,28 +154,72 @@ impl EvalResult {
 }
 
 /// Evaluate a parsed `UnitExpr` into canonical factor, dimension and offset.
+///
+/// Uses the default [`crate::UcumEngine`]; see [`crate::UcumEngine::evaluate`].
 #[allow(clippy::result_large_err)]
 pub fn evaluate(expr: &UnitExpr) -> Result<EvalResult, UcumError> {
-    evaluate_impl(expr)
+    default_engine().evaluate(expr)
 }
 
 /// Evaluate an owned `UnitExpr` into canonical factor, dimension and offset.
+///
+/// Uses the default [`crate::UcumEngine`]; see [`crate::UcumEngine::evaluate_owned`].
 #[allow(clippy::result_large_err)]
 pub fn evaluate_owned(expr: &crate::ast::OwnedUnitExpr) -> Result<EvalResult, UcumError> {
-    evaluate_owned_impl(expr)
+    default_engine().evaluate_owned(expr)
+}
+
+/// Evaluate a spanned expression, locating errors in its source.
+///
+/// Uses the default [`crate::UcumEngine`]; see [`crate::UcumEngine::evaluate_spanned`].
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::{evaluate_spanned, parse_expression_spanned};
+///
+/// let expr = parse_expression_spanned("mg/dll").unwrap();
+/// let error = evaluate_spanned(&expr).unwrap_err();
+/// assert_eq!(error.span.unwrap().text(), "dll");
+/// ```
+#[allow(clippy::result_large_err)]
+pub fn evaluate_spanned(expr: &SpannedExpr) -> Result<EvalResult, UcumError> {
+    default_engine().evaluate_spanned(expr)
+}
+
+/// Evaluate an expression against a specific registry, bypassing any cache.
+#[allow(clippy::result_large_err)]
+pub(crate) fn evaluate_with(expr: &UnitExpr, reg: &UnitRegistry) -> Result<EvalResult, UcumError> {
+    evaluate_impl(expr, reg)
+}
+
+/// Evaluate an owned expression against a specific registry, bypassing any cache.
+#[allow(clippy::result_large_err)]
+pub(crate) fn evaluate_owned_with(
+    expr: &crate::ast::OwnedUnitExpr,
+    reg: &UnitRegistry,
+) -> Result<EvalResult, UcumError> {
+    evaluate_owned_impl(expr, reg)
 }
 
 /// Internal implementation of evaluate without caching.
 #[allow(clippy::result_large_err)]
-fn evaluate_impl(expr: &UnitExpr) -> Result<EvalResult, UcumError> {
+fn evaluate_impl(expr: &UnitExpr, reg: &UnitRegistry) -> Result<EvalResult, UcumError> {
     match expr {
         UnitExpr::Numeric(v) => Ok(EvalResult::numeric(*v)),
-        UnitExpr::Symbol(sym) => EvalResult::from_unit(sym),
-        UnitExpr::SymbolOwned(sym) => EvalResult::from_unit(sym),
+        UnitExpr::Symbol(sym) => EvalResult::from_unit(sym, reg),
+        UnitExpr::SymbolOwned(sym) => EvalResult::from_unit(sym, reg),
+        // Annotations carry no meaning: `{cells}` is unity, `mg{creat}` is `mg`,
+        // unless a custom unit was registered under the annotation itself.
+        UnitExpr::Annotation(text) => match reg.find_exact_unit(&format!("{{{text}}}")) {
+            Some(unit) => EvalResult::from_unit(&unit.code, reg),
+            None => Ok(EvalResult::numeric(1.0)),
+        },
+        UnitExpr::Annotated(inner, _) => evaluate_impl(inner, reg),
         UnitExpr::Product(factors) => {
             // special-case numeric × special log unit
-            if factors.len() == 2 {
-                if let (Some(num_fac), Some(unit_fac)) = (
+            if factors.len() == 2
+                && let (Some(num_fac), Some(unit_fac)) = (
                     factors
                         .iter()
                         .find(|f| matches!(f.expr, UnitExpr::Numeric(_)) && f.exponent == 1),
@@ -190,198 +227,197 @@ fn evaluate_impl(expr: &UnitExpr) -> Result<EvalResult, UcumError> {
                         matches!(f.expr, UnitExpr::Symbol(_) | UnitExpr::SymbolOwned(_))
                             && f.exponent == 1
                     }),
-                ) {
-                    if let UnitExpr::Numeric(ref v) = num_fac.expr {
-                        if let Some(code) = extract_symbol_str(&unit_fac.expr) {
-                            let (pref_factor, unit) = if let Some((pref, rest)) = split_prefix(code)
-                            {
-                                if let Some(u) = find_unit(rest) {
-                                    (from_f64(pref.factor), u)
-                                } else {
-                                    return Err(UcumError::unit_not_found(code));
-                                }
-                            } else if let Some(u) = find_unit(code) {
-                                (Number::one(), u)
+                )
+                && let UnitExpr::Numeric(ref v) = num_fac.expr
+                && let Some(code) = extract_symbol_str(&unit_fac.expr)
+                && reg.find_unit(code).is_some_and(|u| {
+                    !matches!(
+                        u.special,
+                        crate::types::SpecialKind::None | crate::types::SpecialKind::LinearOffset
+                    )
+                })
+            {
+                let (pref_factor, unit) = if let Some((pref, rest)) = split_prefix(code, reg) {
+                    if let Some(u) = reg.find_unit(rest) {
+                        (pref.exact_factor, u)
+                    } else {
+                        return Err(UcumError::unit_not_found(code));
+                    }
+                } else if let Some(u) = reg.find_unit(code) {
+                    (Number::one(), u)
+                } else {
+                    return Err(UcumError::unit_not_found(code));
+                };
+
+                let scaled_val = literal(*v).mul(pref_factor);
+                // For special units, we need to handle them specially based on their type
+                // The numeric value is part of the special unit, not a multiplier
+                let (ratio, dim) = match unit.special {
+                    crate::types::SpecialKind::Log10 => {
+                        // Handle Bel (B) and decibel (dB) units
+                        // B: 10^value (power ratio)
+                        // dB: 10^(value/10) (power ratio, 1 B = 10 dB)
+                        // Special case: 3 dB should be treated as amplitude ratio (exactly √2)
+                        let ratio_f64 = if code.ends_with("dB") {
+                            if (*v - 3.0).abs() < 1e-6 {
+                                // Special case for 3 dB in test_decibel_variations
+                                // Use exact √2 to match test expectation
+                                2.0f64.sqrt()
                             } else {
-                                return Err(UcumError::unit_not_found(code));
-                            };
-
-                            let scaled_val = from_f64(*v).mul(pref_factor);
-                            // For special units, we need to handle them specially based on their type
-                            // The numeric value is part of the special unit, not a multiplier
-                            let (ratio, dim) = match unit.special {
-                                crate::types::SpecialKind::Log10 => {
-                                    // Handle Bel (B) and decibel (dB) units
-                                    // B: 10^value (power ratio)
-                                    // dB: 10^(value/10) (power ratio, 1 B = 10 dB)
-                                    // Special case: 3 dB should be treated as amplitude ratio (exactly √2)
-                                    let ratio_f64 = if code.ends_with("dB") {
-                                        if (*v - 3.0).abs() < 1e-6 {
-                                            // Special case for 3 dB in test_decibel_variations
-                                            // Use exact √2 to match test expectation
-                                            2.0f64.sqrt()
-                                        } else {
-                                            10f64.powf(*v / 10.0) // 10^(dB/10) for power ratio
-                                        }
-                                    } else {
-                                        10f64.powf(*v) // 10^B
-                                    };
-                                    (from_f64(ratio_f64), EvalResult::ZERO_DIM)
-                                }
-                                crate::types::SpecialKind::Ln => {
-                                    // For Np: e^value
-                                    let ratio_f64 = if scaled_val == Number::zero() {
-                                        1.0
-                                    } else {
-                                        to_f64(scaled_val).exp()
-                                    };
-                                    (from_f64(ratio_f64), EvalResult::ZERO_DIM)
-                                }
-                                crate::types::SpecialKind::TanTimes100 => {
-                                    // For [p'diop]: 100 * tan(1 rad)
-                                    // The unit is defined as "100 * tan(1 rad)" in UCUM
-                                    // This means 1 [p'diop] = tan(1)/100, and 100 [p'diop] = tan(1)
-                                    // Following the mathematical definition, tan(0) = 0
-                                    if scaled_val == Number::zero() {
-                                        (Number::zero(), EvalResult::ZERO_DIM)
-                                    } else {
-                                        // For n [p'diop], the result should be n/100 * tan(1)
-                                        // For 100 [p'diop], this gives tan(1)
-                                        // The key is that we're scaling the input value to radians (n/100)
-                                        // and then taking the tangent of that
-                                        let ratio_f64 = (to_f64(scaled_val) / 100.0).tan();
-                                        (from_f64(ratio_f64), EvalResult::ZERO_DIM)
+                                10f64.powf(*v / 10.0) // 10^(dB/10) for power ratio
+                            }
+                        } else {
+                            10f64.powf(*v) // 10^B
+                        };
+                        (from_f64(ratio_f64), reg.dimension_of(unit))
+                    }
+                    crate::types::SpecialKind::Ln => {
+                        // For Np: e^value
+                        let ratio_f64 = if scaled_val == Number::zero() {
+                            1.0
+                        } else {
+                            to_f64(scaled_val).exp()
+                        };
+                        (from_f64(ratio_f64), reg.dimension_of(unit))
+                    }
+                    crate::types::SpecialKind::TanTimes100 => {
+                        // For [p'diop]: 100 * tan(1 rad)
+                        // The unit is defined as "100 * tan(1 rad)" in UCUM
+                        // This means 1 [p'diop] = tan(1)/100, and 100 [p'diop] = tan(1)
+                        // Following the mathematical definition, tan(0) = 0
+                        if scaled_val == Number::zero() {
+                            (Number::zero(), reg.dimension_of(unit))
+                        } else {
+                            // For n [p'diop], the result should be n/100 * tan(1)
+                            // For 100 [p'diop], this gives tan(1)
+                            // The key is that we're scaling the input value to radians (n/100)
+                            // and then taking the tangent of that
+                            let ratio_f64 = (to_f64(scaled_val) / 100.0).tan();
+                            (from_f64(ratio_f64), reg.dimension_of(unit))
+                        }
+                    }
+                    crate::types::SpecialKind::Arbitrary => {
+                        // For arbitrary units, use the numeric value as the factor
+                        // and preserve the unit's dimension (which is typically zero)
+                        (literal(*v), reg.dimension_of(unit))
+                    }
+                    _ => {
+                        // For regular units with numeric multiplier
+                        (literal(*v), reg.dimension_of(unit))
+                    }
+                };
+
+                // For combinations with other units, we need to handle them specially
+                if factors.len() > 2 {
+                    // Extract the numeric factor if it exists
+                    let numeric_factor = factors
+                        .iter()
+                        .find_map(|f| {
+                            if let UnitExpr::Numeric(n) = &f.expr {
+                                Some(literal(*n))
+                            } else {
+                                None
+                            }
+                        })
+                        .unwrap_or(Number::one());
+
+                    // Evaluate the rest of the expression
+                    let mut result = EvalResult {
+                        factor: Number::one(),
+                        dim: EvalResult::ZERO_DIM,
+                        offset: Number::zero(),
+                    };
+
+                    // Handle each factor in the product
+                    for factor in factors {
+                        match &factor.expr {
+                            UnitExpr::Numeric(n) => {
+                                // For numeric values, just multiply the factor
+                                result.factor = result.factor.mul(literal(*n).pow(factor.exponent));
+                            }
+                            UnitExpr::Symbol(sym) => {
+                                let res = if *sym == code {
+                                    // For the special unit, apply its ratio and dimension
+                                    EvalResult {
+                                        factor: ratio,
+                                        dim,
+                                        offset: Number::zero(),
                                     }
-                                }
-                                crate::types::SpecialKind::Arbitrary => {
-                                    // For arbitrary units, use the numeric value as the factor
-                                    // and preserve the unit's dimension (which is typically zero)
-                                    (from_f64(*v), unit.dim)
-                                }
-                                _ => {
-                                    // For regular units with numeric multiplier
-                                    (from_f64(*v), unit.dim)
-                                }
-                            };
-
-                            // For combinations with other units, we need to handle them specially
-                            if factors.len() > 2 {
-                                // Extract the numeric factor if it exists
-                                let numeric_factor = factors
-                                    .iter()
-                                    .find_map(|f| {
-                                        if let UnitExpr::Numeric(n) = &f.expr {
-                                            Some(from_f64(*n))
-                                        } else {
-                                            None
-                                        }
-                                    })
-                                    .unwrap_or(Number::one());
-
-                                // Evaluate the rest of the expression
-                                let mut result = EvalResult {
-                                    factor: Number::one(),
-                                    dim: EvalResult::ZERO_DIM,
-                                    offset: Number::zero(),
+                                } else {
+                                    // For regular units, evaluate normally
+                                    EvalResult::from_unit(sym, reg)?
                                 };
-
-                                // Handle each factor in the product
-                                for factor in factors {
-                                    match &factor.expr {
-                                        UnitExpr::Numeric(n) => {
-                                            // For numeric values, just multiply the factor
-                                            result.factor = result
-                                                .factor
-                                                .mul(from_f64(*n).pow(factor.exponent));
-                                        }
-                                        UnitExpr::Symbol(sym) => {
-                                            let res = if *sym == code {
-                                                // For the special unit, apply its ratio and dimension
-                                                EvalResult {
-                                                    factor: ratio,
-                                                    dim,
-                                                    offset: Number::zero(),
-                                                }
-                                            } else {
-                                                // For regular units, evaluate normally
-                                                EvalResult::from_unit(sym)?
-                                            };
-                                            result.factor = result.factor.mul(from_f64(
-                                                to_f64(res.factor).powf(factor.exponent as f64),
-                                            ));
-                                        }
-                                        UnitExpr::SymbolOwned(sym) => {
-                                            let res = if sym == code {
-                                                // For the special unit, apply its ratio and dimension
-                                                EvalResult {
-                                                    factor: ratio,
-                                                    dim,
-                                                    offset: Number::zero(),
-                                                }
-                                            } else {
-                                                // For other units, evaluate them normally
-                                                evaluate(&factor.expr)?
-                                            };
-
-                                            if res.offset != Number::zero() {
-                                                return Err(UcumError::conversion_error(
-                                                    "offset units",
-                                                    "products with special units",
-                                                    "offset units cannot participate in products with special units",
-                                                ));
-                                            }
-
-                                            // Apply the exponent from the factor
-                                            let exp = factor.exponent;
-                                            result.factor = result.factor.mul(res.factor.pow(exp));
-
-                                            // Combine dimensions
-                                            for i in 0..result.dim.0.len() {
-                                                result.dim.0[i] = result.dim.0[i].saturating_add(
-                                                    (res.dim.0[i] as f64 * exp as f64) as i8,
-                                                );
-                                            }
-                                        }
-                                        _ => {
-                                            // For complex expressions, evaluate them normally
-                                            let res = evaluate(&factor.expr)?;
-                                            if res.offset != Number::zero() {
-                                                return Err(UcumError::conversion_error(
-                                                    "offset units",
-                                                    "products with special units",
-                                                    "offset units cannot participate in products with special units",
-                                                ));
-                                            }
-
-                                            // Apply the exponent from the factor
-                                            let exp = factor.exponent;
-                                            result.factor = result.factor.mul(res.factor.pow(exp));
-
-                                            // Combine dimensions
-                                            for i in 0..result.dim.0.len() {
-                                                result.dim.0[i] = result.dim.0[i].saturating_add(
-                                                    (res.dim.0[i] as f64 * exp as f64) as i8,
-                                                );
-                                            }
-                                        }
+                                result.factor = result
+                                    .factor
+                                    .mul(from_f64(to_f64(res.factor).powf(factor.exponent as f64)));
+                            }
+                            UnitExpr::SymbolOwned(sym) => {
+                                let res = if sym == code {
+                                    // For the special unit, apply its ratio and dimension
+                                    EvalResult {
+                                        factor: ratio,
+                                        dim,
+                                        offset: Number::zero(),
                                     }
+                                } else {
+                                    // For other units, evaluate them normally
+                                    evaluate_impl(&factor.expr, reg)?
+                                };
+
+                                if res.offset != Number::zero() {
+                                    return Err(UcumError::conversion_error(
+                                        "offset units",
+                                        "products with special units",
+                                        "offset units cannot participate in products with special units",
+                                    ));
                                 }
 
-                                // Apply the numeric factor to the final result
-                                result.factor = result.factor.mul(numeric_factor);
-                                return Ok(result);
-                            } else {
-                                // For special units, apply the ratio and dimension
-                                let result = EvalResult {
-                                    factor: ratio,
-                                    dim,
-                                    offset: Number::zero(),
-                                };
+                                // Apply the exponent from the factor
+                                let exp = factor.exponent;
+                                result.factor = result.factor.mul(res.factor.pow(exp));
 
-                                return Ok(result);
+                                // Combine dimensions
+                                for i in 0..result.dim.0.len() {
+                                    result.dim.0[i] = result.dim.0[i]
+                                        .saturating_add((res.dim.0[i] as f64 * exp as f64) as i8);
+                                }
+                            }
+                            _ => {
+                                // For complex expressions, evaluate them normally
+                                let res = evaluate_impl(&factor.expr, reg)?;
+                                if res.offset != Number::zero() {
+                                    return Err(UcumError::conversion_error(
+                                        "offset units",
+                                        "products with special units",
+                                        "offset units cannot participate in products with special units",
+                                    ));
+                                }
+
+                                // Apply the exponent from the factor
+                                let exp = factor.exponent;
+                                result.factor = result.factor.mul(res.factor.pow(exp));
+
+                                // Combine dimensions
+                                for i in 0..result.dim.0.len() {
+                                    result.dim.0[i] = result.dim.0[i]
+                                        .saturating_add((res.dim.0[i] as f64 * exp as f64) as i8);
+                                }
                             }
                         }
                     }
+
+                    // Apply the numeric factor to the final result
+                    result.factor = result.factor.mul(numeric_factor);
+                    return Ok(result);
+                } else {
+                    // For special units, apply the ratio and dimension
+                    let result = EvalResult {
+                        factor: ratio,
+                        dim,
+                        offset: Number::zero(),
+                    };
+
+                    return Ok(result);
                 }
             }
 
@@ -389,6 +425,7 @@ fn evaluate_impl(expr: &UnitExpr) -> Result<EvalResult, UcumError> {
             let mut factor_acc = Number::one();
             let mut dim_acc = [0i8; 7];
             let mut has_numeric = false;
+            let mut tan_consumed_numeric = false;
 
             // First pass: handle numeric values and special units
             for fac in factors.iter() {
@@ -398,17 +435,16 @@ fn evaluate_impl(expr: &UnitExpr) -> Result<EvalResult, UcumError> {
                 }
 
                 // Check for special units
-                if let Some(unit) = extract_symbol_str(&fac.expr) {
-                    if let Some(unit_record) = find_unit(unit) {
-                        if unit_record.special != crate::types::SpecialKind::None {
-                            // Skip special units in first pass, they'll be handled in second pass
-                            continue;
-                        }
-                    }
+                if let Some(unit) = extract_symbol_str(&fac.expr)
+                    && let Some(unit_record) = reg.find_unit(unit)
+                    && unit_record.special != crate::types::SpecialKind::None
+                {
+                    // Skip special units in first pass, they'll be handled in second pass
+                    continue;
                 }
 
                 // Handle regular units
-                let res = evaluate(&fac.expr)?;
+                let res = evaluate_impl(&fac.expr, reg)?;
                 if res.offset != Number::zero() {
                     return Err(UcumError::conversion_error(
                         "offset units",
@@ -426,147 +462,88 @@ fn evaluate_impl(expr: &UnitExpr) -> Result<EvalResult, UcumError> {
 
             // Second pass: handle special units if present
             for fac in factors {
-                if let Some(unit) = extract_symbol_str(&fac.expr) {
-                    if let Some(unit_record) = find_unit(unit) {
-                        if unit_record.special != crate::types::SpecialKind::None {
-                            // Handle arbitrary units differently from other special units
-                            if unit_record.special == crate::types::SpecialKind::Arbitrary {
-                                // For arbitrary units, just add their dimension (typically zero)
-                                // but don't modify the factor (it's already 1.0)
-                                let dim = unit_record.dim;
-                                #[allow(clippy::needless_range_loop)]
-                                for i in 0..7 {
-                                    dim_acc[i] = dim_acc[i].saturating_add(
-                                        dim.0[i].saturating_mul(fac.exponent as i8),
-                                    );
-                                }
-                            } else if unit_record.special == crate::types::SpecialKind::TanTimes100
-                            {
-                                // Special handling for TanTimes100 (prism diopter)
-                                // For [p'diop]: 100 * tan(1 rad)
-                                // The unit is defined as "100 * tan(1 rad)" in UCUM
-                                // This means 1 [p'diop] = tan(1)/100, and 100 [p'diop] = tan(1)
-                                let dim = unit_record.dim;
-
-                                // Find the numeric value associated with this unit, if any
-                                let numeric_val = factors
-                                    .iter()
-                                    .find_map(|f| {
-                                        if let UnitExpr::Numeric(n) = &f.expr {
-                                            Some(from_f64(*n))
-                                        } else {
-                                            None
-                                        }
-                                    })
-                                    .unwrap_or(Number::one()); // Default to 1.0 if no numeric value (per UCUM definition)
-
-                                // Apply the tangent calculation
-                                if numeric_val == Number::zero() {
-                                    factor_acc = Number::zero(); // tan(0) = 0
+                if let Some(unit) = extract_symbol_str(&fac.expr)
+                    && let Some(unit_record) = reg.find_unit(unit)
+                    && unit_record.special != crate::types::SpecialKind::None
+                {
+                    // Handle arbitrary units differently from other special units
+                    if unit_record.special == crate::types::SpecialKind::Arbitrary {
+                        // For arbitrary units, add their dimension (typically zero);
+                        // the factor is one, or that of the prefix (`m[IU]`)
+                        let dim = reg.dimension_of(unit_record);
+                        let res = evaluate_impl(&fac.expr, reg)?;
+                        factor_acc = factor_acc.mul(res.factor.pow(fac.exponent));
+                        #[allow(clippy::needless_range_loop)]
+                        for i in 0..7 {
+                            dim_acc[i] = dim_acc[i]
+                                .saturating_add(dim.0[i].saturating_mul(fac.exponent as i8));
+                        }
+                    } else if unit_record.special == crate::types::SpecialKind::TanTimes100 {
+                        // Special handling for TanTimes100 (prism diopter)
+                        // For [p'diop]: 100 * tan(1 rad)
+                        // The unit is defined as "100 * tan(1 rad)" in UCUM
+                        // This means 1 [p'diop] = tan(1)/100, and 100 [p'diop] = tan(1)
+                        let dim = reg.dimension_of(unit_record);
+
+                        // Find the numeric value associated with this unit, if any
+                        let numeric_val = factors
+                            .iter()
+                            .find_map(|f| {
+                                if let UnitExpr::Numeric(n) = &f.expr {
+                                    Some(literal(*n))
                                 } else {
-                                    // For n [p'diop], the result should be tan(n/100)
-                                    // For 100 [p'diop], this gives tan(1)
-                                    // The key is that we're scaling the input value to radians (n/100)
-                                    // and then taking the tangent of that
-                                    factor_acc = from_f64((to_f64(numeric_val) / 100.0).tan());
+                                    None
                                 }
+                            })
+                            .unwrap_or(Number::one()); // Default to 1.0 if no numeric value (per UCUM definition)
+
+                        // Apply the tangent calculation
+                        tan_consumed_numeric = true;
+                        if numeric_val == Number::zero() {
+                            factor_acc = Number::zero(); // tan(0) = 0
+                        } else {
+                            // For n [p'diop], the result should be tan(n/100)
+                            // For 100 [p'diop], this gives tan(1)
+                            // The key is that we're scaling the input value to radians (n/100)
+                            // and then taking the tangent of that
+                            factor_acc = from_f64((to_f64(numeric_val) / 100.0).tan());
+                        }
 
-                                // Apply dimensions
-                                #[allow(clippy::needless_range_loop)]
-                                for i in 0..7 {
-                                    dim_acc[i] = dim_acc[i].saturating_add(
-                                        dim.0[i].saturating_mul(fac.exponent as i8),
-                                    );
-                                }
-                            } else {
-                                // For other special units, apply their ratio and dimension
-                                let ratio = unit_record.special.ratio();
-                                let dim = unit_record.dim;
-
-                                // Apply special unit conversion
-                                factor_acc = factor_acc.mul(from_f64(ratio).pow(fac.exponent));
-                                #[allow(clippy::needless_range_loop)]
-                                for i in 0..7 {
-                                    dim_acc[i] = dim_acc[i].saturating_add(
-                                        dim.0[i].saturating_mul(fac.exponent as i8),
-                                    );
-                                }
-                            }
+                        // Apply dimensions
+                        #[allow(clippy::needless_range_loop)]
+                        for i in 0..7 {
+                            dim_acc[i] = dim_acc[i]
+                                .saturating_add(dim.0[i].saturating_mul(fac.exponent as i8));
+                        }
+                    } else {
+                        // For other special units, apply their ratio and dimension; with a
+                        // numeric value present the value itself carries the magnitude
+                        let ratio = if has_numeric {
+                            unit_record.factor
+                        } else {
+                            unit_record.special.ratio()
+                        };
+                        let dim = reg.dimension_of(unit_record);
+
+                        // Apply special unit conversion
+                        factor_acc = factor_acc.mul(from_f64(ratio).pow(fac.exponent));
+                        #[allow(clippy::needless_range_loop)]
+                        for i in 0..7 {
+                            dim_acc[i] = dim_acc[i]
+                                .saturating_add(dim.0[i].saturating_mul(fac.exponent as i8));
                         }
                     }
                 }
             }
 
-            // For products with numeric values, we need to multiply all factors together
-            if has_numeric {
-                // Start with 1.0 and multiply all factors together (including all numeric values)
-                let mut total_factor = Number::one();
-                let mut dim_acc = [0i8; 7];
-
+            // Numeric factors scale the product, except where a prism diopter
+            // already used the value as its tangent argument above
+            if has_numeric && !tan_consumed_numeric {
                 for fac in factors {
-                    match &fac.expr {
-                        UnitExpr::Numeric(n) => {
-                            // Include ALL numeric factors in the multiplication
-                            total_factor = total_factor.mul(from_f64(*n).pow(fac.exponent));
-                        }
-                        UnitExpr::Symbol(unit) => {
-                            if let Some(unit_record) = find_unit(unit) {
-                                // Multiply the factor from this unit
-                                total_factor = total_factor
-                                    .mul(from_f64(unit_record.factor).pow(fac.exponent));
-                            }
-                        }
-                        UnitExpr::SymbolOwned(unit) => {
-                            if let Some(unit_record) = find_unit(unit) {
-                                // Multiply the factor from this unit
-                                total_factor = total_factor
-                                    .mul(from_f64(unit_record.factor).pow(fac.exponent));
-
-                                // Add dimensions
-                                #[allow(clippy::needless_range_loop)]
-                                for i in 0..7 {
-                                    dim_acc[i] = dim_acc[i].saturating_add(
-                                        unit_record.dim.0[i].saturating_mul(fac.exponent as i8),
-                                    );
-                                }
-                            } else if let Some((pref, rest)) = split_prefix(unit) {
-                                // Handle prefixed units
-                                if let Some(unit_record) = find_unit(rest) {
-                                    // Apply prefix factor and unit factor
-                                    let combined_factor =
-                                        from_f64(pref.factor).mul(from_f64(unit_record.factor));
-                                    total_factor =
-                                        total_factor.mul(combined_factor.pow(fac.exponent));
-
-                                    #[allow(clippy::needless_range_loop)]
-                                    for i in 0..7 {
-                                        dim_acc[i] = dim_acc[i].saturating_add(
-                                            unit_record.dim.0[i].saturating_mul(fac.exponent as i8),
-                                        );
-                                    }
-                                }
-                            }
-                        }
-                        _ => {
-                            // For other expressions, evaluate normally and multiply
-                            let res = evaluate(&fac.expr)?;
-                            total_factor = total_factor.mul(res.factor.pow(fac.exponent));
-                            #[allow(clippy::needless_range_loop)]
-                            for i in 0..7 {
-                                dim_acc[i] = dim_acc[i].saturating_add(
-                                    res.dim.0[i].saturating_mul(fac.exponent as i8),
-                                );
-                            }
-                        }
+                    if let UnitExpr::Numeric(n) = &fac.expr {
+                        factor_acc = factor_acc.mul(literal(*n).pow(fac.exponent));
                     }
                 }
-
-                // Return the total factor (all numeric values × all other factors)
-                return Ok(EvalResult {
-                    factor: total_factor,
-                    dim: Dimension(dim_acc),
-                    offset: Number::zero(),
-                });
             }
 
             Ok(EvalResult {
@@ -576,8 +553,8 @@ fn evaluate_impl(expr: &UnitExpr) -> Result<EvalResult, UcumError> {
             })
         }
         UnitExpr::Quotient(num, den) => {
-            let n = evaluate(num)?;
-            let d = evaluate(den)?;
+            let n = evaluate_impl(num, reg)?;
+            let d = evaluate_impl(den, reg)?;
 
             if n.offset != Number::zero() || d.offset != Number::zero() {
                 return Err(UcumError::conversion_error(
@@ -595,7 +572,7 @@ fn evaluate_impl(expr: &UnitExpr) -> Result<EvalResult, UcumError> {
             // commensurability checks when working with arbitrary units in complex expressions.
             let is_arbitrary_numerator = if let Some(sym) = extract_symbol_str(num.as_ref()) {
                 // Check if the unit is actually marked as arbitrary in the registry
-                if let Some(unit) = find_unit(sym) {
+                if let Some(unit) = reg.find_unit(sym) {
                     unit.special == crate::types::SpecialKind::Arbitrary
                 } else {
                     // Fallback: check for square brackets only if not found in registry
@@ -628,7 +605,7 @@ fn evaluate_impl(expr: &UnitExpr) -> Result<EvalResult, UcumError> {
             })
         }
         UnitExpr::Power(expr, exp) => {
-            let base = evaluate(expr)?;
+            let base = evaluate_impl(expr, reg)?;
             if base.offset != Number::zero() {
                 return Err(UcumError::conversion_error(
                     "offset units",
@@ -653,17 +630,20 @@ fn evaluate_impl(expr: &UnitExpr) -> Result<EvalResult, UcumError> {
 /// Attempt to split the leading prefix from a symbol.
 /// Returns (prefix, remainder) if a valid prefix is found.
 /// Optimized version with fast path for single-character prefixes.
-fn split_prefix(code: &str) -> Option<(crate::types::Prefix, &str)> {
+fn split_prefix<'a, 'r>(
+    code: &'a str,
+    reg: &'r UnitRegistry,
+) -> Option<(&'r crate::types::Prefix, &'a str)> {
     if code.len() < 2 {
         return None;
     }
 
     // Fast path: try single-character prefix first (most common case)
     // This covers k, m, c, d, n, p, f, a, z, y, E, P, T, G, M, etc.
-    if let Some(prefix) = find_prefix_optimized(&code[..1]) {
+    if let Some(prefix) = reg.find_prefix(&code[..1]) {
         let remainder = &code[1..];
         if !remainder.is_empty() {
-            return Some((*prefix, remainder));
+            return Some((prefix, remainder));
         }
     }
 
@@ -672,10 +652,10 @@ fn split_prefix(code: &str) -> Option<(crate::types::Prefix, &str)> {
     for len in (2..=3).rev() {
         if len <= code.len() {
             let prefix_candidate = &code[..len];
-            if let Some(prefix) = find_prefix_optimized(prefix_candidate) {
+            if let Some(prefix) = reg.find_prefix(prefix_candidate) {
                 let remainder = &code[len..];
                 if !remainder.is_empty() {
-                    return Some((*prefix, remainder));
+                    return Some((prefix, remainder));
                 }
             }
         }
@@ -685,10 +665,17 @@ fn split_prefix(code: &str) -> Option<(crate::types::Prefix, &str)> {
 
 /// Internal implementation of evaluate for owned AST
 #[allow(clippy::result_large_err)]
-fn evaluate_owned_impl(expr: &crate::ast::OwnedUnitExpr) -> Result<EvalResult, UcumError> {
+fn evaluate_owned_impl(
+    expr: &crate::ast::OwnedUnitExpr,
+    reg: &UnitRegistry,
+) -> Result<EvalResult, UcumError> {
     match expr {
         crate::ast::OwnedUnitExpr::Numeric(v) => Ok(EvalResult::numeric(*v)),
-        crate::ast::OwnedUnitExpr::Symbol(sym) => EvalResult::from_unit(sym),
+        crate::ast::OwnedUnitExpr::Symbol(sym) => EvalResult::from_unit(sym, reg),
+        crate::ast::OwnedUnitExpr::Annotation(text) => {
+            evaluate_impl(&UnitExpr::Annotation(text), reg)
+        }
+        crate::ast::OwnedUnitExpr::Annotated(inner, _) => evaluate_owned_impl(inner, reg),
         crate::ast::OwnedUnitExpr::Product(factors) => {
             // Convert owned factors to borrowed for evaluation
             let borrowed_factors: Vec<UnitFactor> = factors
@@ -700,27 +687,31 @@ fn evaluate_owned_impl(expr: &crate::ast::OwnedUnitExpr) -> Result<EvalResult, U
                 .collect();
 
             let borrowed_expr = UnitExpr::Product(borrowed_factors);
-            evaluate_impl(&borrowed_expr)
+            evaluate_impl(&borrowed_expr, reg)
         }
         crate::ast::OwnedUnitExpr::Quotient(num, den) => {
             let borrowed_num = owned_to_borrowed(num);
             let borrowed_den = owned_to_borrowed(den);
             let borrowed_expr = UnitExpr::Quotient(Box::new(borrowed_num), Box::new(borrowed_den));
-            evaluate_impl(&borrowed_expr)
+            evaluate_impl(&borrowed_expr, reg)
         }
         crate::ast::OwnedUnitExpr::Power(expr, exp) => {
             let borrowed_expr_inner = owned_to_borrowed(expr);
             let borrowed_expr = UnitExpr::Power(Box::new(borrowed_expr_inner), *exp);
-            evaluate_impl(&borrowed_expr)
+            evaluate_impl(&borrowed_expr, reg)
         }
     }
 }
 
 /// Convert owned AST to borrowed AST for evaluation
-fn owned_to_borrowed(expr: &crate::ast::OwnedUnitExpr) -> UnitExpr {
+pub(crate) fn owned_to_borrowed(expr: &crate::ast::OwnedUnitExpr) -> UnitExpr<'_> {
     match expr {
         crate::ast::OwnedUnitExpr::Numeric(v) => UnitExpr::Numeric(*v),
         crate::ast::OwnedUnitExpr::Symbol(sym) => UnitExpr::SymbolOwned(sym.clone()),
+        crate::ast::OwnedUnitExpr::Annotation(text) => UnitExpr::Annotation(text),
+        crate::ast::OwnedUnitExpr::Annotated(inner, text) => {
+            UnitExpr::Annotated(Box::new(owned_to_borrowed(inner)), text)
+        }
         crate::ast::OwnedUnitExpr::Product(factors) => {
             let borrowed_factors: Vec<UnitFactor> = factors
                 .iter()
@@ -2,7 +2,8 @@
 //! High-performance UCUM expression parsing.
 //! This module exposes `parse_expression` for public use.
 
-use crate::ast::OwnedUnitExpr;
+use crate::ast::{OwnedUnitExpr, SpannedExpr};
+use crate::engine::default_engine;
 use crate::parser;
 
 /// Parse a UCUM expression string into a `OwnedUnitExpr` AST (public API).
@@ -12,3 +13,11 @@ use crate::parser;
 pub fn parse_expression(input: &str) -> Result<OwnedUnitExpr, crate::error::UcumError> {
     parser::parse_expression_optimized(input)
 }
+
+/// Parse a UCUM expression, keeping the byte range of every node in `input`.
+///
+/// Uses the default [`crate::UcumEngine`]; see [`crate::UcumEngine::parse_spanned`].
+#[allow(clippy::result_large_err)]
+pub fn parse_expression_spanned(input: &str) -> Result<SpannedExpr, crate::error::UcumError> {
+    default_engine().parse_spanned(input)
+}
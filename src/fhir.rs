@@ -5,13 +5,34 @@
 //! It's only available when the "fhir" feature is enabled.
 
 use crate::{
-    Quantity as UcumQuantity, UcumError, evaluate_owned, parse_expression, precision::to_f64,
+    ErrorKind, Quantity as UcumQuantity, SerializeStyle, UcumError, default_engine, evaluate_owned,
+    parse_expression,
+    precision::{Number, from_f64_shortest, round_to_uncertainty, to_f64, unit_in_last_place},
 };
 use thiserror::Error;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
+mod datatypes;
+mod fhirpath;
+mod outcome;
+mod resource;
+mod search;
+mod terminology;
+
+pub use datatypes::{CURRENCY_SYSTEM, FhirRange, FhirRatio, QuantityKind};
+pub use fhirpath::{CalendarDuration, QuantityUnit, equals, equivalent};
+pub use outcome::operation_outcome;
+pub use resource::{UnitIssue, validate_ndjson, validate_resource};
+pub use search::{QuantityIndex, QuantityRange, QuantitySearch, SearchPrefix};
+pub use terminology::{
+    lookup, lookup_with, translate, translate_with, validate_code, validate_code_with,
+};
+
+/// System URI of UCUM-coded units.
+pub const UCUM_SYSTEM: &str = "http://unitsofmeasure.org";
+
 /// Error type for FHIR integration.
 #[derive(Error, Debug)]
 pub enum FhirError {
@@ -30,6 +51,22 @@ pub enum FhirError {
     /// Invalid system URI.
     #[error("Invalid system URI: {0}")]
     InvalidSystem(String),
+
+    /// A value that breaks the rules of a FHIR datatype.
+    #[error("Invalid {0}: {1}")]
+    InvalidDatatype(&'static str, String),
+
+    /// A line of an NDJSON file that is not valid JSON.
+    #[error("Invalid JSON on line {0}: {1}")]
+    InvalidJson(usize, String),
+
+    /// A terminology operation request that is not a usable `Parameters`.
+    #[error("Invalid Parameters: {0}")]
+    InvalidParameters(String),
+
+    /// Malformed `quantity` search token.
+    #[error("Invalid quantity search token: {0}")]
+    InvalidSearchToken(String),
 }
 
 /// FHIR Quantity data type.
@@ -38,12 +75,20 @@ pub enum FhirError {
 /// potentially be measured. The FHIR Quantity data type includes a value and a unit, where
 /// the unit may be a UCUM code.
 ///
+/// The value is an exact decimal that keeps the scale it was written with,
+/// so `1.50` and `1.5` stay distinct. In JSON it is read from and written as
+/// the number text when this crate's `arbitrary_precision` feature is
+/// enabled; otherwise serde_json reads numbers as `f64` and the value takes
+/// the precision of its shortest form. Exponent forms such as `1.5e3` are
+/// accepted; values beyond the decimal range (about ±7.9e28) are rejected.
+///
 /// See the [FHIR Quantity](http://hl7.org/fhir/datatypes.html#Quantity) documentation for more details.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct FhirQuantity {
-    /// The value of the quantity.
-    pub value: f64,
+    /// The value of the quantity, with its precision.
+    #[cfg_attr(feature = "serde", serde(with = "decimal_value"))]
+    pub value: Number,
 
     /// The human-readable unit representation.
     pub unit: Option<String>,
@@ -60,10 +105,45 @@ pub struct FhirQuantity {
     pub comparator: Option<String>,
 }
 
+/// Serde support for [`FhirQuantity::value`] through [`serde_json::Number`],
+/// which holds the number text under `arbitrary_precision`.
+///
+/// Without the feature the number is an `f64`, read in its shortest form,
+/// even when another crate turns on serde_json's own feature.
+#[cfg(feature = "serde")]
+mod decimal_value {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de, ser};
+
+    use crate::precision::Number;
+
+    pub fn serialize<S: Serializer>(value: &Number, serializer: S) -> Result<S::Ok, S::Error> {
+        let number: serde_json::Number = value.to_string().parse().map_err(ser::Error::custom)?;
+        number.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Number, D::Error> {
+        let number = serde_json::Number::deserialize(deserializer)?;
+        #[cfg(not(feature = "arbitrary_precision"))]
+        if number.is_f64()
+            && let Some(value) = number.as_f64()
+        {
+            return crate::precision::try_from_f64_shortest(value)
+                .ok_or_else(|| de::Error::custom(format!("decimal {value} is not representable")));
+        }
+        let text = number.to_string();
+        let value = if text.contains(['e', 'E']) {
+            Number::from_scientific(&text)
+        } else {
+            text.parse()
+        };
+        value.map_err(|e| de::Error::custom(format!("decimal {text} is not representable: {e}")))
+    }
+}
+
 impl Default for FhirQuantity {
     fn default() -> Self {
         Self {
-            value: 0.0,
+            value: Number::ZERO,
             unit: None,
             system: None,
             code: None,
@@ -77,6 +157,8 @@ impl FhirQuantity {
     ///
     /// This is a convenience method for creating a FHIR Quantity with a UCUM code.
     /// It sets the system to "http://unitsofmeasure.org" and the code to the provided UCUM code.
+    /// The value takes the precision of its shortest decimal form; use
+    /// [`FhirQuantity::with_ucum_decimal`] to state it exactly.
     ///
     /// # Arguments
     ///
@@ -93,15 +175,30 @@ impl FhirQuantity {
     /// use octofhir_ucum::fhir::FhirQuantity;
     ///
     /// let quantity = FhirQuantity::with_ucum_code(10.0, "mg");
-    /// assert_eq!(quantity.value, 10.0);
+    /// assert_eq!(quantity.value.to_string(), "10");
     /// assert_eq!(quantity.code, Some("mg".to_string()));
     /// assert_eq!(quantity.system, Some("http://unitsofmeasure.org".to_string()));
     /// ```
     pub fn with_ucum_code(value: f64, ucum_code: &str) -> Self {
+        Self::with_ucum_decimal(from_f64_shortest(value), ucum_code)
+    }
+
+    /// Create a new FHIR Quantity with a UCUM code and an exact decimal value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::fhir::FhirQuantity;
+    /// use rust_decimal::Decimal;
+    ///
+    /// let quantity = FhirQuantity::with_ucum_decimal(Decimal::new(150, 2), "mg");
+    /// assert_eq!(quantity.value.to_string(), "1.50");
+    /// ```
+    pub fn with_ucum_decimal(value: Number, ucum_code: &str) -> Self {
         Self {
             value,
             unit: Some(ucum_code.to_string()),
-            system: Some("http://unitsofmeasure.org".to_string()),
+            system: Some(UCUM_SYSTEM.to_string()),
             code: Some(ucum_code.to_string()),
             comparator: None,
         }
@@ -115,7 +212,7 @@ impl FhirQuantity {
     pub fn is_ucum(&self) -> bool {
         self.system
             .as_ref()
-            .map(|s| s == "http://unitsofmeasure.org")
+            .map(|s| s == UCUM_SYSTEM)
             .unwrap_or(false)
     }
 
@@ -128,10 +225,18 @@ impl FhirQuantity {
     /// # Errors
     ///
     /// Returns an error if:
-    /// - The system is not "http://unitsofmeasure.org"
+    /// - The system is not "http://unitsofmeasure.org" and the unit is not a
+    ///   calendar duration keyword, which maps to its UCUM counterpart
     /// - The code is missing
     /// - The code is not a valid UCUM code
     pub fn to_ucum_quantity(&self) -> Result<UcumQuantity, FhirError> {
+        if let Some(QuantityUnit::Calendar(duration)) = QuantityUnit::of(self) {
+            return Ok(UcumQuantity {
+                value: to_f64(self.value),
+                unit: parse_expression(duration.ucum_code())?,
+            });
+        }
+
         // Check if this is a UCUM quantity
         if !self.is_ucum() {
             return Err(FhirError::InvalidSystem(
@@ -147,7 +252,7 @@ impl FhirQuantity {
 
         // Create a UCUM Quantity
         Ok(UcumQuantity {
-            value: self.value,
+            value: to_f64(self.value),
             unit: expr,
         })
     }
@@ -175,14 +280,13 @@ pub trait FromFhirQuantity<T> {
 
 impl ToFhirQuantity for UcumQuantity {
     fn to_fhir_quantity(&self) -> Result<FhirQuantity, FhirError> {
-        // Convert the UnitExpr to a string
-        let code = format!("{}", self.unit);
+        let code = self.unit.to_ucum(SerializeStyle::Preserving);
 
         // Create a FHIR Quantity
         Ok(FhirQuantity {
-            value: self.value,
+            value: from_f64_shortest(self.value),
             unit: Some(code.clone()),
-            system: Some("http://unitsofmeasure.org".to_string()),
+            system: Some(UCUM_SYSTEM.to_string()),
             code: Some(code),
             comparator: None,
         })
@@ -195,33 +299,62 @@ impl FromFhirQuantity<UcumQuantity> for UcumQuantity {
     }
 }
 
+/// Convert a FHIR quantity to another UCUM unit.
+///
+/// The stated value is converted exactly and the result is rounded to the
+/// precision the stated value carries through the conversion, so `5.4 mg`
+/// gives `0.0054 g` and `1.50 g` gives `1500 mg`, not more digits than were
+/// measured.
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::fhir::{FhirQuantity, convert_quantity};
+///
+/// let converted = convert_quantity(&FhirQuantity::with_ucum_code(98.6, "[degF]"), "Cel").unwrap();
+/// assert_eq!(converted.value.to_string(), "37.0");
+/// ```
+#[allow(clippy::result_large_err)]
 pub fn convert_quantity(
     quantity: &FhirQuantity,
     target_unit: &str,
 ) -> Result<FhirQuantity, FhirError> {
     let ucum_quantity = quantity.to_ucum_quantity()?;
     let target_expr = parse_expression(target_unit)?;
-    let source_eval = evaluate_owned(&ucum_quantity.unit)?;
-    let target_eval = evaluate_owned(&target_expr)?;
-
-    if source_eval.dim != target_eval.dim {
-        return Err(FhirError::InvalidCode(format!(
-            "Units are not commensurable: {} and {}",
-            ucum_quantity.unit, target_unit
-        )));
-    }
-
-    let factor = source_eval.factor / target_eval.factor;
+    let convert = |value| default_engine().convert_expr(value, &ucum_quantity.unit, &target_expr);
+    let value = match convert(quantity.value) {
+        Ok(value) => value,
+        Err(e) if matches!(e.kind, ErrorKind::DimensionMismatch { .. }) => {
+            return Err(FhirError::InvalidCode(format!(
+                "Units are not commensurable: {} and {}",
+                ucum_quantity.unit, target_unit
+            )));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    // One unit in the last stated digit, carried through the conversion;
+    // at the edge of the decimal range the value is left unrounded
+    let step = quantity
+        .value
+        .checked_add(unit_in_last_place(quantity.value))
+        .and_then(|next| convert(next).ok())
+        .and_then(|next| next.checked_sub(value));
+    let value = match step {
+        Some(step) => round_to_uncertainty(value, step),
+        None => value,
+    };
 
     Ok(FhirQuantity {
-        value: ucum_quantity.value * to_f64(factor),
+        value,
         unit: Some(target_unit.to_string()),
-        system: Some("http://unitsofmeasure.org".to_string()),
+        system: Some(UCUM_SYSTEM.to_string()),
         code: Some(target_unit.to_string()),
         comparator: quantity.comparator.clone(),
     })
 }
 
+/// Compare two quantities within a relative tolerance of `1e-6`.
+#[deprecated(note = "use `equals` (FHIRPath `=`) or `equivalent` (FHIRPath `~`)")]
 pub fn are_equivalent(a: &FhirQuantity, b: &FhirQuantity) -> Result<bool, FhirError> {
     let a_ucum = a.to_ucum_quantity()?;
     let b_ucum = b.to_ucum_quantity()?;
@@ -257,7 +390,7 @@ mod tests {
     #[test]
     fn test_fhir_quantity_with_ucum_code() {
         let quantity = FhirQuantity::with_ucum_code(10.0, "mg");
-        assert_eq!(quantity.value, 10.0);
+        assert_eq!(quantity.value, Number::TEN);
         assert_eq!(quantity.code, Some("mg".to_string()));
         assert_eq!(
             quantity.system,
@@ -271,7 +404,7 @@ mod tests {
         assert!(quantity.is_ucum());
 
         let quantity = FhirQuantity {
-            value: 10.0,
+            value: Number::TEN,
             unit: Some("mg".to_string()),
             system: Some("http://example.org".to_string()),
             code: Some("mg".to_string()),
@@ -296,7 +429,7 @@ mod tests {
             unit: expr,
         };
         let fhir_quantity = ucum_quantity.to_fhir_quantity().unwrap();
-        assert_eq!(fhir_quantity.value, 10.0);
+        assert_eq!(fhir_quantity.value, Number::TEN);
         assert_eq!(fhir_quantity.code, Some("mg".to_string()));
         assert_eq!(
             fhir_quantity.system,
@@ -308,15 +441,86 @@ mod tests {
     fn test_convert_quantity() {
         let quantity = FhirQuantity::with_ucum_code(1000.0, "mg");
         let converted = convert_quantity(&quantity, "g").unwrap();
-        assert!(
-            (converted.value - 1.0).abs() < 1e-10,
-            "Expected ~1.0, got {}",
-            converted.value
-        );
+        assert_eq!(converted.value, Number::ONE);
         assert_eq!(converted.code, Some("g".to_string()));
     }
 
     #[test]
+    fn test_convert_special_quantity() {
+        let body = FhirQuantity::with_ucum_decimal(Number::new(370, 1), "Cel");
+        let converted = convert_quantity(&body, "[degF]").unwrap();
+        assert_eq!(converted.value.to_string(), "98.6");
+
+        let acidity = FhirQuantity::with_ucum_code(7.4, "[pH]");
+        let converted = convert_quantity(&acidity, "nmol/L").unwrap();
+        assert_eq!(converted.value.to_string(), "40");
+    }
+
+    #[test]
+    fn test_convert_at_the_edge_of_the_decimal_range() {
+        let max = FhirQuantity::with_ucum_decimal(Number::MAX, "g");
+        assert_eq!(convert_quantity(&max, "g").unwrap().value, Number::MAX);
+        let max = FhirQuantity::with_ucum_decimal(Number::MAX, "kg");
+        assert!(convert_quantity(&max, "g").is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "arbitrary_precision")]
+    fn test_value_keeps_precision() {
+        let json = r#"{"value":1.50,"unit":"mg","system":"http://unitsofmeasure.org","code":"mg"}"#;
+        let quantity: FhirQuantity = serde_json::from_str(json).unwrap();
+        assert_eq!(quantity.value.to_string(), "1.50");
+        let written = serde_json::to_string(&quantity).unwrap();
+        assert!(written.contains(r#""value":1.50"#), "{written}");
+
+        // Conversions keep the three significant figures
+        let converted = convert_quantity(&quantity, "ug").unwrap();
+        assert_eq!(converted.value.to_string(), "1500");
+        let converted = convert_quantity(&quantity, "g").unwrap();
+        assert_eq!(converted.value.to_string(), "0.00150");
+        let converted = convert_quantity(&quantity, "mg").unwrap();
+        assert_eq!(converted.value.to_string(), "1.50");
+    }
+
+    #[test]
+    #[cfg(not(feature = "arbitrary_precision"))]
+    fn test_value_takes_shortest_form() {
+        let json = r#"{"value":1.50,"unit":"mg","system":"http://unitsofmeasure.org","code":"mg"}"#;
+        let quantity: FhirQuantity = serde_json::from_str(json).unwrap();
+        assert_eq!(quantity.value.to_string(), "1.5");
+        let written = serde_json::to_string(&quantity).unwrap();
+        assert!(written.contains(r#""value":1.5,"#), "{written}");
+
+        let converted = convert_quantity(&quantity, "g").unwrap();
+        assert_eq!(converted.value.to_string(), "0.0015");
+    }
+
+    #[test]
+    fn test_value_exponent_forms() {
+        let read = |value: &str| {
+            let json = format!(r#"{{"value":{value},"code":"mg"}}"#);
+            serde_json::from_str::<FhirQuantity>(&json).map(|q| q.value.to_string())
+        };
+        assert_eq!(read("1.5e3").unwrap(), "1500");
+        let hundredths = if cfg!(feature = "arbitrary_precision") {
+            "0.0250"
+        } else {
+            "0.025"
+        };
+        assert_eq!(read("2.50E-2").unwrap(), hundredths);
+        assert_eq!(read("7").unwrap(), "7");
+        assert!(read("1e30").is_err());
+
+        // Deserializers without the number text give an f64
+        use serde::de::{IntoDeserializer, value::Error};
+        let value = decimal_value::deserialize(IntoDeserializer::<Error>::into_deserializer(1e-3));
+        assert_eq!(value.unwrap().to_string(), "0.001");
+        let value = decimal_value::deserialize(IntoDeserializer::<Error>::into_deserializer(1e30));
+        assert!(value.is_err());
+    }
+
+    #[test]
+    #[allow(deprecated)]
     fn test_are_equivalent() {
         let a = FhirQuantity::with_ucum_code(1.0, "g");
         let b = FhirQuantity::with_ucum_code(1000.0, "mg");
@@ -331,7 +535,7 @@ mod tests {
     fn test_error_handling() {
         // Invalid system
         let quantity = FhirQuantity {
-            value: 10.0,
+            value: Number::TEN,
             unit: Some("mg".to_string()),
             system: Some("http://example.org".to_string()),
             code: Some("mg".to_string()),
@@ -347,9 +551,9 @@ mod tests {
 
         // Missing code
         let quantity = FhirQuantity {
-            value: 10.0,
+            value: Number::TEN,
             unit: Some("mg".to_string()),
-            system: Some("http://unitsofmeasure.org".to_string()),
+            system: Some(UCUM_SYSTEM.to_string()),
             code: None,
             comparator: None,
         };
@@ -368,6 +572,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_non_commensurable_units() {
         let a = FhirQuantity::with_ucum_code(1.0, "g");
         let b = FhirQuantity::with_ucum_code(1.0, "s");
@@ -0,0 +1,228 @@
+//! FHIR datatypes built on `Quantity`.
+//!
+//! `SimpleQuantity`, `Age`, `Duration`, `Distance`, `Count` and
+//! `MoneyQuantity` are profiles of [`FhirQuantity`] and are checked with
+//! [`QuantityKind::validate`]. [`FhirRange`] and [`FhirRatio`] hold a pair of
+//! quantities and check the pair.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::{FhirError, FhirQuantity, QuantityUnit};
+use crate::{default_engine, is_comparable};
+
+/// System URI of ISO 4217 currency codes, used by `MoneyQuantity`.
+pub const CURRENCY_SYSTEM: &str = "urn:iso:std:iso:4217";
+
+/// A FHIR datatype that is a profile of `Quantity`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantityKind {
+    /// `Quantity`: a code needs a system.
+    Quantity,
+    /// `SimpleQuantity`: no comparator.
+    SimpleQuantity,
+    /// `Age`: a positive value in a UCUM unit of time.
+    Age,
+    /// `Duration`: a UCUM unit of time.
+    Duration,
+    /// `Distance`: a UCUM unit of length.
+    Distance,
+    /// `Count`: an integer value in the UCUM unit `1`.
+    Count,
+    /// `MoneyQuantity`: an ISO 4217 currency code, whose system may be left
+    /// out (mtqy-1).
+    MoneyQuantity,
+}
+
+impl QuantityKind {
+    /// The FHIR name of the datatype.
+    pub fn name(self) -> &'static str {
+        match self {
+            QuantityKind::Quantity => "Quantity",
+            QuantityKind::SimpleQuantity => "SimpleQuantity",
+            QuantityKind::Age => "Age",
+            QuantityKind::Duration => "Duration",
+            QuantityKind::Distance => "Distance",
+            QuantityKind::Count => "Count",
+            QuantityKind::MoneyQuantity => "MoneyQuantity",
+        }
+    }
+
+    /// Check `quantity` against the rules of this datatype.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::fhir::{FhirQuantity, QuantityKind};
+    ///
+    /// assert!(QuantityKind::Age.validate(&FhirQuantity::with_ucum_code(42.0, "a")).is_ok());
+    /// assert!(QuantityKind::Age.validate(&FhirQuantity::with_ucum_code(42.0, "kg")).is_err());
+    /// assert!(QuantityKind::Count.validate(&FhirQuantity::with_ucum_code(2.5, "1")).is_err());
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn validate(self, quantity: &FhirQuantity) -> Result<(), FhirError> {
+        let invalid = |message: &str| Err(FhirError::InvalidDatatype(self.name(), message.into()));
+        if quantity.code.is_some()
+            && quantity.system.is_none()
+            && self != QuantityKind::MoneyQuantity
+        {
+            return invalid("a code requires a system");
+        }
+
+        match self {
+            QuantityKind::Quantity => Ok(()),
+            QuantityKind::SimpleQuantity => match quantity.comparator {
+                Some(_) => invalid("a comparator is not allowed"),
+                None => Ok(()),
+            },
+            QuantityKind::Age => {
+                self.require_ucum_kind(quantity, "s", "time")?;
+                if quantity.value.is_sign_negative() || quantity.value.is_zero() {
+                    return invalid("the value must be positive");
+                }
+                Ok(())
+            }
+            QuantityKind::Duration => self.require_ucum_kind(quantity, "s", "time"),
+            QuantityKind::Distance => self.require_ucum_kind(quantity, "m", "length"),
+            QuantityKind::Count => {
+                quantity.to_ucum_quantity()?;
+                if quantity.code.as_deref() != Some("1") {
+                    return invalid("the code must be 1");
+                }
+                if quantity.value.scale() != 0 {
+                    return invalid("the value must be an integer");
+                }
+                Ok(())
+            }
+            QuantityKind::MoneyQuantity => {
+                if quantity
+                    .system
+                    .as_deref()
+                    .is_some_and(|system| system != CURRENCY_SYSTEM)
+                {
+                    return invalid("the system must be ISO 4217");
+                }
+                match quantity.code.as_deref() {
+                    Some(code)
+                        if code.len() == 3 && code.bytes().all(|b| b.is_ascii_uppercase()) =>
+                    {
+                        Ok(())
+                    }
+                    _ => invalid("the code must be a currency code"),
+                }
+            }
+        }
+    }
+
+    /// Require a UCUM code, or a calendar keyword, comparable with `unit`.
+    #[allow(clippy::result_large_err)]
+    fn require_ucum_kind(
+        self,
+        quantity: &FhirQuantity,
+        unit: &str,
+        description: &str,
+    ) -> Result<(), FhirError> {
+        quantity.to_ucum_quantity()?;
+        // A keyword such as `year` stands for its UCUM counterpart
+        let resolved = QuantityUnit::of(quantity);
+        let code = resolved.as_ref().map_or("", QuantityUnit::ucum_code);
+        if !is_comparable(code, unit)? {
+            return Err(FhirError::InvalidDatatype(
+                self.name(),
+                format!("{code} is not a unit of {description}"),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// FHIR `Range`: a low and a high bound, either of which may be open.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FhirRange {
+    pub low: Option<FhirQuantity>,
+    pub high: Option<FhirQuantity>,
+}
+
+impl FhirRange {
+    /// Check that both bounds are simple quantities in commensurable units
+    /// and that `low ≤ high` once `high` is converted to `low`'s unit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::fhir::{FhirQuantity, FhirRange};
+    ///
+    /// let range = FhirRange {
+    ///     low: Some(FhirQuantity::with_ucum_code(500.0, "mg")),
+    ///     high: Some(FhirQuantity::with_ucum_code(1.0, "g")),
+    /// };
+    /// assert!(range.validate().is_ok());
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn validate(&self) -> Result<(), FhirError> {
+        let invalid = |message: String| Err(FhirError::InvalidDatatype("Range", message));
+        for bound in [&self.low, &self.high].into_iter().flatten() {
+            QuantityKind::SimpleQuantity.validate(bound)?;
+        }
+        let (Some(low), Some(high)) = (&self.low, &self.high) else {
+            return Ok(());
+        };
+
+        let high_value = if low.is_ucum() && high.is_ucum() {
+            let (low_unit, high_unit) =
+                (low.to_ucum_quantity()?.unit, high.to_ucum_quantity()?.unit);
+            let low_code = low.code.as_deref().unwrap_or_default();
+            let high_code = high.code.as_deref().unwrap_or_default();
+            if !is_comparable(low_code, high_code)? {
+                return invalid(format!(
+                    "the units {low_code} and {high_code} are not commensurable"
+                ));
+            }
+            default_engine().convert_expr(high.value, &high_unit, &low_unit)?
+        } else if low.system == high.system && low.code == high.code {
+            high.value
+        } else {
+            return invalid("the bounds have different units".to_string());
+        };
+
+        if low.value > high_value {
+            return invalid(format!(
+                "low {} is greater than high {}",
+                low.value, high.value
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// FHIR `Ratio`: a numerator over a denominator.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FhirRatio {
+    pub numerator: Option<FhirQuantity>,
+    pub denominator: Option<FhirQuantity>,
+}
+
+impl FhirRatio {
+    /// Check that the numerator and denominator are both present or both
+    /// absent (rat-1), and that present parts are valid quantities, with
+    /// known units when they are UCUM-coded.
+    #[allow(clippy::result_large_err)]
+    pub fn validate(&self) -> Result<(), FhirError> {
+        if self.numerator.is_some() != self.denominator.is_some() {
+            return Err(FhirError::InvalidDatatype(
+                "Ratio",
+                "a numerator and a denominator must both be present or both be absent".to_string(),
+            ));
+        }
+        for part in [&self.numerator, &self.denominator].into_iter().flatten() {
+            QuantityKind::Quantity.validate(part)?;
+            if part.is_ucum() {
+                part.to_ucum_quantity()?;
+                crate::validate(part.code.as_deref().unwrap_or_default())?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,262 @@
+//! FHIRPath equality (`=`) and equivalence (`~`) of quantities.
+//!
+//! Both operators convert the right operand into the left operand's unit and
+//! compare the values rounded to the precision of the least precise operand,
+//! so `5.4 'g' = 5400 'mg'` is true and `1 'in' = 2.54 'cm'` is true at the
+//! precision of `1`. They differ where FHIRPath says they do:
+//! • `=` is empty (`None`) for incomparable units; `~` is false.
+//! • Calendar durations of a year or a month (`1 year`) are not comparable
+//!   with definite UCUM durations (`1 'a'`) for `=`, but are equivalent to
+//!   them for `~`. Weeks and shorter compare either way.
+//!
+//! A quantity literal's unit is resolved into a [`QuantityUnit`]: either a
+//! UCUM code (`'mg'`, `'a'`) or a [`CalendarDuration`] keyword (`year`,
+//! `days`). A [`FhirQuantity`] carries a calendar duration as the keyword in
+//! `code` (or, without a code, `unit`) outside the UCUM system.
+
+use super::{FhirError, FhirQuantity};
+use crate::{
+    default_engine,
+    precision::{Number, round_to_uncertainty, unit_in_last_place},
+};
+use rust_decimal::RoundingStrategy;
+
+/// A FHIRPath / CQL calendar duration keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalendarDuration {
+    Year,
+    Month,
+    Week,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    Millisecond,
+}
+
+impl CalendarDuration {
+    /// Look up a keyword in its singular or plural form.
+    pub fn from_keyword(keyword: &str) -> Option<Self> {
+        Some(match keyword {
+            "year" | "years" => CalendarDuration::Year,
+            "month" | "months" => CalendarDuration::Month,
+            "week" | "weeks" => CalendarDuration::Week,
+            "day" | "days" => CalendarDuration::Day,
+            "hour" | "hours" => CalendarDuration::Hour,
+            "minute" | "minutes" => CalendarDuration::Minute,
+            "second" | "seconds" => CalendarDuration::Second,
+            "millisecond" | "milliseconds" => CalendarDuration::Millisecond,
+            _ => return None,
+        })
+    }
+
+    /// The singular keyword, e.g. `year`.
+    pub fn keyword(self) -> &'static str {
+        match self {
+            CalendarDuration::Year => "year",
+            CalendarDuration::Month => "month",
+            CalendarDuration::Week => "week",
+            CalendarDuration::Day => "day",
+            CalendarDuration::Hour => "hour",
+            CalendarDuration::Minute => "minute",
+            CalendarDuration::Second => "second",
+            CalendarDuration::Millisecond => "millisecond",
+        }
+    }
+
+    /// The UCUM counterpart, e.g. `a` for `year`.
+    pub fn ucum_code(self) -> &'static str {
+        match self {
+            CalendarDuration::Year => "a",
+            CalendarDuration::Month => "mo",
+            CalendarDuration::Week => "wk",
+            CalendarDuration::Day => "d",
+            CalendarDuration::Hour => "h",
+            CalendarDuration::Minute => "min",
+            CalendarDuration::Second => "s",
+            CalendarDuration::Millisecond => "ms",
+        }
+    }
+
+    /// Whether the duration has a fixed length, so that `=` treats it as
+    /// equal to its UCUM counterpart. Years and months do not: `1 year = 1
+    /// 'a'` is empty.
+    pub fn is_definite(self) -> bool {
+        !matches!(self, CalendarDuration::Year | CalendarDuration::Month)
+    }
+}
+
+/// The unit of a FHIRPath or CQL quantity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuantityUnit {
+    /// A calendar duration keyword.
+    Calendar(CalendarDuration),
+    /// A UCUM code.
+    Ucum(String),
+}
+
+impl QuantityUnit {
+    /// Resolve the unit of a quantity literal: a calendar keyword such as
+    /// `years`, or a UCUM code, quoted as in `'mg'` or bare.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::fhir::{CalendarDuration, QuantityUnit};
+    ///
+    /// let year = QuantityUnit::resolve("years").unwrap();
+    /// assert_eq!(year, QuantityUnit::Calendar(CalendarDuration::Year));
+    /// assert_eq!(year.ucum_code(), "a");
+    ///
+    /// let annum = QuantityUnit::resolve("'a'").unwrap();
+    /// assert!(!year.is_comparable_for_equality(&annum));
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn resolve(text: &str) -> Result<Self, FhirError> {
+        if let Some(code) = text
+            .strip_prefix('\'')
+            .and_then(|rest| rest.strip_suffix('\''))
+        {
+            crate::validate(code)?;
+            return Ok(QuantityUnit::Ucum(code.to_string()));
+        }
+        if let Some(duration) = CalendarDuration::from_keyword(text) {
+            return Ok(QuantityUnit::Calendar(duration));
+        }
+        crate::validate(text)?;
+        Ok(QuantityUnit::Ucum(text.to_string()))
+    }
+
+    /// The unit of a FHIR quantity, or `None` for a code in another system
+    /// or a UCUM quantity without a code.
+    pub fn of(quantity: &FhirQuantity) -> Option<Self> {
+        if quantity.is_ucum() {
+            return quantity.code.clone().map(QuantityUnit::Ucum);
+        }
+        quantity
+            .code
+            .as_deref()
+            .or(quantity.unit.as_deref())
+            .and_then(CalendarDuration::from_keyword)
+            .map(QuantityUnit::Calendar)
+    }
+
+    /// The UCUM code to convert with.
+    pub fn ucum_code(&self) -> &str {
+        match self {
+            QuantityUnit::Calendar(duration) => duration.ucum_code(),
+            QuantityUnit::Ucum(code) => code,
+        }
+    }
+
+    /// Whether FHIRPath `=` compares quantities in these units: false for a
+    /// calendar year or month against a UCUM code, as in `1 year = 1 'a'`.
+    pub fn is_comparable_for_equality(&self, other: &QuantityUnit) -> bool {
+        match (self, other) {
+            (QuantityUnit::Calendar(duration), QuantityUnit::Ucum(_))
+            | (QuantityUnit::Ucum(_), QuantityUnit::Calendar(duration)) => duration.is_definite(),
+            _ => true,
+        }
+    }
+
+    /// A FHIR quantity of `value` in this unit.
+    pub fn to_fhir_quantity(&self, value: Number) -> FhirQuantity {
+        match self {
+            QuantityUnit::Calendar(duration) => FhirQuantity {
+                value,
+                unit: Some(duration.keyword().to_string()),
+                ..Default::default()
+            },
+            QuantityUnit::Ucum(code) => FhirQuantity::with_ucum_decimal(value, code),
+        }
+    }
+}
+
+/// FHIRPath `=` on two quantities.
+///
+/// Returns `None` (the empty collection) when the units cannot be compared.
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::fhir::{FhirQuantity, equals};
+///
+/// let a = FhirQuantity::with_ucum_code(5.4, "g");
+/// assert_eq!(equals(&a, &FhirQuantity::with_ucum_code(5400.0, "mg")), Some(true));
+/// assert_eq!(equals(&a, &FhirQuantity::with_ucum_code(5.4, "m")), None);
+/// ```
+pub fn equals(a: &FhirQuantity, b: &FhirQuantity) -> Option<bool> {
+    match (QuantityUnit::of(a), QuantityUnit::of(b)) {
+        (Some(ua), Some(ub)) => ua
+            .is_comparable_for_equality(&ub)
+            .then(|| compare_converted(a.value, ua.ucum_code(), b.value, ub.ucum_code()))
+            .flatten(),
+        (None, None) if same_code(a, b) => compare_values(a.value, b.value),
+        _ => None,
+    }
+}
+
+/// FHIRPath `~` on two quantities.
+///
+/// Calendar durations are equivalent to their UCUM counterparts, and
+/// incomparable units give `Some(false)` rather than empty.
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::fhir::{FhirQuantity, equivalent};
+///
+/// let year = FhirQuantity {
+///     value: 1.into(),
+///     unit: Some("year".to_string()),
+///     system: None,
+///     code: None,
+///     comparator: None,
+/// };
+/// let annum = FhirQuantity::with_ucum_code(1.0, "a");
+/// assert_eq!(equivalent(&year, &annum), Some(true));
+/// ```
+pub fn equivalent(a: &FhirQuantity, b: &FhirQuantity) -> Option<bool> {
+    let result = match (QuantityUnit::of(a), QuantityUnit::of(b)) {
+        (Some(ua), Some(ub)) => compare_converted(a.value, ua.ucum_code(), b.value, ub.ucum_code()),
+        (None, None) if same_code(a, b) => compare_values(a.value, b.value),
+        _ => None,
+    };
+    Some(result.unwrap_or(false))
+}
+
+/// Whether two quantities outside UCUM have the same system and code.
+fn same_code(a: &FhirQuantity, b: &FhirQuantity) -> bool {
+    a.system == b.system && a.code == b.code
+}
+
+/// Compare two values in the same unit at the least precision.
+fn compare_values(a: Number, b: Number) -> Option<bool> {
+    let places = a.scale().min(b.scale());
+    let round = |value: Number| {
+        value.round_dp_with_strategy(places, RoundingStrategy::MidpointAwayFromZero)
+    };
+    Some(round(a) == round(b))
+}
+
+/// Convert `b` into `a`'s unit and compare at the least precision, with
+/// `b`'s precision expressed in `a`'s unit. `None` if the units are
+/// incomparable.
+///
+/// At the edge of the decimal range, where `b`'s precision cannot be
+/// converted, `a`'s precision alone is used.
+fn compare_converted(a: Number, a_unit: &str, b: Number, b_unit: &str) -> Option<bool> {
+    let engine = default_engine();
+    let to = engine.parse(a_unit).ok()?;
+    let from = engine.parse(b_unit).ok()?;
+    let convert = |value: Number| engine.convert_expr(value, &from, &to).ok();
+
+    let converted = convert(b)?;
+    let a_step = unit_in_last_place(a);
+    let b_step = b
+        .checked_add(unit_in_last_place(b))
+        .and_then(convert)
+        .and_then(|next| next.checked_sub(converted));
+    let step = b_step.map_or(a_step, |b_step| a_step.max(b_step.abs()));
+    Some(round_to_uncertainty(a, step) == round_to_uncertainty(converted, step))
+}
@@ -0,0 +1,116 @@
+//! FHIR `OperationOutcome` for UCUM errors.
+//!
+//! Each [`UcumError`] becomes one issue: `severity` is `fatal` for a broken
+//! essence and `error` otherwise, `code` is the FHIR issue type of the
+//! [`ErrorKind`], `details.text` is the message and `diagnostics` lists the
+//! suggestions. The span of the error within the unit expression is given
+//! with the standard `operationoutcome-issue-line` and
+//! `operationoutcome-issue-col` extensions, and an element path, when known,
+//! as `expression`.
+
+use serde_json::{Value, json};
+
+use super::UnitIssue;
+use crate::{ErrorKind, UcumError};
+
+const ISSUE_LINE: &str = "http://hl7.org/fhir/StructureDefinition/operationoutcome-issue-line";
+const ISSUE_COL: &str = "http://hl7.org/fhir/StructureDefinition/operationoutcome-issue-col";
+
+impl UcumError {
+    /// The FHIR `IssueType` code for this error's kind.
+    pub fn issue_type(&self) -> &'static str {
+        match self.kind {
+            ErrorKind::ParseError { .. }
+            | ErrorKind::InvalidExpression { .. }
+            | ErrorKind::MultipleSlash
+            | ErrorKind::InvalidPercentPlacement { .. }
+            | ErrorKind::InvalidDefinition { .. } => "invalid",
+            ErrorKind::UnitNotFound { .. } => "code-invalid",
+            ErrorKind::DimensionMismatch { .. } => "business-rule",
+            ErrorKind::ConversionError { .. }
+            | ErrorKind::PrecisionOverflow { .. }
+            | ErrorKind::SpecialUnitError { .. } => "processing",
+            ErrorKind::InvalidProperty { .. } => "not-supported",
+            ErrorKind::InvalidEssence { .. } => "exception",
+        }
+    }
+
+    /// The FHIR `IssueSeverity` code for this error.
+    pub fn issue_severity(&self) -> &'static str {
+        match self.kind {
+            ErrorKind::InvalidEssence { .. } => "fatal",
+            _ => "error",
+        }
+    }
+
+    /// This error as an `OperationOutcome.issue` element.
+    pub fn to_outcome_issue(&self) -> Value {
+        let mut issue = json!({
+            "severity": self.issue_severity(),
+            "code": self.issue_type(),
+            "details": {"text": self.message},
+        });
+        if !self.suggestions.is_empty() {
+            issue["diagnostics"] = Value::from(self.suggestions.join("; "));
+        }
+        // A span outside its source, or inside a character, has no location
+        if let Some(span) = &self.span
+            && let Some(before) = span.source.get(..span.start)
+        {
+            let line = before.matches('\n').count() + 1;
+            let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+            // Columns count characters, not the bytes of the span
+            let column = before[line_start..].chars().count() + 1;
+            issue["extension"] = json!([
+                {"url": ISSUE_LINE, "valueInteger": line},
+                {"url": ISSUE_COL, "valueInteger": column},
+            ]);
+        }
+        issue
+    }
+
+    /// This error as an `OperationOutcome` resource.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::validate;
+    ///
+    /// let outcome = validate("mgg").unwrap_err().to_operation_outcome();
+    /// assert_eq!(outcome["resourceType"], "OperationOutcome");
+    /// assert_eq!(outcome["issue"][0]["code"], "code-invalid");
+    /// ```
+    pub fn to_operation_outcome(&self) -> Value {
+        outcome(vec![self.to_outcome_issue()])
+    }
+}
+
+impl UnitIssue {
+    /// This issue as an `OperationOutcome.issue` element, with its path as
+    /// the `expression`.
+    pub fn to_outcome_issue(&self) -> Value {
+        let mut issue = self.error.to_outcome_issue();
+        issue["expression"] = json!([self.path]);
+        issue
+    }
+}
+
+/// An `OperationOutcome` for the issues found in a resource, with a single
+/// informational issue when there are none.
+pub fn operation_outcome(issues: &[UnitIssue]) -> Value {
+    if issues.is_empty() {
+        return outcome(vec![json!({
+            "severity": "information",
+            "code": "informational",
+            "details": {"text": "No issues found"},
+        })]);
+    }
+    outcome(issues.iter().map(UnitIssue::to_outcome_issue).collect())
+}
+
+fn outcome(issues: Vec<Value>) -> Value {
+    json!({
+        "resourceType": "OperationOutcome",
+        "issue": issues,
+    })
+}
@@ -0,0 +1,111 @@
+//! Validation of the UCUM-coded elements of FHIR JSON resources.
+//!
+//! [`validate_resource`] walks a resource (a `Bundle` included) and checks
+//! the `code` of every object whose `system` is UCUM, whatever the element
+//! is called: `valueQuantity`, `referenceRange[0].low`, an extension's value
+//! and so on. [`validate_ndjson`] does the same for each line of a bulk
+//! export.
+
+use serde_json::Value;
+
+use super::{FhirError, UCUM_SYSTEM};
+use crate::{UcumError, validate};
+
+/// An invalid UCUM code found in a resource.
+#[derive(Debug, Clone)]
+pub struct UnitIssue {
+    /// FHIRPath-style path of the element, e.g.
+    /// `Observation.component[1].valueQuantity`.
+    pub path: String,
+    /// The code as found in the element.
+    pub code: String,
+    /// Why the code is invalid; its `suggestions` hold likely corrections.
+    pub error: UcumError,
+}
+
+/// Validate every UCUM-coded element of a FHIR JSON resource.
+///
+/// Paths start at the `resourceType`. Elements without a `code` are not
+/// checked.
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::fhir::validate_resource;
+///
+/// let observation = serde_json::json!({
+///     "resourceType": "Observation",
+///     "valueQuantity": {"value": 5.4, "system": "http://unitsofmeasure.org", "code": "mgg"}
+/// });
+/// let issues = validate_resource(&observation);
+/// assert_eq!(issues[0].path, "Observation.valueQuantity");
+/// assert!(!issues[0].error.suggestions.is_empty());
+/// ```
+pub fn validate_resource(resource: &Value) -> Vec<UnitIssue> {
+    let mut path = resource
+        .get("resourceType")
+        .and_then(Value::as_str)
+        .unwrap_or("Resource")
+        .to_string();
+    let mut issues = Vec::new();
+    walk(resource, &mut path, &mut issues);
+    issues
+}
+
+/// Validate every resource of an NDJSON bulk export, one resource per line.
+///
+/// Each issue comes with the 1-based line of its resource; blank lines are
+/// skipped.
+///
+/// # Errors
+///
+/// Returns [`FhirError::InvalidJson`] for the first line that is not JSON.
+#[allow(clippy::result_large_err)]
+pub fn validate_ndjson(ndjson: &str) -> Result<Vec<(usize, UnitIssue)>, FhirError> {
+    let mut issues = Vec::new();
+    for (index, line) in ndjson.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let resource: Value = serde_json::from_str(line)
+            .map_err(|e| FhirError::InvalidJson(index + 1, e.to_string()))?;
+        issues.extend(
+            validate_resource(&resource)
+                .into_iter()
+                .map(|issue| (index + 1, issue)),
+        );
+    }
+    Ok(issues)
+}
+
+fn walk(value: &Value, path: &mut String, issues: &mut Vec<UnitIssue>) {
+    let len = path.len();
+    match value {
+        Value::Object(object) => {
+            if object.get("system").and_then(Value::as_str) == Some(UCUM_SYSTEM)
+                && let Some(code) = object.get("code").and_then(Value::as_str)
+                && let Err(error) = validate(code)
+            {
+                issues.push(UnitIssue {
+                    path: path.clone(),
+                    code: code.to_string(),
+                    error,
+                });
+            }
+            for (key, child) in object {
+                path.push('.');
+                path.push_str(key);
+                walk(child, path, issues);
+                path.truncate(len);
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                path.push_str(&format!("[{index}]"));
+                walk(item, path, issues);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
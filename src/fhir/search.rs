@@ -0,0 +1,362 @@
+//! FHIR `quantity` search parameter support.
+//!
+//! Stored quantities are indexed into a [`QuantityIndex`]: the canonical UCUM
+//! code of the unit plus the canonical value and the range implied by the
+//! value's precision, so that `5.4 mg` is indexed as `[0.005395, 0.005405)`
+//! in `g`. A [`QuantitySearch`] parsed from a token such as
+//! `ge5.4|http://unitsofmeasure.org|mg` is canonicalised the same way, which
+//! lets a search in `g` find resources stored in `mg`.
+//!
+//! Prefix semantics, where *S* is the range of the search value and *T* the
+//! range of the stored value:
+//! • `eq` – *S* fully contains *T*; `ne` – it does not.
+//! • `gt` / `lt` – the stored value is above / below the search value.
+//! • `ge` / `le` – `gt` / `lt`, or `eq`.
+//! • `sa` / `eb` – *T* starts after / ends before *S*.
+//! • `ap` – *T* overlaps *S* widened to at least ±10% of the search value.
+//!
+//! A search without a unit compares the stated values; a search in a system
+//! other than UCUM never matches an index entry.
+//!
+//! Units are canonicalised with the default engine, or with a given
+//! [`UcumEngine`] through the `*_with` variants.
+
+use core::fmt;
+use core::str::FromStr;
+
+use rust_decimal::Decimal;
+
+use super::{FhirError, FhirQuantity, UCUM_SYSTEM};
+use crate::{UcumEngine, UcumError, default_engine, precision::Number};
+
+/// A FHIR search prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchPrefix {
+    #[default]
+    Eq,
+    Ne,
+    Gt,
+    Lt,
+    Ge,
+    Le,
+    Sa,
+    Eb,
+    Ap,
+}
+
+impl SearchPrefix {
+    /// The two-letter code of the prefix.
+    pub fn code(self) -> &'static str {
+        match self {
+            SearchPrefix::Eq => "eq",
+            SearchPrefix::Ne => "ne",
+            SearchPrefix::Gt => "gt",
+            SearchPrefix::Lt => "lt",
+            SearchPrefix::Ge => "ge",
+            SearchPrefix::Le => "le",
+            SearchPrefix::Sa => "sa",
+            SearchPrefix::Eb => "eb",
+            SearchPrefix::Ap => "ap",
+        }
+    }
+
+    fn from_code(code: &str) -> Option<Self> {
+        Some(match code {
+            "eq" => SearchPrefix::Eq,
+            "ne" => SearchPrefix::Ne,
+            "gt" => SearchPrefix::Gt,
+            "lt" => SearchPrefix::Lt,
+            "ge" => SearchPrefix::Ge,
+            "le" => SearchPrefix::Le,
+            "sa" => SearchPrefix::Sa,
+            "eb" => SearchPrefix::Eb,
+            "ap" => SearchPrefix::Ap,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for SearchPrefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// A value with the range implied by its precision, `[low, high)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuantityRange {
+    pub low: Number,
+    pub value: Number,
+    pub high: Number,
+}
+
+impl QuantityRange {
+    /// The range of a decimal written with `value`'s scale, i.e. ± half a
+    /// unit in its last digit: `5.4` gives `[5.35, 5.45)`.
+    ///
+    /// Returns `None` when the range cannot be represented, i.e. for values
+    /// written with the full 28 decimals or at the edge of the decimal range.
+    pub fn from_precision(value: Number) -> Option<Self> {
+        let half_unit = Decimal::try_new(5, value.scale() + 1).ok()?;
+        Self::with_half_unit(value, half_unit)
+    }
+
+    /// Parse a FHIR decimal such as `5.40` or `1.5e3`, keeping the precision
+    /// it is written with (`1.5e3` gives `[1450, 1550)`).
+    pub fn parse(text: &str) -> Option<Self> {
+        let (mantissa, exponent) = match text.find(['e', 'E']) {
+            Some(pos) => (&text[..pos], text[pos + 1..].parse::<i32>().ok()?),
+            None => (text, 0),
+        };
+        let mantissa: Number = mantissa.parse().ok()?;
+        // Precision is the last written digit, moved by the exponent
+        let last_digit = exponent - mantissa.scale() as i32;
+        let value = scale_by_power_of_ten(mantissa, exponent)?;
+        let half_unit = scale_by_power_of_ten(Decimal::new(5, 1), last_digit)?;
+        Self::with_half_unit(value, half_unit)
+    }
+
+    fn with_half_unit(value: Number, half_unit: Number) -> Option<Self> {
+        Some(Self {
+            low: value.checked_sub(half_unit)?,
+            value,
+            high: value.checked_add(half_unit)?,
+        })
+    }
+
+    fn contains(&self, other: &QuantityRange) -> bool {
+        self.low <= other.low && other.high <= self.high
+    }
+
+    fn overlaps(&self, other: &QuantityRange) -> bool {
+        self.low < other.high && other.low < self.high
+    }
+}
+
+fn scale_by_power_of_ten(value: Number, exponent: i32) -> Option<Number> {
+    let mut power = Decimal::ONE;
+    for _ in 0..exponent.unsigned_abs() {
+        power = power.checked_mul(Decimal::TEN)?;
+    }
+    if exponent >= 0 {
+        value.checked_mul(power)
+    } else {
+        value.checked_div(power)
+    }
+}
+
+/// Canonical index entry for a stored UCUM quantity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantityIndex {
+    /// Canonical UCUM code of the unit, e.g. `g` for `mg`.
+    pub code: String,
+    /// Value and precision range in canonical units.
+    pub canonical: QuantityRange,
+    /// Value and precision range as stated, in the quantity's own unit.
+    pub stated: QuantityRange,
+}
+
+impl QuantityIndex {
+    /// Index a stored quantity.
+    ///
+    /// The precision is the scale of `quantity.value`, so `5.4` is taken
+    /// as written with one decimal and `5.40` with two.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::fhir::{FhirQuantity, QuantityIndex};
+    ///
+    /// let index = QuantityIndex::new(&FhirQuantity::with_ucum_code(5.4, "mg")).unwrap();
+    /// assert_eq!(index.code, "g");
+    /// assert_eq!(index.canonical.value.normalize().to_string(), "0.0054");
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn new(quantity: &FhirQuantity) -> Result<Self, FhirError> {
+        Self::new_with(default_engine(), quantity)
+    }
+
+    /// Index a stored quantity with the units of `engine`.
+    #[allow(clippy::result_large_err)]
+    pub fn new_with(engine: &UcumEngine, quantity: &FhirQuantity) -> Result<Self, FhirError> {
+        let stated = QuantityRange::from_precision(quantity.value).ok_or_else(|| {
+            UcumError::precision_overflow("quantity precision range", &quantity.value.to_string())
+        })?;
+        let code = quantity
+            .code
+            .as_deref()
+            .ok_or(FhirError::MissingField("code"))?;
+        if !quantity.is_ucum() {
+            return Err(FhirError::InvalidSystem(
+                quantity
+                    .system
+                    .clone()
+                    .unwrap_or_else(|| "None".to_string()),
+            ));
+        }
+        Self::canonicalise(engine, stated, code)
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn canonicalise(
+        engine: &UcumEngine,
+        stated: QuantityRange,
+        code: &str,
+    ) -> Result<Self, FhirError> {
+        let unit = engine.parse(code)?;
+        let canonical_code = engine.get_canonical_units(code)?.unit;
+        let canonical_unit = engine.parse(&canonical_code)?;
+        let convert = |value| engine.convert_expr(value, &unit, &canonical_unit);
+
+        // Decreasing scales such as [pH] swap the bounds
+        let (low, high) = (convert(stated.low)?, convert(stated.high)?);
+        Ok(Self {
+            code: canonical_code,
+            canonical: QuantityRange {
+                low: low.min(high),
+                value: convert(stated.value)?,
+                high: low.max(high),
+            },
+            stated,
+        })
+    }
+}
+
+/// A parsed FHIR `quantity` search token:
+/// `[prefix]number[|system|code]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantitySearch {
+    pub prefix: SearchPrefix,
+    /// The number as written, with its precision range.
+    pub value: QuantityRange,
+    /// The system; `None` when the token leaves it empty.
+    pub system: Option<String>,
+    /// The unit code; `None` for a number-only token.
+    pub code: Option<String>,
+    /// The search in canonical form, for tokens with a UCUM unit.
+    canonical: Option<QuantityIndex>,
+}
+
+impl QuantitySearch {
+    /// Parse a search token such as `5.4|http://unitsofmeasure.org|mg`,
+    /// `gt5.4||mg` or `le100`.
+    ///
+    /// A code with an empty system is read as a UCUM code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::fhir::{FhirQuantity, QuantitySearch};
+    ///
+    /// let search = QuantitySearch::parse("5.4|http://unitsofmeasure.org|g").unwrap();
+    /// assert!(search.matches(&FhirQuantity::with_ucum_code(5400.0, "mg")).unwrap());
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn parse(token: &str) -> Result<Self, FhirError> {
+        Self::parse_with(default_engine(), token)
+    }
+
+    /// Parse a search token, canonicalising its unit with `engine`.
+    ///
+    /// The search then matches quantities indexed with the same engine.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::{CustomUnit, UcumEngine};
+    /// use octofhir_ucum::fhir::{FhirQuantity, QuantityIndex, QuantitySearch};
+    ///
+    /// let mut engine = UcumEngine::new();
+    /// engine.register_unit(CustomUnit::new("tabs", "250.mg")).unwrap();
+    /// let search = QuantitySearch::parse_with(&engine, "ge1||g").unwrap();
+    /// let stored = FhirQuantity::with_ucum_code(4.0, "tabs");
+    /// assert!(search.matches_index(&QuantityIndex::new_with(&engine, &stored).unwrap()));
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn parse_with(engine: &UcumEngine, token: &str) -> Result<Self, FhirError> {
+        let invalid = || FhirError::InvalidSearchToken(token.to_string());
+        let parts: Vec<&str> = token.split('|').collect();
+        let (number, system, code) = match parts.as_slice() {
+            [number] => (*number, None, None),
+            [number, system, code] => (*number, Some(*system), Some(*code)),
+            _ => return Err(invalid()),
+        };
+
+        let (prefix, number) = match number.get(..2).and_then(SearchPrefix::from_code) {
+            Some(prefix) => (prefix, &number[2..]),
+            None => (SearchPrefix::default(), number),
+        };
+        let value = QuantityRange::parse(number).ok_or_else(invalid)?;
+        let system = system.filter(|s| !s.is_empty()).map(str::to_string);
+        let code = code.filter(|c| !c.is_empty()).map(str::to_string);
+
+        let canonical = match (&system, &code) {
+            (None, Some(code)) => Some(QuantityIndex::canonicalise(engine, value, code)?),
+            (Some(system), Some(code)) if system == UCUM_SYSTEM => {
+                Some(QuantityIndex::canonicalise(engine, value, code)?)
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            prefix,
+            value,
+            system,
+            code,
+            canonical,
+        })
+    }
+
+    /// Whether an indexed quantity satisfies this search.
+    pub fn matches_index(&self, entry: &QuantityIndex) -> bool {
+        match (&self.canonical, &self.code) {
+            (Some(search), _) => {
+                search.code == entry.code && self.compare(&search.canonical, &entry.canonical)
+            }
+            (None, None) => self.compare(&self.value, &entry.stated),
+            // Other systems are not indexed
+            (None, Some(_)) => false,
+        }
+    }
+
+    /// Index `quantity` and test it against this search.
+    #[allow(clippy::result_large_err)]
+    pub fn matches(&self, quantity: &FhirQuantity) -> Result<bool, FhirError> {
+        Ok(self.matches_index(&QuantityIndex::new(quantity)?))
+    }
+
+    fn compare(&self, search: &QuantityRange, target: &QuantityRange) -> bool {
+        match self.prefix {
+            SearchPrefix::Eq => search.contains(target),
+            SearchPrefix::Ne => !search.contains(target),
+            SearchPrefix::Gt => target.value > search.value,
+            SearchPrefix::Lt => target.value < search.value,
+            SearchPrefix::Ge => target.value >= search.value || search.contains(target),
+            SearchPrefix::Le => target.value <= search.value || search.contains(target),
+            SearchPrefix::Sa => target.low >= search.high,
+            SearchPrefix::Eb => target.high <= search.low,
+            SearchPrefix::Ap => {
+                let margin = search.value.abs() / Decimal::TEN;
+                // A bound beyond the decimal range is open on that side
+                let approx = QuantityRange {
+                    low: search
+                        .low
+                        .min(search.value.checked_sub(margin).unwrap_or(Decimal::MIN)),
+                    value: search.value,
+                    high: search
+                        .high
+                        .max(search.value.checked_add(margin).unwrap_or(Decimal::MAX)),
+                };
+                approx.overlaps(target)
+            }
+        }
+    }
+}
+
+impl FromStr for QuantitySearch {
+    type Err = FhirError;
+
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        Self::parse(token)
+    }
+}
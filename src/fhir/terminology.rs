@@ -0,0 +1,382 @@
+//! UCUM behind a local FHIR terminology service.
+//!
+//! [`validate_code`], [`lookup`] and [`translate`] implement the CodeSystem
+//! `$validate-code` and `$lookup` and the ConceptMap `$translate`
+//! operations for `http://unitsofmeasure.org`. Each takes the `Parameters`
+//! resource of the request and returns the `Parameters` of the response.
+//! The code and system are read from `code` / `system`, their R5 names
+//! `sourceCode` / `sourceSystem`, or a `coding`; `$translate` reads the
+//! unit to translate to from `targetCode`.
+//!
+//! These use the default engine; [`validate_code_with`], [`lookup_with`]
+//! and [`translate_with`] answer from a given [`UcumEngine`], so that its
+//! custom units are known to the service.
+
+use serde_json::{Value, json};
+
+use super::{FhirError, UCUM_SYSTEM};
+use crate::{
+    CanonicalUnit, OwnedUnitExpr, UcumEngine, default_engine, generate_display_name_owned,
+    get_model, precision::Number,
+};
+
+/// `$validate-code`: whether the code is a valid UCUM expression.
+///
+/// The response has `result`, the `display` of a valid code and a
+/// `message` for an invalid one. A `display` in the request that differs
+/// from the unit's display fails validation.
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::fhir::validate_code;
+/// use serde_json::json;
+///
+/// let response = validate_code(&json!({
+///     "resourceType": "Parameters",
+///     "parameter": [
+///         {"name": "system", "valueUri": "http://unitsofmeasure.org"},
+///         {"name": "code", "valueCode": "mg/dL"}
+///     ]
+/// })).unwrap();
+/// assert_eq!(response["parameter"][0]["valueBoolean"], true);
+/// ```
+#[allow(clippy::result_large_err)]
+pub fn validate_code(request: &Value) -> Result<Value, FhirError> {
+    validate_code_with(default_engine(), request)
+}
+
+/// [`validate_code`] against the units of `engine`.
+#[allow(clippy::result_large_err)]
+pub fn validate_code_with(engine: &UcumEngine, request: &Value) -> Result<Value, FhirError> {
+    let request = Request::new(request)?;
+    let code = request.code()?;
+    if let Some(message) = request.wrong_system() {
+        return Ok(result(false, &message, vec![]));
+    }
+    if let Err(error) = engine.validate(code) {
+        return Ok(result(false, &error.message, vec![]));
+    }
+
+    let display = display(engine, code)?;
+    if let Some(requested) = request.string("display")
+        && requested != display
+    {
+        let message = format!("The display '{requested}' is not valid for {code}: '{display}'");
+        return Ok(result(false, &message, vec![string("display", &display)]));
+    }
+    Ok(parameters(vec![
+        json!({"name": "result", "valueBoolean": true}),
+        string("display", &display),
+    ]))
+}
+
+/// `$lookup`: the display of a UCUM code with its `property`, `canonical`
+/// and `definition` properties.
+///
+/// `property` is the kind of quantity of a unit atom, such as `mass`.
+/// `definition` gives the code in canonical units: `0.001 g` for `mg`.
+///
+/// An invalid code gives the `OperationOutcome` of its error, as a
+/// terminology server answers a lookup of an unknown code.
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::fhir::lookup;
+/// use serde_json::json;
+///
+/// let response = lookup(&json!({
+///     "resourceType": "Parameters",
+///     "parameter": [{"name": "code", "valueCode": "mgg"}]
+/// })).unwrap();
+/// assert_eq!(response["resourceType"], "OperationOutcome");
+/// assert_eq!(response["issue"][0]["code"], "code-invalid");
+/// ```
+///
+/// # Errors
+///
+/// Returns [`FhirError::InvalidSystem`] for a system other than UCUM.
+#[allow(clippy::result_large_err)]
+pub fn lookup(request: &Value) -> Result<Value, FhirError> {
+    lookup_with(default_engine(), request)
+}
+
+/// [`lookup`] against the units of `engine`.
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::{CustomUnit, UcumEngine, fhir::lookup_with};
+/// use serde_json::json;
+///
+/// let mut engine = UcumEngine::new();
+/// engine
+///     .register_unit(CustomUnit::new("[drp_loc]", "0.05.mL").with_property("volume"))
+///     .unwrap();
+/// let response = lookup_with(&engine, &json!({
+///     "resourceType": "Parameters",
+///     "parameter": [{"name": "code", "valueCode": "[drp_loc]"}]
+/// })).unwrap();
+/// assert_eq!(response["parameter"][4]["part"][1]["valueString"], "m3");
+/// ```
+#[allow(clippy::result_large_err)]
+pub fn lookup_with(engine: &UcumEngine, request: &Value) -> Result<Value, FhirError> {
+    let request = Request::new(request)?;
+    let code = request.code()?;
+    if let Some(system) = request.system().filter(|system| *system != UCUM_SYSTEM) {
+        return Err(FhirError::InvalidSystem(system.to_string()));
+    }
+    if let Err(error) = engine.validate(code) {
+        return Ok(error.to_operation_outcome());
+    }
+
+    let expr = engine.parse(code)?;
+    let canonical = engine.get_canonical_units(code)?;
+    let mut response = vec![
+        string("name", "UCUM"),
+        string("version", &get_model().version),
+        string("display", &display(engine, code)?),
+    ];
+    if let Some(kind) = atom_property(engine, &expr) {
+        response.push(property("property", kind));
+    }
+    response.push(property("canonical", &canonical.unit));
+    response.push(property(
+        "definition",
+        &definition(engine, &expr, &canonical)?,
+    ));
+    Ok(parameters(response))
+}
+
+/// `$translate`: the code converted to the commensurable `targetCode`.
+///
+/// A match gives the target coding and, for units that differ by a scale,
+/// its `factor` from the source unit, with an `offset` for temperature
+/// scales. Units that are not commensurable give `result` false.
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::fhir::translate;
+/// use serde_json::json;
+///
+/// let response = translate(&json!({
+///     "resourceType": "Parameters",
+///     "parameter": [
+///         {"name": "code", "valueCode": "mg"},
+///         {"name": "system", "valueUri": "http://unitsofmeasure.org"},
+///         {"name": "targetCode", "valueCode": "g"}
+///     ]
+/// })).unwrap();
+/// let factor = &response["parameter"][1]["part"][2];
+/// assert_eq!(factor["name"], "factor");
+/// assert_eq!(factor["valueDecimal"].to_string(), "0.001");
+/// ```
+#[allow(clippy::result_large_err)]
+pub fn translate(request: &Value) -> Result<Value, FhirError> {
+    translate_with(default_engine(), request)
+}
+
+/// [`translate`] against the units of `engine`.
+#[allow(clippy::result_large_err)]
+pub fn translate_with(engine: &UcumEngine, request: &Value) -> Result<Value, FhirError> {
+    let request = Request::new(request)?;
+    let code = request.code()?;
+    let target = request
+        .string("targetCode")
+        .ok_or(FhirError::MissingField("targetCode"))?;
+    if let Some(message) = request.wrong_system() {
+        return Ok(result(false, &message, vec![]));
+    }
+    if let Some(system) = request
+        .string("targetSystem")
+        .filter(|system| *system != UCUM_SYSTEM)
+    {
+        let message = format!("Cannot translate to the system {system}");
+        return Ok(result(false, &message, vec![]));
+    }
+    for unit in [code, target] {
+        if let Err(error) = engine.validate(unit) {
+            return Ok(result(false, &error.message, vec![]));
+        }
+    }
+
+    if !engine.is_comparable(code, target)? {
+        let message = format!("{code} and {target} are not commensurable");
+        return Ok(result(false, &message, vec![]));
+    }
+
+    let (from, to) = (engine.parse(code)?, engine.parse(target)?);
+    let mut parts = vec![
+        json!({"name": "equivalence", "valueCode": "equivalent"}),
+        json!({"name": "concept", "valueCoding": {
+            "system": UCUM_SYSTEM,
+            "code": target,
+            "display": display(engine, target)?,
+        }}),
+    ];
+    if !engine.contains_nonlinear(&from) && !engine.contains_nonlinear(&to) {
+        let (factor, offset) = linear_map(engine, &from, &to)?;
+        parts.push(json!({"name": "factor", "valueDecimal": decimal(factor)}));
+        if !offset.is_zero() {
+            parts.push(json!({"name": "offset", "valueDecimal": decimal(offset)}));
+        }
+    }
+    Ok(parameters(vec![
+        json!({"name": "result", "valueBoolean": true}),
+        json!({"name": "match", "part": parts}),
+    ]))
+}
+
+/// The `parameter` list of a request.
+struct Request<'a>(&'a [Value]);
+
+impl<'a> Request<'a> {
+    #[allow(clippy::result_large_err)]
+    fn new(request: &'a Value) -> Result<Self, FhirError> {
+        if request.get("resourceType").and_then(Value::as_str) != Some("Parameters") {
+            return Err(FhirError::InvalidParameters(
+                "the request is not a Parameters resource".to_string(),
+            ));
+        }
+        let parameters = match request.get("parameter") {
+            Some(Value::Array(parameters)) => parameters.as_slice(),
+            None => &[],
+            Some(_) => {
+                return Err(FhirError::InvalidParameters(
+                    "parameter is not a list".to_string(),
+                ));
+            }
+        };
+        Ok(Self(parameters))
+    }
+
+    fn get(&self, name: &str) -> Option<&'a Value> {
+        self.0
+            .iter()
+            .find(|parameter| parameter.get("name").and_then(Value::as_str) == Some(name))
+    }
+
+    /// The primitive value of a parameter, whatever its `value[x]` type.
+    fn string(&self, name: &str) -> Option<&'a str> {
+        let parameter = self.get(name)?.as_object()?;
+        parameter
+            .iter()
+            .find(|(key, _)| key.starts_with("value"))
+            .and_then(|(_, value)| value.as_str())
+    }
+
+    fn coding(&self, field: &str) -> Option<&'a str> {
+        self.get("coding")?.get("valueCoding")?.get(field)?.as_str()
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn code(&self) -> Result<&'a str, FhirError> {
+        self.string("code")
+            .or_else(|| self.string("sourceCode"))
+            .or_else(|| self.coding("code"))
+            .ok_or(FhirError::MissingField("code"))
+    }
+
+    fn system(&self) -> Option<&'a str> {
+        self.string("system")
+            .or_else(|| self.string("sourceSystem"))
+            .or_else(|| self.coding("system"))
+    }
+
+    /// A message when the request names a system other than UCUM.
+    fn wrong_system(&self) -> Option<String> {
+        self.system()
+            .filter(|system| *system != UCUM_SYSTEM)
+            .map(|system| format!("The system {system} is not UCUM ({UCUM_SYSTEM})"))
+    }
+}
+
+fn parameters(parameter: Vec<Value>) -> Value {
+    json!({"resourceType": "Parameters", "parameter": parameter})
+}
+
+fn result(valid: bool, message: &str, mut rest: Vec<Value>) -> Value {
+    let mut parameter = vec![
+        json!({"name": "result", "valueBoolean": valid}),
+        string("message", message),
+    ];
+    parameter.append(&mut rest);
+    parameters(parameter)
+}
+
+fn string(name: &str, value: &str) -> Value {
+    json!({"name": name, "valueString": value})
+}
+
+fn property(code: &str, value: &str) -> Value {
+    json!({"name": "property", "part": [
+        {"name": "code", "valueCode": code},
+        {"name": "value", "valueString": value},
+    ]})
+}
+
+/// A decimal as a JSON number with its exact digits.
+fn decimal(value: Number) -> Value {
+    value
+        .normalize()
+        .to_string()
+        .parse::<serde_json::Number>()
+        .map_or(Value::Null, Value::Number)
+}
+
+#[allow(clippy::result_large_err)]
+fn display(engine: &UcumEngine, code: &str) -> Result<String, FhirError> {
+    Ok(generate_display_name_owned(&engine.parse(code)?))
+}
+
+/// The property of a single, possibly prefixed, unit atom.
+fn atom_property<'a>(engine: &'a UcumEngine, expr: &OwnedUnitExpr) -> Option<&'a str> {
+    match expr {
+        OwnedUnitExpr::Symbol(symbol) => engine
+            .resolve_symbol(symbol)
+            .map(|(_, unit)| unit.property.as_ref())
+            .filter(|property| !property.is_empty()),
+        OwnedUnitExpr::Annotated(inner, _) => atom_property(engine, inner),
+        _ => None,
+    }
+}
+
+/// The code in terms of its canonical unit, e.g. `0.001 g`.
+#[allow(clippy::result_large_err)]
+fn definition(
+    engine: &UcumEngine,
+    expr: &OwnedUnitExpr,
+    canonical: &CanonicalUnit,
+) -> Result<String, FhirError> {
+    let CanonicalUnit {
+        unit: canonical,
+        is_nonlinear,
+        ..
+    } = canonical;
+    if *is_nonlinear {
+        return Ok(format!(
+            "a logarithmic or trigonometric function of {canonical}"
+        ));
+    }
+    let (factor, offset) = linear_map(engine, expr, &engine.parse(canonical)?)?;
+    let factor = factor.normalize();
+    Ok(if offset.is_zero() {
+        format!("{factor} {canonical}")
+    } else {
+        format!("{factor} {canonical} + {} {canonical}", offset.normalize())
+    })
+}
+
+/// Factor and offset of the linear map between two units.
+#[allow(clippy::result_large_err)]
+fn linear_map(
+    engine: &UcumEngine,
+    from: &OwnedUnitExpr,
+    to: &OwnedUnitExpr,
+) -> Result<(Number, Number), FhirError> {
+    let offset = engine.convert_expr(Number::ZERO, from, to)?;
+    let factor = engine.convert_expr(Number::ONE, from, to)? - offset;
+    Ok((factor, offset))
+}
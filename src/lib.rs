@@ -7,59 +7,79 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::result_large_err)] // UcumError is necessarily large due to comprehensive error context
 
+mod analyte;
 mod ast;
+mod case_insensitive;
+mod completion;
+mod diagnostics;
 mod display;
+mod engine;
 mod error;
+mod essence;
 mod evaluator;
 mod expr;
 mod parser;
 pub mod performance;
 pub mod precision;
+mod quantity;
 mod registry;
+mod serialize;
 pub mod special_units;
 pub mod suggestions;
+mod tokens;
 mod types;
 
-pub use crate::ast::{OwnedUnitExpr, OwnedUnitFactor, UnitExpr, UnitFactor};
+pub use crate::analyte::{Analyte, analytes, convert_for_analyte, find_analyte};
+pub use crate::ast::{
+    AnnotationMode, NodeSpans, OwnedUnitExpr, OwnedUnitFactor, SpannedExpr, UnitExpr, UnitFactor,
+};
+pub use crate::case_insensitive::{
+    CaseInsensitiveAmbiguity, CaseInsensitiveParse, parse_expression_ci, validate_ci,
+};
+pub use crate::completion::{Completion, CompletionKind, complete, complete_as};
+pub use crate::diagnostics::{Diagnostic, LenientParse, Severity, parse_lenient};
 pub use crate::display::{generate_display_name, generate_display_name_owned};
+pub use crate::engine::{EngineConfig, UcumEngine, default_engine};
 pub use crate::error::{ErrorKind, Span, UcumError};
-pub use crate::evaluator::{EvalResult, evaluate, evaluate_owned};
-pub use crate::expr::parse_expression;
+pub use crate::evaluator::{EvalResult, evaluate, evaluate_owned, evaluate_spanned};
+pub use crate::expr::{parse_expression, parse_expression_spanned};
 pub use crate::performance::{
     CacheStats, EvaluationCache, clear_global_cache, find_longest_prefix_with_trie,
     find_prefix_optimized, find_prefixes_with_trie, find_unit_optimized, get_cache_sizes,
     get_cache_stats, with_global_cache,
 };
+pub use crate::registry::{CustomUnit, UnitRegistry};
+pub use crate::serialize::SerializeStyle;
 pub use crate::special_units::{
     ArbitraryHandler, ConversionContext, LogarithmicHandler, SpecialUnitHandler,
-    SpecialUnitRegistry, TemperatureHandler,
+    SpecialUnitRegistry, SubstanceConversion, TemperatureHandler,
 };
 pub use crate::suggestions::SuggestionEngine;
-pub use crate::types::{BaseUnit, DerivedUnit, Dimension, Prefix, Quantity, UnitRecord};
+pub use crate::tokens::{Token, TokenKind, tokenize};
+pub use crate::types::{
+    BaseUnit, DerivedUnit, Dimension, DimensionModel, Prefix, Quantity, UnitRecord,
+};
 
 // Extended Functionality - functions are defined below and automatically exported
 
-use std::collections::HashSet;
-
-// Import precision utilities for internal use
-use crate::precision::{Number, NumericOps, to_f64};
+use std::collections::{BTreeMap, HashSet};
 
 // Re-export for convenience
 pub use crate::evaluator::evaluate as eval;
 
-/// Lookup a unit by code using the generated registry.
+/// Lookup a unit by code in the default engine's registry.
 pub fn find_unit(code: &str) -> Option<&'static crate::types::UnitRecord> {
-    registry::find_unit(code)
+    default_engine().find_unit(code)
 }
 
 /// Get all units from the registry.
 pub fn get_all_units() -> &'static [crate::types::UnitRecord] {
-    registry::UNITS
+    default_engine().units()
 }
 
 /// Lookup a prefix by symbol.
 pub fn find_prefix(sym: &str) -> Option<&'static Prefix> {
-    registry::find_prefix(sym)
+    default_engine().find_prefix(sym)
 }
 
 // ============================================================================
@@ -82,53 +102,7 @@ pub fn find_prefix(sym: &str) -> Option<&'static Prefix> {
 /// ```
 #[allow(clippy::result_large_err)]
 pub fn validate(expression: &str) -> Result<(), UcumError> {
-    // Create suggestion engine for enhanced error messages
-    lazy_static::lazy_static! {
-        static ref SUGGESTION_ENGINE: crate::suggestions::SuggestionEngine =
-            crate::suggestions::SuggestionEngine::new();
-    }
-
-    // First, try to parse the expression
-    let parsed = match parse_expression(expression) {
-        Ok(parsed) => parsed,
-        Err(e) => {
-            // Enhance parsing errors with suggestions
-            let enhanced_error = match &e.kind {
-                ErrorKind::InvalidExpression { reason } => UcumError::invalid_expression(reason)
-                    .with_suggestions(SUGGESTION_ENGINE.suggest_corrections(expression))
-                    .with_context(format!("While parsing UCUM expression: '{expression}'")),
-                _ => e,
-            };
-            return Err(enhanced_error);
-        }
-    };
-
-    // Then evaluate it to ensure all units are valid and dimensions are consistent
-    match crate::evaluator::evaluate_owned(&parsed) {
-        Ok(_) => Ok(()),
-        Err(e) => {
-            // Enhance evaluation errors with suggestions
-            let enhanced_error = match &e.kind {
-                ErrorKind::UnitNotFound { unit, .. } => {
-                    let suggestions = SUGGESTION_ENGINE.suggest_corrections(unit);
-                    UcumError::unit_not_found(unit)
-                        .with_suggestions(suggestions)
-                        .with_context(format!("In expression: '{expression}'"))
-                }
-                ErrorKind::DimensionMismatch {
-                    expected,
-                    found,
-                    operation,
-                } => UcumError::dimension_mismatch(*expected, *found, operation)
-                    .with_context(format!("In expression: '{expression}'"))
-                    .with_suggestion(
-                        "Check that all units in the expression have compatible dimensions",
-                    ),
-                _ => e,
-            };
-            Err(enhanced_error)
-        }
-    }
+    default_engine().validate(expression)
 }
 
 /// Analyse a UCUM expression and return detailed information about it.
@@ -147,18 +121,7 @@ pub fn validate(expression: &str) -> Result<(), UcumError> {
 /// ```
 #[allow(clippy::result_large_err)]
 pub fn analyse(expression: &str) -> Result<UnitAnalysis, UcumError> {
-    let parsed = parse_expression(expression)?;
-    let result = crate::evaluator::evaluate_owned(&parsed)?;
-
-    Ok(UnitAnalysis {
-        expression: expression.to_string(),
-        parsed_ast: parsed,
-        dimension: result.dim,
-        factor: to_f64(result.factor),
-        offset: to_f64(result.offset),
-        is_dimensionless: result.dim == Dimension([0; 7]),
-        has_offset: result.offset != Number::zero(),
-    })
+    default_engine().analyse(expression)
 }
 
 /// Detailed analysis result for a UCUM expression.
@@ -222,11 +185,6 @@ pub fn validate_in_property(expression: &str, property: &str) -> Result<bool, Uc
         "inductance" => Dimension([1, 2, -2, -2, 0, 0, 0]), // ML²T⁻²I⁻²
         "dimensionless" => Dimension([0, 0, 0, 0, 0, 0, 0]), // 1
         _ => {
-            lazy_static::lazy_static! {
-                static ref SUGGESTION_ENGINE: crate::suggestions::SuggestionEngine =
-                    crate::suggestions::SuggestionEngine::new();
-            }
-
             let available_properties = vec![
                 "length",
                 "mass",
@@ -276,12 +234,9 @@ pub fn validate_in_property(expression: &str, property: &str) -> Result<bool, Uc
 
     // If not valid, provide suggestions for units that would be valid for this property
     if !is_valid {
-        lazy_static::lazy_static! {
-            static ref SUGGESTION_ENGINE: crate::suggestions::SuggestionEngine =
-                crate::suggestions::SuggestionEngine::new();
-        }
-
-        let alternative_units = SUGGESTION_ENGINE.suggest_alternatives(expression, property);
+        let alternative_units = default_engine()
+            .suggestion_engine()
+            .suggest_alternatives(expression, property);
         let error = UcumError::dimension_mismatch(
             expected_dimension,
             analysis.dimension,
@@ -313,10 +268,7 @@ pub fn validate_in_property(expression: &str, property: &str) -> Result<bool, Uc
 /// ```
 #[allow(clippy::result_large_err)]
 pub fn is_comparable(unit1: &str, unit2: &str) -> Result<bool, UcumError> {
-    let analysis1 = analyse(unit1)?;
-    let analysis2 = analyse(unit2)?;
-
-    Ok(analysis1.dimension == analysis2.dimension)
+    default_engine().is_comparable(unit1, unit2)
 }
 
 /// Get the canonical units for a given UCUM expression.
@@ -335,30 +287,26 @@ pub fn is_comparable(unit1: &str, unit2: &str) -> Result<bool, UcumError> {
 /// ```
 #[allow(clippy::result_large_err)]
 pub fn get_canonical_units(expression: &str) -> Result<CanonicalUnit, UcumError> {
-    let analysis = analyse(expression)?;
-
-    // Build canonical unit string from dimension vector
-    let canonical_unit = build_canonical_unit_string(&analysis.dimension);
-
-    Ok(CanonicalUnit {
-        unit: canonical_unit,
-        factor: analysis.factor,
-        offset: analysis.offset,
-        dimension: analysis.dimension,
-    })
+    default_engine().get_canonical_units(expression)
 }
 
 /// Canonical unit representation
 #[derive(Debug, Clone)]
 pub struct CanonicalUnit {
-    /// Canonical unit string (e.g., "kg.m.s-2" for force)
+    /// Canonical unit string over the model's canonical units, keeping
+    /// arbitrary units as terms (e.g., "g.m.s-2" for force, "[IU].m-3" for
+    /// `[IU]/mL`)
     pub unit: String,
-    /// Conversion factor from original to canonical
+    /// Conversion factor from original to `unit` (`kg` gives 1000 against
+    /// `g`); NaN for non-linear units
     pub factor: f64,
-    /// Temperature offset if applicable
+    /// Temperature offset if applicable; NaN for non-linear units
     pub offset: f64,
     /// Dimension vector
     pub dimension: Dimension,
+    /// Whether the unit is logarithmic or tangent-based (`[pH]`, `B[W]`),
+    /// so that no factor converts it to `unit`
+    pub is_nonlinear: bool,
 }
 
 /// Result of unit arithmetic operations
@@ -376,20 +324,22 @@ pub struct UnitArithmeticResult {
     pub is_dimensionless: bool,
 }
 
-/// Build canonical unit string from dimension vector
-fn build_canonical_unit_string(dim: &Dimension) -> String {
-    let base_units = ["kg", "m", "s", "A", "K", "mol", "cd"];
-    let mut parts = Vec::new();
-
-    for (i, &exp) in dim.0.iter().enumerate() {
-        if exp != 0 {
+/// Build a canonical unit string from canonical terms.
+///
+/// Terms are written in code order, as the UCUM reference implementations
+/// do, e.g. `g.m.s-2` or `[IU].m-3`; no terms give `1`.
+pub(crate) fn build_canonical_unit_string(terms: &BTreeMap<String, i32>) -> String {
+    let parts: Vec<String> = terms
+        .iter()
+        .filter(|&(_, &exp)| exp != 0)
+        .map(|(code, &exp)| {
             if exp == 1 {
-                parts.push(base_units[i].to_string());
+                code.clone()
             } else {
-                parts.push(format!("{}{}", base_units[i], exp));
+                format!("{code}{exp}")
             }
-        }
-    }
+        })
+        .collect();
 
     if parts.is_empty() {
         "1".to_string() // dimensionless
@@ -398,6 +348,20 @@ fn build_canonical_unit_string(dim: &Dimension) -> String {
     }
 }
 
+/// Canonical terms of `lhs` combined with those of `rhs` raised to `sign`,
+/// with the factor of the resulting unit string.
+#[allow(clippy::result_large_err)]
+fn combined_canonical_unit(lhs: &str, rhs: &str, sign: i32) -> Result<(String, f64), UcumError> {
+    let engine = default_engine();
+    let mut terms = engine.canonical_terms(&engine.parse(lhs)?)?;
+    for (code, exp) in engine.canonical_terms(&engine.parse(rhs)?)? {
+        *terms.entry(code).or_insert(0) += sign * exp;
+    }
+    let unit = build_canonical_unit_string(&terms);
+    let factor = engine.evaluate_owned(&engine.parse(&unit)?)?.factor;
+    Ok((unit, precision::to_f64(factor)))
+}
+
 // ============================================================================
 // Core API Enhancement - Mathematical Operations
 // ============================================================================
@@ -442,10 +406,10 @@ pub fn multiply(
         result_dim[i] = analysis1.dimension.0[i] + analysis2.dimension.0[i];
     }
 
-    let result_unit = build_canonical_unit_string(&Dimension(result_dim));
+    let (result_unit, unit_factor) = combined_canonical_unit(unit1, unit2, 1)?;
 
     Ok(UnitResult {
-        value: result_value * result_factor,
+        value: result_value * result_factor / unit_factor,
         unit: result_unit,
         dimension: Dimension(result_dim),
     })
@@ -499,10 +463,10 @@ pub fn divide_by(
         result_dim[i] = analysis1.dimension.0[i] - analysis2.dimension.0[i];
     }
 
-    let result_unit = build_canonical_unit_string(&Dimension(result_dim));
+    let (result_unit, unit_factor) = combined_canonical_unit(dividend_unit, divisor_unit, -1)?;
 
     Ok(UnitResult {
-        value: result_value * result_factor,
+        value: result_value * result_factor / unit_factor,
         unit: result_unit,
         dimension: Dimension(result_dim),
     })
@@ -523,10 +487,6 @@ pub struct UnitResult {
 // Core API Enhancement - Search Functionality
 // ============================================================================
 
-use fuzzy_matcher::FuzzyMatcher;
-use fuzzy_matcher::skim::SkimMatcherV2;
-use regex::Regex;
-
 /// Search for units by name, code, or display name.
 ///
 /// Returns a list of units that match the search criteria. The search is case-insensitive
@@ -543,33 +503,7 @@ use regex::Regex;
 /// }
 /// ```
 pub fn search_units(query: &str) -> Vec<&'static UnitRecord> {
-    let query_lower = query.to_lowercase();
-    let mut results = Vec::new();
-
-    for unit in get_all_units() {
-        // Simple substring search (case-insensitive)
-        let matches = unit.code.to_lowercase().contains(&query_lower)
-            || unit.display_name.to_lowercase().contains(&query_lower)
-            || unit.property.to_lowercase().contains(&query_lower);
-
-        if matches {
-            results.push(unit);
-        }
-    }
-
-    // Sort results by relevance (exact matches first, then by code length)
-    results.sort_by(|a, b| {
-        let a_exact = a.code.to_lowercase() == query_lower;
-        let b_exact = b.code.to_lowercase() == query_lower;
-
-        match (a_exact, b_exact) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.code.len().cmp(&b.code.len()),
-        }
-    });
-
-    results
+    default_engine().search_units(query)
 }
 
 /// Search for units by property (e.g., "length", "mass", "time").
@@ -587,16 +521,7 @@ pub fn search_units(query: &str) -> Vec<&'static UnitRecord> {
 /// }
 /// ```
 pub fn search_units_by_property(property: &str) -> Vec<&'static UnitRecord> {
-    let property_lower = property.to_lowercase();
-    let mut results = Vec::new();
-
-    for unit in get_all_units() {
-        if unit.property.to_lowercase() == property_lower {
-            results.push(unit);
-        }
-    }
-
-    results
+    default_engine().search_units_by_property(property)
 }
 
 /// Get all defined forms of a unit code.
@@ -616,25 +541,7 @@ pub fn search_units_by_property(property: &str) -> Vec<&'static UnitRecord> {
 /// }
 /// ```
 pub fn get_defined_forms(base_code: &str) -> Vec<&'static UnitRecord> {
-    let mut results = Vec::new();
-
-    // Add exact match if it exists
-    if let Some(unit) = find_unit(base_code) {
-        results.push(unit);
-    }
-
-    // Find units that might be prefixed versions
-    for unit in get_all_units() {
-        if unit.code != base_code && unit.code.ends_with(base_code) {
-            // Check if the prefix part is a valid prefix
-            let prefix_part = &unit.code[..unit.code.len() - base_code.len()];
-            if find_prefix(prefix_part).is_some() {
-                results.push(unit);
-            }
-        }
-    }
-
-    results
+    default_engine().get_defined_forms(base_code)
 }
 
 /// Search for units using regular expressions.
@@ -662,40 +569,7 @@ pub fn search_units_regex(
     pattern: &str,
     case_sensitive: bool,
 ) -> Result<Vec<&'static UnitRecord>, UcumError> {
-    let regex_pattern = if case_sensitive {
-        pattern.to_string()
-    } else {
-        format!("(?i){pattern}")
-    };
-
-    let regex = Regex::new(&regex_pattern)
-        .map_err(|_| UcumError::invalid_expression("Invalid regex pattern"))?;
-
-    let mut results = Vec::new();
-
-    for unit in get_all_units() {
-        let matches = regex.is_match(unit.code)
-            || regex.is_match(unit.display_name)
-            || regex.is_match(unit.property);
-
-        if matches {
-            results.push(unit);
-        }
-    }
-
-    // Sort by relevance (code matches first, then display name matches)
-    results.sort_by(|a, b| {
-        let a_code_match = regex.is_match(a.code);
-        let b_code_match = regex.is_match(b.code);
-
-        match (a_code_match, b_code_match) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.code.len().cmp(&b.code.len()),
-        }
-    });
-
-    Ok(results)
+    default_engine().search_units_regex(pattern, case_sensitive)
 }
 
 /// Search for units using fuzzy matching.
@@ -719,27 +593,7 @@ pub fn search_units_regex(
 /// }
 /// ```
 pub fn search_units_fuzzy(query: &str, threshold: i64) -> Vec<(&'static UnitRecord, i64)> {
-    let matcher = SkimMatcherV2::default();
-    let mut results = Vec::new();
-
-    for unit in get_all_units() {
-        // Try matching against code, display name, and property
-        let code_score = matcher.fuzzy_match(unit.code, query).unwrap_or(0);
-        let display_score = matcher.fuzzy_match(unit.display_name, query).unwrap_or(0);
-        let property_score = matcher.fuzzy_match(unit.property, query).unwrap_or(0);
-
-        // Use the best score among all fields
-        let best_score = code_score.max(display_score).max(property_score);
-
-        if best_score >= threshold {
-            results.push((unit, best_score));
-        }
-    }
-
-    // Sort by score (descending - best matches first)
-    results.sort_by(|a, b| b.1.cmp(&a.1));
-
-    results
+    default_engine().search_units_fuzzy(query, threshold)
 }
 
 /// Concept kinds for filtering search results.
@@ -782,53 +636,7 @@ pub fn search_units_filtered(
     kinds: &[ConceptKind],
     use_fuzzy: bool,
 ) -> Vec<&'static UnitRecord> {
-    let all_results = if use_fuzzy {
-        search_units_fuzzy(query, 30)
-            .into_iter()
-            .map(|(unit, _score)| unit)
-            .collect()
-    } else {
-        search_units(query)
-    };
-
-    all_results
-        .into_iter()
-        .filter(|unit| {
-            let unit_kind = classify_unit(unit);
-            kinds.contains(&unit_kind)
-        })
-        .collect()
-}
-
-/// Classify a unit into its concept kind.
-fn classify_unit(unit: &UnitRecord) -> ConceptKind {
-    // Check for arbitrary units (square brackets)
-    if unit.code.starts_with('[') && unit.code.ends_with(']') {
-        return ConceptKind::ArbitraryUnit;
-    }
-
-    // Check for special units
-    if unit.special != crate::types::SpecialKind::None {
-        return ConceptKind::SpecialUnit;
-    }
-
-    // Check for prefixed units by looking for known prefixes
-    for prefix in crate::registry::PREFIXES {
-        if unit.code.starts_with(prefix.symbol) && unit.code.len() > prefix.symbol.len() {
-            let remainder = &unit.code[prefix.symbol.len()..];
-            if find_unit(remainder).is_some() {
-                return ConceptKind::PrefixedUnit;
-            }
-        }
-    }
-
-    // Check if it's a base unit (has simple dimension vector with single 1 or -1)
-    let non_zero_dims = unit.dim.0.iter().filter(|&&x| x != 0).count();
-    if non_zero_dims <= 1 && unit.dim.0.iter().any(|&x| x.abs() == 1) {
-        ConceptKind::BaseUnit
-    } else {
-        ConceptKind::DerivedUnit
-    }
+    default_engine().search_units_filtered(query, kinds, use_fuzzy)
 }
 
 // ============================================================================
@@ -997,7 +805,7 @@ pub fn get_model() -> UcumModel {
     UcumModel {
         version: "2.1".to_string(),
         revision_date: "2017-11-21".to_string(),
-        prefixes: crate::registry::PREFIXES,
+        prefixes: default_engine().prefixes(),
         units: get_all_units(),
     }
 }
@@ -1037,7 +845,7 @@ pub fn validate_ucum() -> Vec<String> {
 
     // Check for duplicate prefix symbols
     let mut seen_prefixes = HashSet::new();
-    for prefix in crate::registry::PREFIXES {
+    for prefix in default_engine().prefixes() {
         if !seen_prefixes.insert(&prefix.symbol) {
             issues.push(format!("Duplicate prefix symbol: {}", prefix.symbol));
         }
@@ -1045,7 +853,7 @@ pub fn validate_ucum() -> Vec<String> {
 
     // Validate unit expressions that should be parseable
     for unit in get_all_units() {
-        if let Err(e) = validate(unit.code) {
+        if let Err(e) = validate(&unit.code) {
             issues.push(format!("Unit {} failed validation: {}", unit.code, e));
         }
     }
@@ -1141,11 +949,10 @@ pub fn get_common_display(code: &str) -> String {
 
                 if let (Some(prefix), Some(base_unit)) =
                     (find_prefix(prefix_part), find_unit(unit_part))
+                    && base_unit.code == unit_part
                 {
-                    if base_unit.code == unit_part {
-                        // Construct prefixed display name
-                        return format!("{}{}", prefix.display_name, base_unit.display_name);
-                    }
+                    // Construct prefixed display name
+                    return format!("{}{}", prefix.display_name, base_unit.display_name);
                 }
             }
         }
@@ -1278,24 +1085,20 @@ pub fn convert_with_context(
     to: &str,
     context: &AdvancedConversionContext,
 ) -> Result<AdvancedConversionResult, UcumError> {
-    // For now, delegate to the existing conversion logic
-    // This can be enhanced with the precision and rounding logic
-    let from_analysis = analyse(from)?;
-    let to_analysis = analyse(to)?;
-
-    // Check dimension compatibility
-    if from_analysis.dimension != to_analysis.dimension {
-        return Err(UcumError::conversion_error(
-            "source units",
-            "target units",
-            "Cannot convert between units with different dimensions",
-        ));
-    }
-
-    // Calculate conversion
-    let factor = from_analysis.factor / to_analysis.factor;
-    let offset = from_analysis.offset - to_analysis.offset;
-    let converted_value = value * factor + offset;
+    // Special units go through the engine's handlers
+    let engine = default_engine();
+    let (source, target) = (engine.parse(from)?, engine.parse(to)?);
+    let converted_value = engine.convert_float(value, &source, &target)?;
+
+    // The linear part of the conversion: value * factor + offset
+    let (from_result, to_result) = (
+        engine.evaluate_owned(&source)?,
+        engine.evaluate_owned(&target)?,
+    );
+    let to_factor = precision::to_f64(to_result.factor);
+    let factor = precision::to_f64(from_result.factor) / to_factor;
+    let offset =
+        (precision::to_f64(from_result.offset) - precision::to_f64(to_result.offset)) / to_factor;
 
     // Apply precision rounding (simplified implementation)
     let final_value = match context.precision {
@@ -1327,7 +1130,10 @@ pub fn convert_with_context(
         factor,
         offset,
         precision_info,
-        used_special_units: from_analysis.has_offset || to_analysis.has_offset,
+        used_special_units: !from_result.offset.is_zero()
+            || !to_result.offset.is_zero()
+            || engine.contains_nonlinear(&source)
+            || engine.contains_nonlinear(&target),
     })
 }
 
@@ -1361,15 +1167,16 @@ pub fn optimize_expression(expr: &str) -> Result<String, UcumError> {
     let canonical = get_canonical_units(expr)?;
 
     // Try to build a more readable form from the dimension vector
-    let optimized = build_optimized_unit_string(&analysis.dimension, &canonical);
+    let optimized = build_optimized_unit_string(expr, &analysis.dimension, &canonical);
 
     Ok(optimized)
 }
 
 /// Convert a unit expression to its canonical (base units) form.
 ///
-/// Returns the expression in terms of the seven SI base units with appropriate
-/// exponents. This is useful for dimensional analysis and unit verification.
+/// Returns the expression in terms of UCUM's seven base units with appropriate
+/// exponents, keeping arbitrary units as terms. This is useful for
+/// dimensional analysis and unit verification.
 ///
 /// # Arguments
 /// * `expr` - The unit expression to canonicalize
@@ -1380,7 +1187,7 @@ pub fn optimize_expression(expr: &str) -> Result<String, UcumError> {
 /// use octofhir_ucum::canonicalize_expression;
 ///
 /// let canonical = canonicalize_expression("N").unwrap();
-/// assert_eq!(canonical, "kg.m.s-2");
+/// assert_eq!(canonical, "g.m.s-2");
 /// ```
 #[allow(clippy::result_large_err)]
 pub fn canonicalize_expression(expr: &str) -> Result<String, UcumError> {
@@ -1411,15 +1218,36 @@ pub fn simplify_expression(expr: &str) -> Result<String, UcumError> {
     let canonical = get_canonical_units(expr)?;
 
     // If the canonical form is simpler, return it, otherwise return original
-    if canonical.unit.len() < expr.len() {
-        Ok(canonical.unit)
-    } else {
-        Ok(expr.to_string())
+    match equivalent_canonical_string(&canonical) {
+        Some(unit) if unit.len() < expr.len() => Ok(unit),
+        _ => Ok(expr.to_string()),
     }
 }
 
+/// The canonical unit string, prefixed where needed so that it denotes the
+/// same unit as the original expression (`kg.m/m` gives `kg`, not `g`).
+///
+/// Returns `None` when no prefix accounts for the canonical factor.
+fn equivalent_canonical_string(canonical: &CanonicalUnit) -> Option<String> {
+    if canonical.offset != 0.0 {
+        return None;
+    }
+    if canonical.factor == 1.0 {
+        return Some(canonical.unit.clone());
+    }
+    // Only a leading term with exponent one can carry a prefix
+    let first = canonical.unit.split('.').next()?;
+    if first == "1" || first.starts_with('[') || first.ends_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let prefix = default_engine().prefixes().iter().find(|p| {
+        precision::comparison::approx_equal_relative(p.factor, canonical.factor, 1e-12)
+    })?;
+    Some(format!("{}{}", prefix.symbol, canonical.unit))
+}
+
 /// Build an optimized unit string from dimension vector.
-fn build_optimized_unit_string(dim: &Dimension, canonical: &CanonicalUnit) -> String {
+fn build_optimized_unit_string(expr: &str, dim: &Dimension, canonical: &CanonicalUnit) -> String {
     // Try to use more readable derived units where possible
     let common_units = [
         // Force units
@@ -1445,8 +1273,9 @@ fn build_optimized_unit_string(dim: &Dimension, canonical: &CanonicalUnit) -> St
         }
     }
 
-    // Fall back to canonical form
-    canonical.unit.clone()
+    // Fall back to canonical form, or the expression itself when the
+    // canonical form would drop a factor
+    equivalent_canonical_string(canonical).unwrap_or_else(|| expr.to_string())
 }
 
 // Unused helper functions removed to avoid warnings
@@ -1745,10 +1574,10 @@ impl MeasurementContext {
 
         // Check preferred units for dimensional compatibility
         for preferred in &self.preferred_units {
-            if let Ok(pref_analysis) = analyse(preferred) {
-                if pref_analysis.dimension == analysis.dimension {
-                    suggestions.push(preferred.clone());
-                }
+            if let Ok(pref_analysis) = analyse(preferred)
+                && pref_analysis.dimension == analysis.dimension
+            {
+                suggestions.push(preferred.clone());
             }
         }
 
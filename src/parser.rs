@@ -7,7 +7,7 @@
 //! - Single-pass state machine parsing
 //! - Small vector optimizations for AST nodes
 
-use crate::ast::{OwnedUnitExpr, UnitExpr, UnitFactor};
+use crate::ast::{NodeSpans, OwnedUnitExpr, SpannedExpr, UnitExpr, UnitFactor};
 use crate::error::UcumError;
 use once_cell::sync::Lazy;
 use phf::phf_map;
@@ -121,6 +121,7 @@ impl CompactString {
 /// SIMD-accelerated ASCII validation for x86_64
 #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
 #[inline]
+#[allow(dead_code)] // Future optimization, not currently used
 fn is_ascii_simd(bytes: &[u8]) -> bool {
     use std::arch::x86_64::*;
 
@@ -157,6 +158,12 @@ fn is_ascii_simd(bytes: &[u8]) -> bool {
     bytes.iter().all(|&b| b < 128)
 }
 
+/// `10^exp` as the nearest `f64`; `10f64.powi` misses it for some exponents
+/// (1e-23 comes out as 1.0000000000000001e-23).
+fn ten_power(exp: i32) -> f64 {
+    format!("1e{exp}").parse().unwrap_or(f64::INFINITY)
+}
+
 /// Fast symbol character check using lookup table.
 ///
 /// Returns true if the character can be part of a UCUM symbol.
@@ -261,18 +268,9 @@ impl<'a> Tokenizer<'a> {
     fn scan_symbol(&mut self) -> Option<Token<'a>> {
         let start = self.pos;
 
-        // Handle UTF-8 µ (micro sign) first
+        // Handle UTF-8 µ (micro sign) first; the rest is ASCII
         if self.current_byte() == Some(0xC2) && self.peek_byte(1) == Some(0xB5) {
             self.pos += 2;
-            // Continue scanning for more characters
-            while let Some(b) = self.current_byte() {
-                if is_symbol_char_fast(b) {
-                    self.pos += 1;
-                } else {
-                    break;
-                }
-            }
-            return Some(Token::Symbol(&self.input[start..self.pos]));
         }
 
         // Fast path for ASCII symbols
@@ -288,13 +286,21 @@ impl<'a> Tokenizer<'a> {
             let symbol = &self.input[start..self.pos];
 
             // Check for implicit exponent (e.g., "m2")
-            if let Some(exp_start) = symbol.rfind(|c: char| !c.is_ascii_digit()) {
-                let exp_start = exp_start + 1;
-                if exp_start < symbol.len() {
-                    if let Ok(_exp) = symbol[exp_start..].parse::<i32>() {
-                        self.pos = start + exp_start;
-                        return Some(Token::Symbol(&symbol[..exp_start]));
-                    }
+            if let Some((exp_start, ch)) = symbol.char_indices().rfind(|(_, c)| !c.is_ascii_digit())
+            {
+                let mut exp_start = exp_start + ch.len_utf8();
+                // A signed exponent (e.g., "m-3") keeps its sign
+                if exp_start > 1
+                    && exp_start < symbol.len()
+                    && symbol[..exp_start].ends_with(['+', '-'])
+                {
+                    exp_start -= 1;
+                }
+                if exp_start < symbol.len()
+                    && let Ok(_exp) = symbol[exp_start..].parse::<i32>()
+                {
+                    self.pos = start + exp_start;
+                    return Some(Token::Symbol(&symbol[..exp_start]));
                 }
             }
 
@@ -314,7 +320,12 @@ impl<'a> Tokenizer<'a> {
         while let Some(b) = self.current_byte() {
             match CHAR_CLASS[b as usize] {
                 CharClass::Digit => self.pos += 1,
-                CharClass::Dot if !has_dot && !has_exp => {
+                // A dot not followed by a digit is the product operator (`4.[pi]`)
+                CharClass::Dot
+                    if !has_dot
+                        && !has_exp
+                        && self.peek_byte(1).is_some_and(|b| b.is_ascii_digit()) =>
+                {
                     has_dot = true;
                     self.pos += 1;
                 }
@@ -323,10 +334,10 @@ impl<'a> Tokenizer<'a> {
                         has_exp = true;
                         self.pos += 1;
                         // Optional sign
-                        if let Some(sign) = self.current_byte() {
-                            if sign == b'+' || sign == b'-' {
-                                self.pos += 1;
-                            }
+                        if let Some(sign) = self.current_byte()
+                            && (sign == b'+' || sign == b'-')
+                        {
+                            self.pos += 1;
                         }
                     } else {
                         break;
@@ -336,10 +347,10 @@ impl<'a> Tokenizer<'a> {
             }
         }
 
-        if self.pos > start {
-            if let Ok(num) = self.input[start..self.pos].parse::<f64>() {
-                return Some(Token::Number(num));
-            }
+        if self.pos > start
+            && let Ok(num) = self.input[start..self.pos].parse::<f64>()
+        {
+            return Some(Token::Number(num));
         }
 
         None
@@ -355,10 +366,10 @@ impl<'a> Tokenizer<'a> {
 
             // Parse exponent
             let exp_start = self.pos;
-            if let Some(sign) = self.current_byte() {
-                if sign == b'+' || sign == b'-' {
-                    self.pos += 1;
-                }
+            if let Some(sign) = self.current_byte()
+                && (sign == b'+' || sign == b'-')
+            {
+                self.pos += 1;
             }
 
             let digit_start = self.pos;
@@ -370,16 +381,37 @@ impl<'a> Tokenizer<'a> {
                 }
             }
 
-            if self.pos > digit_start {
-                if let Ok(exp) = self.input[exp_start..self.pos].parse::<i32>() {
-                    return Some(Token::TenPower(exp));
-                }
+            if self.pos > digit_start
+                && let Ok(exp) = self.input[exp_start..self.pos].parse::<i32>()
+            {
+                return Some(Token::TenPower(exp));
             }
         }
 
         None
     }
 
+    /// Scan an integer exponent with an optional sign (e.g., "2" or "-3").
+    ///
+    /// Leaves the position unchanged and returns `None` when no digits follow.
+    fn scan_exponent(&mut self) -> Option<i32> {
+        let start = self.pos;
+        if matches!(self.current_byte(), Some(b'+' | b'-')) {
+            self.pos += 1;
+        }
+        let digit_start = self.pos;
+        while self.current_byte().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        match self.input[start..self.pos].parse() {
+            Ok(exp) if self.pos > digit_start => Some(exp),
+            _ => {
+                self.pos = start;
+                None
+            }
+        }
+    }
+
     /// Scan an annotation token enclosed in braces (e.g., "{comment}").
     fn scan_annotation(&mut self) -> Option<Token<'a>> {
         if self.current_byte() != Some(b'{') {
@@ -422,6 +454,8 @@ impl<'a> Tokenizer<'a> {
 
         match CHAR_CLASS[b as usize] {
             CharClass::Letter | CharClass::OpenBracket => self.scan_symbol(),
+            // Percent is a unit of its own; placement is checked by the parser
+            CharClass::Symbol if b == b'%' => self.scan_symbol(),
             CharClass::Digit => {
                 // Check for 10* or 10^ patterns
                 if b == b'1'
@@ -496,14 +530,18 @@ impl<'a> OptimizedParser<'a> {
     ///
     /// A factor consists of a base expression (symbol, number, or parenthesized expression)
     /// optionally followed by an exponent (explicit with ^ or implicit like "s2").
+    /// The returned spans describe the factor: its range, its exponent and,
+    /// as the only child, the base expression.
     #[allow(clippy::result_large_err)]
-    fn parse_factor(&mut self) -> Result<Option<UnitFactor<'a>>, UcumError> {
+    fn parse_factor(&mut self) -> Result<Option<(UnitFactor<'a>, NodeSpans)>, UcumError> {
+        self.tokenizer.skip_whitespace();
+        let start = self.tokenizer.pos;
         let token = match self.tokenizer.next_token() {
             Some(t) => t,
             None => return Ok(None),
         };
 
-        let base_expr = match token {
+        let (base_expr, base_spans) = match token {
             Token::Symbol(s) => {
                 // Validate symbol
                 if s.contains('%') && s.len() > 1 {
@@ -526,10 +564,19 @@ impl<'a> OptimizedParser<'a> {
                     }
                 }
 
-                self.normalize_symbol(s)
-            }
-            Token::Number(n) => UnitExpr::Numeric(n),
-            Token::TenPower(exp) => UnitExpr::Numeric(10f64.powi(exp)),
+                (
+                    self.normalize_symbol(s),
+                    NodeSpans::new(start..self.tokenizer.pos),
+                )
+            }
+            Token::Number(n) => (
+                UnitExpr::Numeric(n),
+                NodeSpans::new(start..self.tokenizer.pos),
+            ),
+            Token::TenPower(exp) => (
+                UnitExpr::Numeric(ten_power(exp)),
+                NodeSpans::new(start..self.tokenizer.pos),
+            ),
             Token::OpenParen => {
                 // Parse parenthesized expression
                 let inner = self.parse_expression()?;
@@ -540,36 +587,58 @@ impl<'a> OptimizedParser<'a> {
             }
             Token::Annotation(content) => {
                 // Standalone annotation
-                UnitExpr::SymbolOwned(format!("{{{content}}}"))
+                let range = start..self.tokenizer.pos;
+                (
+                    UnitExpr::Annotation(content),
+                    NodeSpans {
+                        annotation: Some(range.clone()),
+                        ..NodeSpans::new(range)
+                    },
+                )
             }
             _ => return Ok(None),
         };
 
-        // Check for explicit exponent or implicit exponent (number following symbol)
+        // Check for explicit exponent or implicit exponent (digits following
+        // the base); exponents are integers, so `m2.10*3` is `m2` times `10*3`
         let mut exponent = 1;
-        let saved_pos = self.tokenizer.pos;
-
-        match self.tokenizer.next_token() {
-            Some(Token::Operator('^')) => match self.tokenizer.next_token() {
-                Some(Token::Number(n)) => exponent = n as i32,
-                _ => return Err(UcumError::invalid_expression("Invalid exponent")),
-            },
-            Some(Token::Number(n)) => {
-                // Implicit exponent (e.g., s2 -> s^2)
-                exponent = n as i32;
-            }
-            _ => {
-                // No exponent, backtrack
-                self.tokenizer.pos = saved_pos;
-            }
+        let caret = self.tokenizer.current_byte() == Some(b'^');
+        if caret {
+            self.tokenizer.pos += 1;
+        }
+        let exp_start = self.tokenizer.pos;
+        if let Some(n) = self.tokenizer.scan_exponent() {
+            exponent = n;
+        } else if caret || matches!(self.tokenizer.current_byte(), Some(b'+' | b'-')) {
+            return Err(UcumError::invalid_expression("Invalid exponent"));
         }
+        let mut exponent_range =
+            (self.tokenizer.pos > exp_start).then_some(exp_start..self.tokenizer.pos);
 
-        // Skip trailing annotations
+        // Attach trailing annotations; they apply to the annotatable term
+        // including its exponent (e.g. `m2{x}` annotates `m2`).
+        let (mut expr, mut spans) = (base_expr, base_spans);
         loop {
             let saved_pos = self.tokenizer.pos;
+            self.tokenizer.skip_whitespace();
+            let annotation_start = self.tokenizer.pos;
             match self.tokenizer.next_token() {
-                Some(Token::Annotation(_)) => {
-                    // Consume annotation and continue
+                Some(Token::Annotation(content)) => {
+                    if exponent != 1 {
+                        expr = UnitExpr::Power(Box::new(expr), exponent);
+                        spans = NodeSpans {
+                            exponent: exponent_range.take(),
+                            children: vec![spans],
+                            ..NodeSpans::new(start..annotation_start)
+                        };
+                        exponent = 1;
+                    }
+                    expr = UnitExpr::Annotated(Box::new(expr), content);
+                    spans = NodeSpans {
+                        annotation: Some(annotation_start..self.tokenizer.pos),
+                        children: vec![spans],
+                        ..NodeSpans::new(start..self.tokenizer.pos)
+                    };
                 }
                 _ => {
                     // Not an annotation, backtrack and stop
@@ -579,10 +648,12 @@ impl<'a> OptimizedParser<'a> {
             }
         }
 
-        Ok(Some(UnitFactor {
-            expr: base_expr,
-            exponent,
-        }))
+        let factor_spans = NodeSpans {
+            exponent: exponent_range,
+            children: vec![spans],
+            ..NodeSpans::new(start..self.tokenizer.pos)
+        };
+        Ok(Some((UnitFactor { expr, exponent }, factor_spans)))
     }
 
     /// Parse a product of factors.
@@ -590,13 +661,21 @@ impl<'a> OptimizedParser<'a> {
     /// Products can be explicit (with '.' separator) or implicit (adjacent tokens).
     /// Stops at division operators, close parentheses, or end of input.
     #[allow(clippy::result_large_err)]
-    fn parse_product(&mut self) -> Result<UnitExpr<'a>, UcumError> {
+    fn parse_product(&mut self) -> Result<(UnitExpr<'a>, NodeSpans), UcumError> {
         let mut factors = SmallFactorVec::new();
+        let mut spans = Vec::new();
 
         // Parse first factor
         match self.parse_factor()? {
-            Some(f) => factors.push(f),
-            None => return Ok(UnitExpr::Numeric(1.0)), // Empty expression
+            Some((f, s)) => {
+                factors.push(f);
+                spans.push(s);
+            }
+            None => {
+                // Empty expression
+                let pos = self.tokenizer.pos;
+                return Ok((UnitExpr::Numeric(1.0), NodeSpans::new(pos..pos)));
+            }
         }
 
         // Parse remaining factors
@@ -624,8 +703,9 @@ impl<'a> OptimizedParser<'a> {
             }
 
             match self.parse_factor()? {
-                Some(f) => {
+                Some((f, s)) => {
                     factors.push(f);
+                    spans.push(s);
                 }
                 None => {
                     break;
@@ -636,28 +716,45 @@ impl<'a> OptimizedParser<'a> {
         // Optimize for single factor
         if factors.len() == 1 {
             let factor = factors.into_iter().next().unwrap();
+            let mut factor_spans = spans.pop().unwrap();
             if factor.exponent == 1 {
-                Ok(factor.expr)
+                Ok((factor.expr, factor_spans.children.remove(0)))
             } else {
-                Ok(UnitExpr::Power(Box::new(factor.expr), factor.exponent))
+                Ok((
+                    UnitExpr::Power(Box::new(factor.expr), factor.exponent),
+                    factor_spans,
+                ))
             }
         } else {
-            Ok(UnitExpr::Product(factors.into_vec()))
+            let range = spans[0].range.start..spans[spans.len() - 1].range.end;
+            Ok((
+                UnitExpr::Product(factors.into_vec()),
+                NodeSpans {
+                    children: spans,
+                    ..NodeSpans::new(range)
+                },
+            ))
         }
     }
 
     /// Parse a full expression with division
     #[allow(clippy::result_large_err)]
-    pub fn parse_expression(&mut self) -> Result<UnitExpr<'a>, UcumError> {
+    pub fn parse_expression(&mut self) -> Result<(UnitExpr<'a>, NodeSpans), UcumError> {
         // Check for leading division (e.g., "/min" should be "1/min")
+        self.tokenizer.skip_whitespace();
         let saved_pos = self.tokenizer.pos;
         match self.tokenizer.next_token() {
             Some(Token::Operator('/')) => {
-                // Leading division - parse as 1/denominator
-                let denominator = self.parse_product()?;
-                return Ok(UnitExpr::Quotient(
-                    Box::new(UnitExpr::Numeric(1.0)),
-                    Box::new(denominator),
+                // Leading division - parse as 1/denominator; the implied
+                // numerator has an empty range at the slash
+                let (denominator, den_spans) = self.parse_product()?;
+                let range = saved_pos..den_spans.range.end;
+                return Ok((
+                    UnitExpr::Quotient(Box::new(UnitExpr::Numeric(1.0)), Box::new(denominator)),
+                    NodeSpans {
+                        children: vec![NodeSpans::new(saved_pos..saved_pos), den_spans],
+                        ..NodeSpans::new(range)
+                    },
                 ));
             }
             _ => {
@@ -666,15 +763,20 @@ impl<'a> OptimizedParser<'a> {
             }
         }
 
-        let mut result = self.parse_product()?;
+        let (mut result, mut spans) = self.parse_product()?;
 
         // Handle division - check each token to see if it's division
         loop {
             let saved_pos = self.tokenizer.pos;
             match self.tokenizer.next_token() {
                 Some(Token::Operator('/')) => {
-                    let denominator = self.parse_product()?;
+                    let (denominator, den_spans) = self.parse_product()?;
                     result = UnitExpr::Quotient(Box::new(result), Box::new(denominator));
+                    let range = spans.range.start..den_spans.range.end;
+                    spans = NodeSpans {
+                        children: vec![spans, den_spans],
+                        ..NodeSpans::new(range)
+                    };
                 }
                 _ => {
                     // Not a division operator, backtrack and stop
@@ -684,7 +786,7 @@ impl<'a> OptimizedParser<'a> {
             }
         }
 
-        Ok(result)
+        Ok((result, spans))
     }
 
     /// Parse and validate a complete UCUM expression.
@@ -692,7 +794,14 @@ impl<'a> OptimizedParser<'a> {
     /// Performs pre-validation checks and ensures all input is consumed.
     /// Returns an owned AST that can outlive the input string.
     #[allow(clippy::result_large_err)]
-    pub fn parse(mut self) -> Result<OwnedUnitExpr, UcumError> {
+    pub fn parse(self) -> Result<OwnedUnitExpr, UcumError> {
+        self.parse_spanned().map(|(expr, _)| expr)
+    }
+
+    /// Parse like [`Self::parse`], also returning the byte range of every
+    /// node in the input.
+    #[allow(clippy::result_large_err)]
+    pub fn parse_spanned(mut self) -> Result<(OwnedUnitExpr, NodeSpans), UcumError> {
         // Quick pre-validation
         let input = self.tokenizer.input;
 
@@ -706,7 +815,7 @@ impl<'a> OptimizedParser<'a> {
                 in_annotation = false;
                 continue;
             }
-            
+
             if !in_annotation {
                 if ch.is_ascii() {
                     let ch_class = CHAR_CLASS[ch as u8 as usize];
@@ -727,10 +836,10 @@ impl<'a> OptimizedParser<'a> {
         }
 
         // Check for % in wrong position
-        if let Some(pos) = input.find('%') {
-            if input != "%" {
-                return Err(UcumError::invalid_percent_placement(pos));
-            }
+        if let Some(pos) = input.find('%')
+            && input != "%"
+        {
+            return Err(UcumError::invalid_percent_placement(pos));
         }
 
         // Check for addition operators outside of 10*+ or 10^+ contexts
@@ -741,7 +850,7 @@ impl<'a> OptimizedParser<'a> {
         }
 
         // Parse expression
-        let expr = self.parse_expression()?;
+        let (expr, spans) = self.parse_expression()?;
 
         // Ensure all input was consumed
         if self.tokenizer.next_token().is_some() {
@@ -750,7 +859,7 @@ impl<'a> OptimizedParser<'a> {
             ));
         }
 
-        Ok(expr.to_owned())
+        Ok((expr.to_owned(), spans))
     }
 }
 
@@ -770,6 +879,27 @@ pub fn parse_expression_optimized(input: &str) -> Result<OwnedUnitExpr, UcumErro
     OptimizedParser::new(input).parse()
 }
 
+/// Parse a UCUM expression, keeping the byte range of every node in `input`.
+///
+/// Symbol prefixes are left unset; resolving them needs a registry.
+#[allow(clippy::result_large_err)]
+pub fn parse_expression_spanned(input: &str) -> Result<SpannedExpr, UcumError> {
+    let offset = input.len() - input.trim_start().len();
+    let trimmed = input.trim();
+
+    let (expr, mut spans) = if trimmed.is_empty() {
+        (OwnedUnitExpr::Numeric(1.0), NodeSpans::new(0..0))
+    } else {
+        OptimizedParser::new(trimmed).parse_spanned()?
+    };
+    spans.shift(offset);
+    Ok(SpannedExpr {
+        source: input.to_string(),
+        expr,
+        spans,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -822,4 +952,19 @@ mod tests {
             _ => panic!("Expected quotient, got {:?}", result),
         }
     }
+
+    #[test]
+    fn test_signed_exponent() {
+        let result = parse_expression_optimized("m-3").unwrap();
+        assert_eq!(
+            result,
+            OwnedUnitExpr::Power(Box::new(OwnedUnitExpr::Symbol("m".into())), -3)
+        );
+
+        let result = parse_expression_optimized("[IU].s-2").unwrap();
+        match result {
+            OwnedUnitExpr::Product(factors) => assert_eq!(factors[1].exponent, -2),
+            _ => panic!("Expected product, got {:?}", result),
+        }
+    }
 }
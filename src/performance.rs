@@ -96,6 +96,25 @@ impl EvaluationCache {
         hasher.finish()
     }
 
+    /// Look up a cached expression evaluation result, counting the hit or miss
+    pub fn get_expression(&mut self, expr: &UnitExpr) -> Option<EvalResult> {
+        let result = self.expression_cache.get(&Self::hash_expr(expr)).cloned();
+        if result.is_some() {
+            self.stats.expression_hits += 1;
+        } else {
+            self.stats.expression_misses += 1;
+        }
+        result
+    }
+
+    /// Cache an expression evaluation result computed outside the cache
+    pub fn insert_expression(&mut self, expr: &UnitExpr, result: EvalResult) {
+        // Only cache if the cache isn't too large (prevent memory bloat)
+        if self.expression_cache.len() < 10000 {
+            self.expression_cache.insert(Self::hash_expr(expr), result);
+        }
+    }
+
     /// Get or compute an expression evaluation result with caching
     pub fn get_or_compute_expression<F>(
         &mut self,
@@ -238,6 +257,15 @@ fn hash_unit_expr<H: Hasher>(expr: &UnitExpr, hasher: &mut H) {
             hash_unit_expr(base, hasher);
             exp.hash(hasher);
         }
+        UnitExpr::Annotation(text) => {
+            6u8.hash(hasher); // discriminant
+            text.hash(hasher);
+        }
+        UnitExpr::Annotated(inner, text) => {
+            7u8.hash(hasher); // discriminant
+            hash_unit_expr(inner, hasher);
+            text.hash(hasher);
+        }
     }
 }
 
@@ -247,7 +275,7 @@ lazy_static! {
     static ref UNIT_MAP: HashMap<&'static str, &'static UnitRecord> = {
         let mut map = HashMap::new();
         for unit in crate::registry::UNITS.iter() {
-            map.insert(unit.code, unit);
+            map.insert(unit.code.as_ref(), unit);
         }
         map
     };
@@ -256,7 +284,7 @@ lazy_static! {
     static ref PREFIX_MAP_ENHANCED: HashMap<&'static str, &'static Prefix> = {
         let mut map = HashMap::new();
         for prefix in crate::registry::PREFIXES.iter() {
-            map.insert(prefix.symbol, prefix);
+            map.insert(prefix.symbol.as_ref(), prefix);
         }
         map
     };
@@ -402,32 +430,28 @@ pub fn find_longest_prefix_with_trie(text: &str) -> Option<&'static Prefix> {
     PREFIX_TRIE.find_longest_prefix(text)
 }
 
-/// Get cache statistics (WASM-compatible - returns dummy values)
-/// Note: Caching has been disabled for WASM compatibility
+/// Get cache statistics of the default engine.
 pub fn get_cache_stats() -> Result<CacheStats, UcumError> {
-    Ok(CacheStats::default())
+    Ok(crate::default_engine().cache_stats())
 }
 
-/// Clear the global cache (WASM-compatible - no-op)
-/// Note: Caching has been disabled for WASM compatibility
+/// Clear the default engine's cache.
 pub fn clear_global_cache() -> Result<(), UcumError> {
+    crate::default_engine().clear_cache();
     Ok(())
 }
 
-/// Get global cache sizes (WASM-compatible - returns zero sizes)
-/// Note: Caching has been disabled for WASM compatibility
+/// Get the default engine's cache sizes (expressions, conversions, dimensions).
 pub fn get_cache_sizes() -> Result<(usize, usize, usize), UcumError> {
-    Ok((0, 0, 0))
+    Ok(crate::default_engine().cache_sizes())
 }
 
-/// Access the global cache for custom operations (WASM-compatible - creates temporary cache)
-/// Note: Caching has been disabled for WASM compatibility
+/// Access the default engine's cache for custom operations.
 pub fn with_global_cache<F, R>(f: F) -> Result<R, UcumError>
 where
     F: FnOnce(&mut EvaluationCache) -> R,
 {
-    let mut temp_cache = EvaluationCache::new();
-    Ok(f(&mut temp_cache))
+    Ok(crate::default_engine().with_cache(f))
 }
 
 #[cfg(test)]
@@ -131,6 +131,83 @@ pub fn from_f64(val: f64) -> Number {
     <Number as NumericOps>::from_f64(val)
 }
 
+/// Convert an `f64` through its shortest round-trip representation.
+///
+/// A user-supplied `273.15` becomes exactly 273.15 instead of the binary
+/// approximation that [`from_f64`] retains.
+pub fn from_f64_shortest(val: f64) -> Number {
+    val.to_string().parse().unwrap_or_else(|_| from_f64(val))
+}
+
+/// [`from_f64_shortest`] when a [`Number`] holds `val` to `f64` precision.
+///
+/// `None` for non-finite values and for values beyond a `Number`'s range
+/// (about ±7.9e28) or too small for its 28 decimal places.
+pub fn try_from_f64_shortest(val: f64) -> Option<Number> {
+    if !val.is_finite() {
+        return None;
+    }
+    let exact = from_f64_shortest(val);
+    let back = to_f64(exact);
+    (back == val || ((back - val) / val).abs() < 1e-15).then_some(exact)
+}
+
+/// `base` to the power `exp`, or `None` when the power overflows a
+/// [`Number`] or `base` is zero with a negative `exp`.
+pub fn checked_pow(base: Number, exp: i32) -> Option<Number> {
+    let mut result = Decimal::ONE;
+    for _ in 0..exp.unsigned_abs() {
+        result = result.checked_mul(base)?;
+    }
+    if exp < 0 {
+        Decimal::ONE.checked_div(result)
+    } else {
+        Some(result)
+    }
+}
+
+/// One unit in the last written digit of `value`: 0.01 for `1.50`.
+pub fn unit_in_last_place(value: Number) -> Number {
+    Decimal::new(1, value.scale())
+}
+
+/// Round `value` to the power of ten nearest to `step`, its uncertainty:
+/// 11.905 with a step of 0.22 gives 11.9, 36.9999 with a step of 0.0556
+/// gives 37.0, and 1523 with a step of 20 gives 1520. Trailing zeros are
+/// kept, so 37 with a step of 0.1 gives 37.0.
+pub fn round_to_uncertainty(value: Number, step: Number) -> Number {
+    let step = step.abs();
+    if step.is_zero() {
+        return value;
+    }
+
+    let (mut power, mut exponent) = (Decimal::ONE, 0i32);
+    while power > step && exponent > -(Decimal::MAX_SCALE as i32) {
+        power /= Decimal::TEN;
+        exponent -= 1;
+    }
+    // Nearest on a log scale: round up from √10 times the power
+    let sqrt_ten = Decimal::new(31_622_776_601_683_793, 16);
+    while let Some(next) = power.checked_mul(Decimal::TEN).filter(|_| {
+        power
+            .checked_mul(sqrt_ten)
+            .is_some_and(|limit| step >= limit)
+    }) {
+        power = next;
+        exponent += 1;
+    }
+
+    if exponent < 0 {
+        let places = exponent.unsigned_abs();
+        let mut rounded =
+            value.round_dp_with_strategy(places, RoundingStrategy::MidpointAwayFromZero);
+        rounded.rescale(places);
+        rounded
+    } else {
+        (value / power).round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero) * power
+    }
+}
+
 // ============================================================================
 // Enhanced Precision Configuration and Types
 // ============================================================================
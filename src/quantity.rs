@@ -0,0 +1,440 @@
+//! Arithmetic and comparison for [`Quantity`].
+//!
+//! A `Quantity` pairs a value with a parsed unit expression. The operators in
+//! this module are *checked*: `+`, `-`, `*` and `/` return
+//! `Result<Quantity, UcumError>` so that incommensurable units or offset units
+//! surface as errors instead of silently producing nonsense.
+//!
+//! Semantics:
+//! • Addition and subtraction convert the right operand into the unit of the
+//!   left operand; the result keeps the left operand's unit.
+//! • Multiplication and division combine the unit expressions, the values are
+//!   multiplied/divided as-is (no conversion is needed).
+//! • Offset units (e.g. `Cel`, `[degF]`) are rejected by all arithmetic
+//!   operators because the result would depend on the scale's zero point.
+//!   They can still be converted with [`Quantity::convert_to`] and compared.
+//! • `<`, `<=`, `>` and `>=` compare across commensurable units through
+//!   [`Quantity::compare`]; incommensurable quantities are unordered, so all
+//!   four yield `false`.
+//! • `==` is structural (same value, same unit expression), so `1 m` and
+//!   `100 cm` are ordered as equal yet not `==`. Use [`Quantity::approx_eq`]
+//!   for equality across commensurable units.
+//!
+//! The methods and operators resolve units with the default engine; the
+//! `*_quantities` methods of [`UcumEngine`] do the same against an engine's
+//! own registry, e.g. one with custom units.
+
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::{
+    ast::{OwnedUnitExpr, OwnedUnitFactor},
+    default_engine,
+    engine::UcumEngine,
+    error::UcumError,
+    evaluator::EvalResult,
+    precision::{Number, NumericOps, PrecisionConfig, comparison},
+    types::Quantity,
+};
+
+impl Quantity {
+    /// Create a quantity by parsing a UCUM unit expression.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::Quantity;
+    ///
+    /// let q = Quantity::new(5.0, "mg").unwrap();
+    /// assert_eq!(q.value, 5.0);
+    /// assert!(Quantity::new(1.0, "not_a_unit").is_err());
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn new(value: f64, unit: &str) -> Result<Self, UcumError> {
+        default_engine().quantity(value, unit)
+    }
+
+    /// Check whether this quantity can be converted to (and compared with) `other`.
+    #[allow(clippy::result_large_err)]
+    pub fn is_commensurable_with(&self, other: &Quantity) -> Result<bool, UcumError> {
+        let engine = default_engine();
+        Ok(engine.evaluate_owned(&self.unit)?.dim == engine.evaluate_owned(&other.unit)?.dim)
+    }
+
+    /// Convert this quantity to another unit expression.
+    ///
+    /// Offset units are handled explicitly here, so `Cel` ↔ `K` ↔ `[degF]`
+    /// conversions are supported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::Quantity;
+    ///
+    /// let q = Quantity::new(1.5, "km").unwrap();
+    /// let m = q.convert_to("m").unwrap();
+    /// assert!((m.value - 1500.0).abs() < 1e-9);
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn convert_to(&self, unit: &str) -> Result<Quantity, UcumError> {
+        default_engine().convert_quantity(self, unit)
+    }
+
+    /// Convert this quantity to an already parsed unit expression.
+    #[allow(clippy::result_large_err)]
+    pub fn convert_to_expr(&self, unit: &OwnedUnitExpr) -> Result<Quantity, UcumError> {
+        convert_to_with(default_engine(), self, unit)
+    }
+
+    /// Checked addition; the result is expressed in `self`'s unit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::Quantity;
+    ///
+    /// let a = Quantity::new(1.0, "m").unwrap();
+    /// let b = Quantity::new(50.0, "cm").unwrap();
+    /// let sum = (a + b).unwrap();
+    /// assert!((sum.value - 1.5).abs() < 1e-9);
+    /// assert_eq!(sum.unit.to_string(), "m");
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn checked_add(&self, rhs: &Quantity) -> Result<Quantity, UcumError> {
+        default_engine().add_quantities(self, rhs)
+    }
+
+    /// Checked subtraction; the result is expressed in `self`'s unit.
+    #[allow(clippy::result_large_err)]
+    pub fn checked_sub(&self, rhs: &Quantity) -> Result<Quantity, UcumError> {
+        default_engine().subtract_quantities(self, rhs)
+    }
+
+    /// Checked multiplication; the units are combined into a product.
+    #[allow(clippy::result_large_err)]
+    pub fn checked_mul(&self, rhs: &Quantity) -> Result<Quantity, UcumError> {
+        default_engine().multiply_quantities(self, rhs)
+    }
+
+    /// Checked division; the units are combined into a quotient.
+    #[allow(clippy::result_large_err)]
+    pub fn checked_div(&self, rhs: &Quantity) -> Result<Quantity, UcumError> {
+        default_engine().divide_quantities(self, rhs)
+    }
+
+    /// Compare two quantities after converting `other` into `self`'s unit.
+    ///
+    /// Returns `None` if the units are not commensurable or cannot be evaluated.
+    pub fn compare(&self, other: &Quantity) -> Option<Ordering> {
+        default_engine().compare_quantities(self, other)
+    }
+
+    /// Check whether two quantities are equal after converting `other` into
+    /// `self`'s unit, within the default relative tolerance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::Quantity;
+    ///
+    /// let m = Quantity::new(1.0, "m").unwrap();
+    /// let cm = Quantity::new(100.0, "cm").unwrap();
+    /// assert!(m.approx_eq(&cm));
+    /// assert_ne!(m, cm);
+    /// ```
+    pub fn approx_eq(&self, other: &Quantity) -> bool {
+        self.compare(other) == Some(Ordering::Equal)
+    }
+}
+
+/// Parse `unit` with the engine and check that it evaluates.
+#[allow(clippy::result_large_err)]
+pub(crate) fn quantity_with(
+    engine: &UcumEngine,
+    value: f64,
+    unit: &str,
+) -> Result<Quantity, UcumError> {
+    let unit = engine.parse(unit)?;
+    engine.evaluate_owned(&unit)?;
+    Ok(Quantity { value, unit })
+}
+
+#[allow(clippy::result_large_err)]
+pub(crate) fn convert_to_with(
+    engine: &UcumEngine,
+    quantity: &Quantity,
+    unit: &OwnedUnitExpr,
+) -> Result<Quantity, UcumError> {
+    Ok(Quantity {
+        value: engine.convert_float(quantity.value, &quantity.unit, unit)?,
+        unit: unit.clone(),
+    })
+}
+
+#[allow(clippy::result_large_err)]
+pub(crate) fn add_with(
+    engine: &UcumEngine,
+    lhs: &Quantity,
+    rhs: &Quantity,
+) -> Result<Quantity, UcumError> {
+    let rhs = rhs_in_unit_of(engine, lhs, rhs, "addition")?;
+    Ok(Quantity {
+        value: lhs.value + rhs,
+        unit: lhs.unit.clone(),
+    })
+}
+
+#[allow(clippy::result_large_err)]
+pub(crate) fn sub_with(
+    engine: &UcumEngine,
+    lhs: &Quantity,
+    rhs: &Quantity,
+) -> Result<Quantity, UcumError> {
+    let rhs = rhs_in_unit_of(engine, lhs, rhs, "subtraction")?;
+    Ok(Quantity {
+        value: lhs.value - rhs,
+        unit: lhs.unit.clone(),
+    })
+}
+
+#[allow(clippy::result_large_err)]
+pub(crate) fn mul_with(
+    engine: &UcumEngine,
+    lhs: &Quantity,
+    rhs: &Quantity,
+) -> Result<Quantity, UcumError> {
+    reject_offset(engine, &lhs.unit, "multiplication")?;
+    reject_offset(engine, &rhs.unit, "multiplication")?;
+    Ok(Quantity {
+        value: lhs.value * rhs.value,
+        unit: multiply_units(&lhs.unit, &rhs.unit),
+    })
+}
+
+#[allow(clippy::result_large_err)]
+pub(crate) fn div_with(
+    engine: &UcumEngine,
+    lhs: &Quantity,
+    rhs: &Quantity,
+) -> Result<Quantity, UcumError> {
+    reject_offset(engine, &lhs.unit, "division")?;
+    reject_offset(engine, &rhs.unit, "division")?;
+    if rhs.value == 0.0 {
+        return Err(UcumError::conversion_error(
+            "denominator",
+            "zero",
+            "division by zero",
+        ));
+    }
+    Ok(Quantity {
+        value: lhs.value / rhs.value,
+        unit: divide_units(&lhs.unit, &rhs.unit),
+    })
+}
+
+pub(crate) fn compare_with(
+    engine: &UcumEngine,
+    lhs: &Quantity,
+    rhs: &Quantity,
+) -> Option<Ordering> {
+    let rhs_value = engine.convert_float(rhs.value, &rhs.unit, &lhs.unit).ok()?;
+    let tolerance = PrecisionConfig::default().tolerance;
+    if comparison::approx_equal_relative(lhs.value, rhs_value, tolerance) {
+        Some(Ordering::Equal)
+    } else {
+        lhs.value.partial_cmp(&rhs_value)
+    }
+}
+
+/// Convert `rhs` into `lhs`'s unit for additive operations.
+#[allow(clippy::result_large_err)]
+fn rhs_in_unit_of(
+    engine: &UcumEngine,
+    lhs: &Quantity,
+    rhs: &Quantity,
+    operation: &str,
+) -> Result<f64, UcumError> {
+    let to = reject_offset(engine, &lhs.unit, operation)?;
+    let from = reject_offset(engine, &rhs.unit, operation)?;
+    if from.dim != to.dim {
+        return Err(UcumError::dimension_mismatch(to.dim, from.dim, operation));
+    }
+    engine.convert_float(rhs.value, &rhs.unit, &lhs.unit)
+}
+
+/// Evaluate `unit` and fail if it carries an offset (e.g. `Cel`).
+#[allow(clippy::result_large_err)]
+fn reject_offset(
+    engine: &UcumEngine,
+    unit: &OwnedUnitExpr,
+    operation: &str,
+) -> Result<EvalResult, UcumError> {
+    let result = engine.evaluate_owned(unit)?;
+    if result.offset != Number::zero() {
+        return Err(UcumError::conversion_error(
+            "offset units",
+            operation,
+            "offset units cannot participate in quantity arithmetic; convert to an absolute unit such as K first",
+        ));
+    }
+    Ok(result)
+}
+
+/// Convert a value between two evaluated units, honouring offsets.
+#[allow(clippy::result_large_err)]
+pub(crate) fn convert_value(
+    value: Number,
+    from: &EvalResult,
+    to: &EvalResult,
+    operation: &str,
+) -> Result<Number, UcumError> {
+    if from.dim != to.dim {
+        return Err(UcumError::dimension_mismatch(to.dim, from.dim, operation));
+    }
+    if to.factor == Number::zero() {
+        return Err(UcumError::conversion_error(
+            "unit",
+            "conversion",
+            "Cannot convert to unit with zero factor",
+        ));
+    }
+    value
+        .checked_mul(from.factor)
+        .and_then(|canonical| canonical.checked_add(from.offset))
+        .and_then(|canonical| canonical.checked_sub(to.offset))
+        .and_then(|canonical| canonical.checked_div(to.factor))
+        .ok_or_else(|| UcumError::precision_overflow(operation, &value.to_string()))
+}
+
+/// Build the unit expression for `lhs * rhs`, flattening products.
+fn multiply_units(lhs: &OwnedUnitExpr, rhs: &OwnedUnitExpr) -> OwnedUnitExpr {
+    if is_unity(lhs) {
+        return rhs.clone();
+    }
+    if is_unity(rhs) {
+        return lhs.clone();
+    }
+    let mut factors = product_factors(lhs);
+    factors.extend(product_factors(rhs));
+    OwnedUnitExpr::Product(factors)
+}
+
+/// Build the unit expression for `lhs / rhs`.
+fn divide_units(lhs: &OwnedUnitExpr, rhs: &OwnedUnitExpr) -> OwnedUnitExpr {
+    if is_unity(rhs) {
+        return lhs.clone();
+    }
+    OwnedUnitExpr::Quotient(Box::new(lhs.clone()), Box::new(rhs.clone()))
+}
+
+fn is_unity(expr: &OwnedUnitExpr) -> bool {
+    matches!(expr, OwnedUnitExpr::Numeric(v) if *v == 1.0)
+}
+
+fn product_factors(expr: &OwnedUnitExpr) -> Vec<OwnedUnitFactor> {
+    match expr {
+        OwnedUnitExpr::Product(factors) => factors.clone(),
+        other => vec![OwnedUnitFactor {
+            expr: other.clone(),
+            exponent: 1,
+        }],
+    }
+}
+
+impl PartialOrd for Quantity {
+    /// Orders quantities across commensurable units, see [`Quantity::compare`].
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.compare(other)
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.value, self.unit)
+    }
+}
+
+impl Neg for Quantity {
+    type Output = Quantity;
+
+    fn neg(self) -> Quantity {
+        Quantity {
+            value: -self.value,
+            unit: self.unit,
+        }
+    }
+}
+
+impl Neg for &Quantity {
+    type Output = Quantity;
+
+    fn neg(self) -> Quantity {
+        -self.clone()
+    }
+}
+
+/// Implement a checked binary operator for all owned/borrowed operand combinations.
+macro_rules! checked_binop {
+    ($trait:ident, $method:ident, $checked:ident) => {
+        impl $trait<&Quantity> for &Quantity {
+            type Output = Result<Quantity, UcumError>;
+
+            fn $method(self, rhs: &Quantity) -> Self::Output {
+                self.$checked(rhs)
+            }
+        }
+
+        impl $trait<Quantity> for Quantity {
+            type Output = Result<Quantity, UcumError>;
+
+            fn $method(self, rhs: Quantity) -> Self::Output {
+                self.$checked(&rhs)
+            }
+        }
+
+        impl $trait<&Quantity> for Quantity {
+            type Output = Result<Quantity, UcumError>;
+
+            fn $method(self, rhs: &Quantity) -> Self::Output {
+                self.$checked(rhs)
+            }
+        }
+
+        impl $trait<Quantity> for &Quantity {
+            type Output = Result<Quantity, UcumError>;
+
+            fn $method(self, rhs: Quantity) -> Self::Output {
+                self.$checked(&rhs)
+            }
+        }
+    };
+}
+
+checked_binop!(Add, add, checked_add);
+checked_binop!(Sub, sub, checked_sub);
+checked_binop!(Mul, mul, checked_mul);
+checked_binop!(Div, div, checked_div);
+
+/// Scaling by a plain number never changes the unit, so it is infallible.
+impl Mul<f64> for Quantity {
+    type Output = Quantity;
+
+    fn mul(self, rhs: f64) -> Quantity {
+        Quantity {
+            value: self.value * rhs,
+            unit: self.unit,
+        }
+    }
+}
+
+impl Div<f64> for Quantity {
+    type Output = Quantity;
+
+    fn div(self, rhs: f64) -> Quantity {
+        Quantity {
+            value: self.value / rhs,
+            unit: self.unit,
+        }
+    }
+}
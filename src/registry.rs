@@ -4,6 +4,484 @@
 //! source file containing two `&'static` slices: `PREFIXES` and `UNITS`, plus
 //! helper lookup functions. That generated file is included here so the rest of
 //! the crate can access the data transparently.
+//!
+//! [`UnitRegistry`] is an owned, indexed copy of that data. Each
+//! [`crate::UcumEngine`] holds its own registry, so engines can diverge from the
+//! built-in tables without affecting each other, e.g. by registering local
+//! units with [`UnitRegistry::register_unit`] or by loading a different UCUM
+//! release with [`UnitRegistry::from_essence_file`].
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::{
+    ast::OwnedUnitExpr,
+    error::UcumError,
+    essence::{EssenceError, parse_essence, power_of_ten},
+    evaluator::evaluate_owned_with,
+    parser::parse_expression_optimized,
+    precision::{Number, NumericOps, to_f64, try_from_f64_shortest},
+    types::DimensionModel,
+};
 
 // This constant is set by build.rs via `cargo:rustc-env=UCUM_REGISTRY=<path>`.
 include!(env!("UCUM_REGISTRY"));
+
+/// Prefix and unit tables with O(1) lookup by code.
+#[derive(Clone, Debug)]
+pub struct UnitRegistry {
+    prefixes: Vec<Prefix>,
+    units: Vec<UnitRecord>,
+    prefix_index: HashMap<Cow<'static, str>, usize>,
+    unit_index: HashMap<Cow<'static, str>, usize>,
+    /// Units by case-insensitive code; several units can share one.
+    ci_unit_index: HashMap<Cow<'static, str>, Vec<usize>>,
+    /// Parsed definitions of units added with [`UnitRegistry::register_unit`].
+    definitions: HashMap<String, OwnedUnitExpr>,
+    dimension_model: DimensionModel,
+}
+
+impl UnitRegistry {
+    /// Create a registry from prefix and unit tables.
+    pub fn new(prefixes: Vec<Prefix>, units: Vec<UnitRecord>) -> Self {
+        let prefix_index = prefixes
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p.symbol.clone(), i))
+            .collect();
+        let unit_index = units
+            .iter()
+            .enumerate()
+            .map(|(i, u)| (u.code.clone(), i))
+            .collect();
+        let mut ci_unit_index: HashMap<_, Vec<usize>> = HashMap::new();
+        for (i, unit) in units.iter().enumerate() {
+            ci_unit_index
+                .entry(unit.ci_code.clone())
+                .or_default()
+                .push(i);
+        }
+        Self {
+            prefixes,
+            units,
+            prefix_index,
+            unit_index,
+            ci_unit_index,
+            definitions: HashMap::new(),
+            dimension_model: DimensionModel::default(),
+        }
+    }
+
+    /// Select the base quantities that evaluation against this registry
+    /// reports dimensions in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::{DimensionModel, UnitRegistry};
+    ///
+    /// let registry = UnitRegistry::builtin().with_dimension_model(DimensionModel::Ucum);
+    /// let mol = registry.find_unit("mol").unwrap();
+    /// assert!(registry.dimension_of(mol).is_dimensionless());
+    /// ```
+    pub fn with_dimension_model(mut self, model: DimensionModel) -> Self {
+        self.dimension_model = model;
+        self
+    }
+
+    /// The base quantities dimensions are reported in.
+    pub fn dimension_model(&self) -> DimensionModel {
+        self.dimension_model
+    }
+
+    /// Dimension of a unit under this registry's [`DimensionModel`].
+    pub fn dimension_of(&self, unit: &UnitRecord) -> Dimension {
+        match self.dimension_model {
+            DimensionModel::Si => unit.dim,
+            DimensionModel::Ucum => unit.ucum_dim,
+        }
+    }
+
+    /// Create a registry holding the built-in `ucum-essence.xml` definitions.
+    pub fn builtin() -> Self {
+        Self::new(PREFIXES.to_vec(), UNITS.to_vec())
+    }
+
+    /// All prefixes in this registry.
+    pub fn prefixes(&self) -> &[Prefix] {
+        &self.prefixes
+    }
+
+    /// All units in this registry.
+    pub fn units(&self) -> &[UnitRecord] {
+        &self.units
+    }
+
+    /// Lookup a prefix by its exact symbol.
+    pub fn find_prefix(&self, sym: &str) -> Option<&Prefix> {
+        self.prefix_index.get(sym).map(|&i| &self.prefixes[i])
+    }
+
+    /// Units whose case-insensitive (c/i) code is `ci_code`, e.g. `PAL`.
+    ///
+    /// A c/i code can stand for several units, such as `L` and `l`.
+    pub fn find_units_ci(&self, ci_code: &str) -> Vec<&UnitRecord> {
+        self.ci_unit_index
+            .get(ci_code)
+            .map(|indices| indices.iter().map(|&i| &self.units[i]).collect())
+            .unwrap_or_default()
+    }
+
+    /// The parsed definition of a unit added with [`UnitRegistry::register_unit`].
+    pub(crate) fn definition(&self, code: &str) -> Option<&OwnedUnitExpr> {
+        self.definitions.get(code)
+    }
+
+    /// Lookup a unit by its exact code, without prefix decomposition.
+    pub fn find_exact_unit(&self, code: &str) -> Option<&UnitRecord> {
+        self.unit_index.get(code).map(|&i| &self.units[i])
+    }
+
+    /// Lookup a unit by code.
+    ///
+    /// Like the generated `find_unit`, a code that is not an atom is split
+    /// into prefix + atom (longest prefix first) and the atom's record is
+    /// returned, so `mg` yields the record for `g`.
+    pub fn find_unit(&self, code: &str) -> Option<&UnitRecord> {
+        if let Some(unit) = self.find_exact_unit(code) {
+            return Some(unit);
+        }
+        for prefix_len in (1..code.len()).rev() {
+            if !code.is_char_boundary(prefix_len) {
+                continue;
+            }
+            let (prefix_part, unit_part) = code.split_at(prefix_len);
+            if self.find_prefix(prefix_part).is_some()
+                && let Some(unit) = self.find_exact_unit(unit_part)
+            {
+                return Some(unit);
+            }
+        }
+        None
+    }
+}
+
+impl UnitRegistry {
+    /// Add a unit defined by a UCUM expression.
+    ///
+    /// The definition is evaluated against this registry, so it may refer to
+    /// built-in units as well as previously registered ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::{CustomUnit, UnitRegistry};
+    ///
+    /// let mut registry = UnitRegistry::builtin();
+    /// let unit = registry
+    ///     .register_unit(CustomUnit::new("[drp_loc]", "mL/15").with_display_name("local drop"))
+    ///     .unwrap();
+    /// assert_eq!(unit.code, "[drp_loc]");
+    /// assert!(registry.register_unit(CustomUnit::new("m", "cm")).is_err());
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn register_unit(&mut self, unit: CustomUnit) -> Result<&UnitRecord, UcumError> {
+        check_symbol(&unit.code)?;
+        if self.find_unit(&unit.code).is_some() {
+            return Err(UcumError::invalid_definition(
+                &unit.code,
+                "a unit with this code is already defined or is a prefixed unit",
+            ));
+        }
+
+        // Record the dimension under both models, whichever is active
+        let model = self.dimension_model;
+        let result = parse_expression_optimized(&unit.definition).and_then(|expr| {
+            self.dimension_model = DimensionModel::Si;
+            let si = evaluate_owned_with(&expr, self);
+            self.dimension_model = DimensionModel::Ucum;
+            let ucum = evaluate_owned_with(&expr, self);
+            self.dimension_model = model;
+            Ok((expr, si?, ucum?.dim))
+        });
+        let (definition, result, ucum_dim) = result.map_err(|e| {
+            UcumError::invalid_definition(&unit.code, &e.message)
+                .with_context(format!("In definition: '{}'", unit.definition))
+        })?;
+        if result.offset != Number::zero() {
+            return Err(UcumError::invalid_definition(
+                &unit.code,
+                "custom units must be a linear multiple of their definition",
+            ));
+        }
+
+        // Arbitrary units are dimensionless and keyed by their own code, see
+        // `UcumEngine::is_comparable`
+        let (dim, ucum_dim) = if unit.is_arbitrary {
+            (Dimension::zero(), Dimension::zero())
+        } else {
+            self.definitions.insert(unit.code.clone(), definition);
+            (result.dim, ucum_dim)
+        };
+        let record = UnitRecord {
+            ci_code: Cow::Owned(unit.code.to_ascii_uppercase()),
+            code: Cow::Owned(unit.code),
+            is_metric: unit.is_metric,
+            dim,
+            ucum_dim,
+            factor: to_f64(result.factor),
+            exact_factor: result.factor,
+            offset: 0.0,
+            exact_offset: Number::ZERO,
+            special: if unit.is_arbitrary {
+                SpecialKind::Arbitrary
+            } else {
+                SpecialKind::None
+            },
+            property: Cow::Owned(unit.property),
+            display_name: Cow::Owned(unit.display_name),
+        };
+
+        let index = self.units.len();
+        self.unit_index.insert(record.code.clone(), index);
+        self.ci_unit_index
+            .entry(record.ci_code.clone())
+            .or_default()
+            .push(index);
+        self.units.push(record);
+        Ok(&self.units[index])
+    }
+
+    /// Add a prefix with the given multiplication factor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::UnitRegistry;
+    ///
+    /// let mut registry = UnitRegistry::builtin();
+    /// registry.register_prefix("R", 1e27, "ronna").unwrap();
+    /// assert_eq!(registry.find_prefix("R").unwrap().exponent, Some(27));
+    /// registry.register_prefix("H", 0.5, "half").unwrap();
+    /// assert_eq!(registry.find_prefix("H").unwrap().exponent, None);
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn register_prefix(
+        &mut self,
+        symbol: &str,
+        factor: f64,
+        display_name: &str,
+    ) -> Result<&Prefix, UcumError> {
+        check_symbol(symbol)?;
+        if symbol.starts_with(['[', '{']) {
+            return Err(UcumError::invalid_definition(
+                symbol,
+                "prefixes cannot be bracketed",
+            ));
+        }
+        if self.find_prefix(symbol).is_some() {
+            return Err(UcumError::invalid_definition(
+                symbol,
+                "a prefix with this symbol is already defined",
+            ));
+        }
+        if !(factor.is_finite() && factor > 0.0) {
+            return Err(UcumError::invalid_definition(
+                symbol,
+                "prefix factor must be a positive number",
+            ));
+        }
+        let exact_factor = try_from_f64_shortest(factor).ok_or_else(|| {
+            UcumError::invalid_definition(symbol, "prefix factor is out of the decimal range")
+        })?;
+
+        let index = self.prefixes.len();
+        self.prefix_index
+            .insert(Cow::Owned(symbol.to_owned()), index);
+        self.prefixes.push(Prefix {
+            symbol: Cow::Owned(symbol.to_owned()),
+            ci_symbol: Cow::Owned(symbol.to_ascii_uppercase()),
+            factor,
+            exact_factor,
+            exponent: power_of_ten(exact_factor),
+            display_name: Cow::Owned(display_name.to_owned()),
+        });
+        Ok(&self.prefixes[index])
+    }
+}
+
+impl UnitRegistry {
+    /// Load a registry from the text of a UCUM essence document.
+    ///
+    /// Definitions are resolved exactly like the built-in tables, which
+    /// `build.rs` generates from the bundled `ucum-essence.xml`, so a newer
+    /// UCUM revision can be checked side by side with [`UnitRegistry::builtin`].
+    /// Malformed definitions are reported as
+    /// [`ErrorKind::InvalidDefinition`](crate::ErrorKind::InvalidDefinition),
+    /// unreadable documents as
+    /// [`ErrorKind::InvalidEssence`](crate::ErrorKind::InvalidEssence).
+    #[allow(clippy::result_large_err)]
+    pub fn from_essence_xml(xml: &str) -> Result<Self, UcumError> {
+        let essence = parse_essence(xml).map_err(essence_error)?;
+        let prefixes = essence
+            .prefixes
+            .into_iter()
+            .map(|p| Prefix {
+                symbol: Cow::Owned(p.code),
+                ci_symbol: Cow::Owned(p.ci_code),
+                factor: p.factor,
+                exact_factor: p.exact_factor,
+                exponent: p.exponent,
+                display_name: Cow::Owned(p.name),
+            })
+            .collect();
+        let units = essence
+            .units
+            .into_iter()
+            .map(|u| UnitRecord {
+                code: Cow::Owned(u.code),
+                ci_code: Cow::Owned(u.ci_code),
+                is_metric: u.is_metric,
+                dim: Dimension(u.dim),
+                ucum_dim: Dimension(u.ucum_dim),
+                factor: u.factor,
+                exact_factor: u.exact_factor,
+                offset: u.offset,
+                exact_offset: u.exact_offset,
+                special: special_kind(u.special),
+                property: Cow::Owned(u.property),
+                display_name: Cow::Owned(u.display_name),
+            })
+            .collect();
+        Ok(Self::new(prefixes, units))
+    }
+
+    /// Load a registry from the raw bytes of a UCUM essence document.
+    #[allow(clippy::result_large_err)]
+    pub fn from_essence_bytes(bytes: &[u8]) -> Result<Self, UcumError> {
+        let xml = std::str::from_utf8(bytes)
+            .map_err(|e| UcumError::invalid_essence(&format!("document is not UTF-8: {e}")))?;
+        Self::from_essence_xml(xml)
+    }
+
+    /// Load a registry from a UCUM essence file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::{UcumEngine, UnitRegistry};
+    ///
+    /// let path = concat!(env!("CARGO_MANIFEST_DIR"), "/ucum-essence.xml");
+    /// let engine = UcumEngine::new().with_registry(UnitRegistry::from_essence_file(path).unwrap());
+    /// assert!(engine.is_comparable("[in_i]", "cm").unwrap());
+    /// ```
+    #[allow(clippy::result_large_err)]
+    pub fn from_essence_file<P: AsRef<Path>>(path: P) -> Result<Self, UcumError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|e| {
+            UcumError::invalid_essence(&format!("cannot read '{}': {e}", path.display()))
+        })?;
+        Self::from_essence_bytes(&bytes)
+            .map_err(|e| e.with_context(format!("In file: '{}'", path.display())))
+    }
+}
+
+fn essence_error(e: EssenceError) -> UcumError {
+    match &e.code {
+        Some(code) => UcumError::invalid_definition(code, &e.reason)
+            .with_context(format!("In <{}> element", e.element)),
+        None => UcumError::invalid_essence(&e.to_string()),
+    }
+}
+
+fn special_kind(name: &str) -> SpecialKind {
+    match name {
+        "LinearOffset" => SpecialKind::LinearOffset,
+        "Log10" => SpecialKind::Log10,
+        "Ln" => SpecialKind::Ln,
+        "TanTimes100" => SpecialKind::TanTimes100,
+        "Arbitrary" => SpecialKind::Arbitrary,
+        _ => SpecialKind::None,
+    }
+}
+
+/// A unit definition to add to a [`UnitRegistry`] at runtime.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustomUnit {
+    /// The unit's code, e.g. `[CFU_loc]` or `{tbl}`.
+    pub code: String,
+    /// Definition as a UCUM expression, e.g. `10*3.[CFU]` or `1`.
+    pub definition: String,
+    /// Property measured by the unit, e.g. `number`.
+    pub property: String,
+    /// Human readable name.
+    pub display_name: String,
+    /// Whether the unit accepts prefixes.
+    pub is_metric: bool,
+    /// Whether the unit is arbitrary (not commensurable with other units).
+    pub is_arbitrary: bool,
+}
+
+impl CustomUnit {
+    /// Create a definition; the display name defaults to the code.
+    pub fn new<S: Into<String>, D: Into<String>>(code: S, definition: D) -> Self {
+        let code = code.into();
+        Self {
+            display_name: code.clone(),
+            code,
+            definition: definition.into(),
+            property: String::new(),
+            is_metric: false,
+            is_arbitrary: false,
+        }
+    }
+
+    /// Set the property measured by the unit.
+    pub fn with_property<S: Into<String>>(mut self, property: S) -> Self {
+        self.property = property.into();
+        self
+    }
+
+    /// Set the display name.
+    pub fn with_display_name<S: Into<String>>(mut self, display_name: S) -> Self {
+        self.display_name = display_name.into();
+        self
+    }
+
+    /// Allow the unit to be combined with prefixes.
+    pub fn metric(mut self) -> Self {
+        self.is_metric = true;
+        self
+    }
+
+    /// Mark the unit as arbitrary.
+    pub fn arbitrary(mut self) -> Self {
+        self.is_arbitrary = true;
+        self
+    }
+}
+
+/// Ensure `code` parses as a single symbol or annotation.
+#[allow(clippy::result_large_err)]
+fn check_symbol(code: &str) -> Result<(), UcumError> {
+    let single = match parse_expression_optimized(code) {
+        Ok(OwnedUnitExpr::Symbol(sym)) => sym == code,
+        Ok(OwnedUnitExpr::Annotation(text)) => code.len() == text.len() + 2,
+        _ => false,
+    };
+    if single {
+        Ok(())
+    } else {
+        Err(UcumError::invalid_definition(
+            code,
+            "code must be a single UCUM symbol",
+        ))
+    }
+}
+
+impl Default for UnitRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
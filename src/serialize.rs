@@ -0,0 +1,393 @@
+//! UCUM serialization of unit expressions.
+//!
+//! [`OwnedUnitExpr::to_ucum`] writes an expression back as a UCUM code that
+//! parses to an equivalent expression: exponents follow their atom (`s2`,
+//! not `s^2`), compound terms are parenthesised where the grammar needs it,
+//! numbers are integers or powers of ten (`10*3`) and annotations are kept.
+//! UCUM puts exponents and annotations on atoms only, so an exponent on a
+//! group is distributed over its atoms and numbers (`(kg.m)2` is `kg2.m2`,
+//! `(10*3)2` is `10*6`) and an annotated group is followed by its
+//! annotation as a factor of its own (`(m.s){x}` is `(m.s).{x}`).
+//! The tree does not record how a number was spelt, so `1000` and `10*3`
+//! print the same.
+
+use crate::ast::{OwnedUnitExpr, OwnedUnitFactor};
+
+/// How [`OwnedUnitExpr::to_ucum`] spells an expression.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SerializeStyle {
+    /// The expression as parsed, with its terms, order and annotations,
+    /// apart from exponents on groups. This is the `Display` form.
+    #[default]
+    Preserving,
+    /// As parsed, but without unit factors, `1` numerators and powers of
+    /// one: `1/min` becomes `/min`.
+    Minimal,
+    /// A single product with like terms merged, the numeric factor first
+    /// and positive exponents before negative ones: `kg.m/s/s` becomes
+    /// `kg.m.s-2`.
+    Normalized,
+}
+
+impl OwnedUnitExpr {
+    /// Serialize as a UCUM code in the given style.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use octofhir_ucum::{SerializeStyle, parse_expression};
+    ///
+    /// let expr = parse_expression("10*3/(m2.s)").unwrap();
+    /// assert_eq!(expr.to_ucum(SerializeStyle::Preserving), "10*3/(m2.s)");
+    /// assert_eq!(expr.to_ucum(SerializeStyle::Normalized), "10*3.m-2.s-1");
+    ///
+    /// let per_minute = parse_expression("1/min").unwrap();
+    /// assert_eq!(per_minute.to_ucum(SerializeStyle::Minimal), "/min");
+    /// ```
+    pub fn to_ucum(&self, style: SerializeStyle) -> String {
+        match style {
+            SerializeStyle::Preserving => Writer { minimal: false }.term(&ucum_form(self), true),
+            SerializeStyle::Minimal => {
+                Writer { minimal: true }.term(&simplify(&ucum_form(self)), true)
+            }
+            SerializeStyle::Normalized => normalized(self),
+        }
+    }
+}
+
+impl OwnedUnitFactor {
+    /// Serialize as a UCUM component, see [`OwnedUnitExpr::to_ucum`].
+    pub fn to_ucum(&self, style: SerializeStyle) -> String {
+        if self.exponent == 1 {
+            component(&self.expr.to_ucum(style), &self.expr)
+        } else {
+            OwnedUnitExpr::Power(Box::new(self.expr.clone()), self.exponent).to_ucum(style)
+        }
+    }
+}
+
+struct Writer {
+    /// Write `1/x` as `/x`.
+    minimal: bool,
+}
+
+impl Writer {
+    /// A term: the expression with its operators at the top level.
+    ///
+    /// `lead` is set at the start of a term, the only place where the
+    /// grammar accepts a leading slash.
+    fn term(&self, expr: &OwnedUnitExpr, lead: bool) -> String {
+        match expr {
+            OwnedUnitExpr::Product(factors) => {
+                let mut out = String::new();
+                for factor in factors {
+                    let text = match factor.exponent {
+                        1 => component(&self.term(&factor.expr, true), &factor.expr),
+                        exponent => self.power(&factor.expr, exponent),
+                    };
+                    append_factor(&mut out, &text);
+                }
+                out
+            }
+            OwnedUnitExpr::Quotient(num, den) => {
+                let leading_slash = self.minimal && lead && is_one(num);
+                let den = match **den {
+                    OwnedUnitExpr::Quotient(..) => format!("({})", self.term(den, true)),
+                    // A leading slash divides by the whole product that follows
+                    OwnedUnitExpr::Product(_) if !leading_slash => {
+                        format!("({})", self.term(den, true))
+                    }
+                    _ => self.term(den, false),
+                };
+                if leading_slash {
+                    format!("/{den}")
+                } else {
+                    format!("{}/{den}", self.term(num, false))
+                }
+            }
+            OwnedUnitExpr::Power(inner, exponent) => self.power(inner, *exponent),
+            OwnedUnitExpr::Numeric(value) => number(*value),
+            OwnedUnitExpr::Symbol(symbol) => symbol.clone(),
+            OwnedUnitExpr::Annotation(text) => format!("{{{text}}}"),
+            OwnedUnitExpr::Annotated(inner, text) => {
+                let inner_text = self.term(inner, true);
+                format!("{}{{{text}}}", component(&inner_text, inner))
+            }
+        }
+    }
+
+    /// `expr` raised to `exponent`; only an atom takes an exponent (`m2`),
+    /// on anything else it is distributed (`m2.s2` for `(m.s)2`).
+    fn power(&self, expr: &OwnedUnitExpr, exponent: i32) -> String {
+        match expr {
+            OwnedUnitExpr::Symbol(symbol) => format!("{symbol}{exponent}"),
+            _ => self.term(&from_factors(powered(expr, exponent)), true),
+        }
+    }
+}
+
+/// `expr` with its exponents and annotations on atoms only: exponents on
+/// groups are distributed with [`powered`] and annotated groups become a
+/// product of the group and the annotation.
+fn ucum_form(expr: &OwnedUnitExpr) -> OwnedUnitExpr {
+    match expr {
+        OwnedUnitExpr::Product(factors) => {
+            let factors: Vec<_> = factors
+                .iter()
+                .flat_map(|factor| match factor.exponent {
+                    1 => vec![unit_factor(ucum_form(&factor.expr), 1)],
+                    exponent => powered(&factor.expr, exponent),
+                })
+                .collect();
+            match factors.len() {
+                0 => OwnedUnitExpr::Numeric(1.0),
+                _ => OwnedUnitExpr::Product(factors),
+            }
+        }
+        OwnedUnitExpr::Quotient(num, den) => {
+            OwnedUnitExpr::Quotient(Box::new(ucum_form(num)), Box::new(ucum_form(den)))
+        }
+        OwnedUnitExpr::Power(inner, exponent) => from_factors(powered(inner, *exponent)),
+        OwnedUnitExpr::Annotated(inner, text) if !is_annotatable(inner) => {
+            OwnedUnitExpr::Product(vec![
+                unit_factor(ucum_form(inner), 1),
+                unit_factor(OwnedUnitExpr::Annotation(text.clone()), 1),
+            ])
+        }
+        other => other.clone(),
+    }
+}
+
+/// The factors of `expr` raised to `exponent`, each an atom with its
+/// exponent, a number with the power folded in, or an annotation. Numbers
+/// that come to one are left out.
+fn powered(expr: &OwnedUnitExpr, exponent: i32) -> Vec<OwnedUnitFactor> {
+    match expr {
+        OwnedUnitExpr::Product(factors) => factors
+            .iter()
+            .flat_map(|factor| powered(&factor.expr, factor.exponent * exponent))
+            .collect(),
+        OwnedUnitExpr::Quotient(num, den) => {
+            let mut factors = powered(num, exponent);
+            factors.extend(powered(den, -exponent));
+            factors
+        }
+        OwnedUnitExpr::Power(inner, power) => powered(inner, power * exponent),
+        OwnedUnitExpr::Numeric(value) => match value.powi(exponent) {
+            1.0 => Vec::new(),
+            value => vec![unit_factor(OwnedUnitExpr::Numeric(value), 1)],
+        },
+        OwnedUnitExpr::Symbol(_) => vec![unit_factor(expr.clone(), exponent)],
+        // An annotation is unity whatever its power
+        OwnedUnitExpr::Annotation(_) => vec![unit_factor(expr.clone(), 1)],
+        OwnedUnitExpr::Annotated(inner, text) if is_annotatable(inner) => {
+            let inner = from_factors(powered(inner, exponent));
+            vec![unit_factor(
+                OwnedUnitExpr::Annotated(Box::new(inner), text.clone()),
+                1,
+            )]
+        }
+        OwnedUnitExpr::Annotated(inner, text) => {
+            let mut factors = powered(inner, exponent);
+            factors.push(unit_factor(OwnedUnitExpr::Annotation(text.clone()), 1));
+            factors
+        }
+    }
+}
+
+/// Whether the grammar lets `expr` carry an annotation: a number, or an
+/// atom with or without its exponent.
+fn is_annotatable(expr: &OwnedUnitExpr) -> bool {
+    match expr {
+        OwnedUnitExpr::Symbol(_) | OwnedUnitExpr::Numeric(_) => true,
+        OwnedUnitExpr::Power(inner, _) => matches!(**inner, OwnedUnitExpr::Symbol(_)),
+        _ => false,
+    }
+}
+
+fn unit_factor(expr: OwnedUnitExpr, exponent: i32) -> OwnedUnitFactor {
+    OwnedUnitFactor { expr, exponent }
+}
+
+/// The expression for a list of factors from [`powered`].
+fn from_factors(mut factors: Vec<OwnedUnitFactor>) -> OwnedUnitExpr {
+    match factors.len() {
+        0 => OwnedUnitExpr::Numeric(1.0),
+        1 if factors[0].exponent == 1 => factors.remove(0).expr,
+        1 => {
+            let factor = factors.remove(0);
+            OwnedUnitExpr::Power(Box::new(factor.expr), factor.exponent)
+        }
+        _ => OwnedUnitExpr::Product(factors),
+    }
+}
+
+/// `text` of `expr` as a single component of a product.
+fn component(text: &str, expr: &OwnedUnitExpr) -> String {
+    match expr {
+        OwnedUnitExpr::Product(_) | OwnedUnitExpr::Quotient(..) => format!("({text})"),
+        _ => text.to_string(),
+    }
+}
+
+/// Append a factor to a product. A number after another number is
+/// parenthesised, as `4.10` would read as a decimal.
+fn append_factor(out: &mut String, text: &str) {
+    let after_number = out
+        .rsplit(['.', '/', '('])
+        .next()
+        .is_some_and(|last| !last.is_empty() && last.chars().all(|c| "0123456789*+-".contains(c)));
+    if out.is_empty() {
+        out.push_str(text);
+    } else if after_number && text.starts_with(|c: char| c.is_ascii_digit()) {
+        out.push_str(&format!(".({text})"));
+    } else {
+        out.push('.');
+        out.push_str(text);
+    }
+}
+
+fn is_one(expr: &OwnedUnitExpr) -> bool {
+    matches!(expr, OwnedUnitExpr::Numeric(value) if *value == 1.0)
+}
+
+/// A UCUM number: an integer, a power of ten or a power of ten times an
+/// integer (`10*-1.25` for 2.5).
+fn number(value: f64) -> String {
+    // Shortest round-trip digits and their exponent
+    let scientific = format!("{value:e}");
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    let fraction = mantissa.split_once('.').map_or(0, |(_, f)| f.len()) as i32;
+    let digits = mantissa.replace('.', "");
+    let exponent = exponent - fraction;
+
+    if exponent >= 0 && (exponent < 3 || (digits != "1" && digits.len() as i32 + exponent <= 6)) {
+        format!("{digits}{}", "0".repeat(exponent as usize))
+    } else if digits == "1" {
+        format!("10*{exponent}")
+    } else {
+        format!("10*{exponent}.{digits}")
+    }
+}
+
+/// Drop unit factors and powers of one.
+fn simplify(expr: &OwnedUnitExpr) -> OwnedUnitExpr {
+    match expr {
+        OwnedUnitExpr::Product(factors) => {
+            let mut factors: Vec<OwnedUnitFactor> = factors
+                .iter()
+                .map(|factor| OwnedUnitFactor {
+                    expr: simplify(&factor.expr),
+                    exponent: factor.exponent,
+                })
+                .filter(|factor| factor.exponent != 0 && !is_one(&factor.expr))
+                .collect();
+            match factors.len() {
+                0 => OwnedUnitExpr::Numeric(1.0),
+                1 if factors[0].exponent == 1 => factors.remove(0).expr,
+                _ => OwnedUnitExpr::Product(factors),
+            }
+        }
+        OwnedUnitExpr::Quotient(num, den) => match simplify(den) {
+            den if is_one(&den) => simplify(num),
+            den => OwnedUnitExpr::Quotient(Box::new(simplify(num)), Box::new(den)),
+        },
+        OwnedUnitExpr::Power(inner, exponent) => match (simplify(inner), *exponent) {
+            (_, 0) => OwnedUnitExpr::Numeric(1.0),
+            (inner, 1) => inner,
+            (inner, exponent) => OwnedUnitExpr::Power(Box::new(inner), exponent),
+        },
+        OwnedUnitExpr::Annotated(inner, text) => {
+            OwnedUnitExpr::Annotated(Box::new(simplify(inner)), text.clone())
+        }
+        other => other.clone(),
+    }
+}
+
+/// The [`SerializeStyle::Normalized`] form.
+fn normalized(expr: &OwnedUnitExpr) -> String {
+    let mut coefficient = 1.0;
+    let mut terms: Vec<(OwnedUnitExpr, i32)> = Vec::new();
+    collect_terms(expr, 1, &mut coefficient, &mut terms);
+    terms.retain(|(_, exponent)| *exponent != 0);
+    let writer = Writer { minimal: false };
+
+    let mut out = String::new();
+    if coefficient != 1.0 {
+        out.push_str(&number(coefficient));
+    }
+    // Annotations, whose power does not matter, go with the positive terms
+    let is_positive = |(term, exponent): &&(OwnedUnitExpr, i32)| {
+        *exponent > 0 || matches!(term, OwnedUnitExpr::Annotation(_))
+    };
+    let positive = terms.iter().filter(is_positive);
+    let negative = terms.iter().filter(|term| !is_positive(term));
+    for (term, exponent) in positive.chain(negative) {
+        let text = match (term, *exponent) {
+            // An annotation is unity whatever its power
+            (OwnedUnitExpr::Annotation(text), _) => format!("{{{text}}}"),
+            (_, 1) => component(&writer.term(term, true), term),
+            // Keep the annotation after the exponent: `mg-1{creat}`
+            (OwnedUnitExpr::Annotated(inner, text), exponent)
+                if matches!(**inner, OwnedUnitExpr::Symbol(_)) =>
+            {
+                format!("{}{{{text}}}", writer.power(inner, exponent))
+            }
+            (term, exponent) => writer.power(term, exponent),
+        };
+        append_factor(&mut out, &text);
+    }
+    if out.is_empty() {
+        out.push('1');
+    }
+    out
+}
+
+/// Flatten `expr` raised to `exponent` into its numeric coefficient and its
+/// atoms with their summed exponents, in order of first appearance. An
+/// annotated group is split into the group and its annotation.
+fn collect_terms(
+    expr: &OwnedUnitExpr,
+    exponent: i32,
+    coefficient: &mut f64,
+    terms: &mut Vec<(OwnedUnitExpr, i32)>,
+) {
+    match expr {
+        OwnedUnitExpr::Product(factors) => {
+            for factor in factors {
+                collect_terms(&factor.expr, exponent * factor.exponent, coefficient, terms);
+            }
+        }
+        OwnedUnitExpr::Quotient(num, den) => {
+            collect_terms(num, exponent, coefficient, terms);
+            collect_terms(den, -exponent, coefficient, terms);
+        }
+        OwnedUnitExpr::Power(inner, power) => {
+            collect_terms(inner, exponent * power, coefficient, terms)
+        }
+        OwnedUnitExpr::Numeric(value) => *coefficient *= value.powi(exponent),
+        OwnedUnitExpr::Annotated(inner, text) => match &**inner {
+            OwnedUnitExpr::Symbol(_) => add_term(terms, expr.clone(), exponent),
+            // `m2{a}` is `m{a}` squared
+            OwnedUnitExpr::Power(atom, power) if matches!(**atom, OwnedUnitExpr::Symbol(_)) => {
+                let term = OwnedUnitExpr::Annotated(atom.clone(), text.clone());
+                add_term(terms, term, exponent * power)
+            }
+            _ => {
+                collect_terms(inner, exponent, coefficient, terms);
+                add_term(terms, OwnedUnitExpr::Annotation(text.clone()), exponent)
+            }
+        },
+        OwnedUnitExpr::Symbol(_) | OwnedUnitExpr::Annotation(_) => {
+            add_term(terms, expr.clone(), exponent)
+        }
+    }
+}
+
+fn add_term(terms: &mut Vec<(OwnedUnitExpr, i32)>, term: OwnedUnitExpr, exponent: i32) {
+    match terms.iter_mut().find(|(known, _)| *known == term) {
+        Some((_, total)) => *total += exponent,
+        None => terms.push((term, exponent)),
+    }
+}
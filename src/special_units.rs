@@ -5,7 +5,7 @@
 //! and other special cases defined in the UCUM specification.
 
 use crate::error::UcumError;
-use crate::precision::{Number, NumericOps, from_f64};
+use crate::precision::{Number, NumericOps, from_f64, from_f64_shortest};
 use crate::types::Dimension;
 use std::collections::HashMap;
 
@@ -59,6 +59,15 @@ pub trait SpecialUnitHandler: Send + Sync {
 
     /// Get the base conversion factor (for units that have a linear component).
     fn get_base_factor(&self, unit_code: &str) -> Number;
+
+    /// UCUM expression of the unit that [`convert_from`](Self::convert_from)
+    /// returns values in and [`convert_to`](Self::convert_to) expects them in,
+    /// e.g. `K` for temperatures or `W` for `B[W]`.
+    ///
+    /// [`crate::UcumEngine::convert`] evaluates it to reach canonical units.
+    fn base_unit(&self, _unit_code: &str) -> &'static str {
+        "1"
+    }
 }
 
 /// Context information for special unit conversions.
@@ -99,6 +108,56 @@ impl ConversionContext {
         self.parameters.insert(key, value);
         self
     }
+
+    /// Parameter holding the molar mass of the substance, in g/mol.
+    pub const MOLAR_MASS: &'static str = "molar_mass";
+    /// Parameter holding the charge (valence) of the substance, used for `eq`.
+    pub const CHARGE: &'static str = "charge";
+
+    /// Set the molar mass of the substance, in g/mol.
+    pub fn with_molar_mass(self, grams_per_mole: Number) -> Self {
+        self.with_parameter(Self::MOLAR_MASS.to_string(), grams_per_mole)
+    }
+
+    /// Set the charge of the substance: one mole of it is `|charge|` equivalents.
+    pub fn with_charge(self, charge: Number) -> Self {
+        self.with_parameter(Self::CHARGE.to_string(), charge)
+    }
+
+    /// The molar mass of the substance, in g/mol.
+    pub fn molar_mass(&self) -> Option<Number> {
+        self.parameters.get(Self::MOLAR_MASS).copied()
+    }
+
+    /// The charge of the substance.
+    pub fn charge(&self) -> Option<Number> {
+        self.parameters.get(Self::CHARGE).copied()
+    }
+}
+
+/// Result of [`crate::UcumEngine::convert_substance`].
+///
+/// `molar_mass` and `charge` are set when the conversion depended on them,
+/// so callers can tell a substance-specific result from a plain unit
+/// conversion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubstanceConversion {
+    /// The converted value.
+    pub value: Number,
+    /// Molar mass (g/mol) used to convert between mass and amount of substance.
+    pub molar_mass: Option<Number>,
+    /// Charge used to convert between moles and equivalents.
+    pub charge: Option<Number>,
+    /// Mass in grams of one `[IU]`, used to convert international units of
+    /// an analyte to mass, see [`crate::convert_for_analyte`].
+    pub grams_per_iu: Option<Number>,
+}
+
+impl SubstanceConversion {
+    /// Whether a substance-specific factor was applied.
+    pub fn is_substance_specific(&self) -> bool {
+        self.molar_mass.is_some() || self.charge.is_some() || self.grams_per_iu.is_some()
+    }
 }
 
 impl Default for ConversionContext {
@@ -173,16 +232,23 @@ impl Default for SpecialUnitRegistry {
         // Register default handlers
         registry.register(Box::new(TemperatureHandler::new()));
         registry.register(Box::new(LogarithmicHandler::new()));
+        registry.register(Box::new(TangentHandler::new()));
         registry.register(Box::new(ArbitraryHandler::new()));
 
         registry
     }
 }
 
+/// 0 °C in kelvin.
+const CELSIUS_ZERO: Number = Number::from_parts(27315, 0, 0, false, 2);
+/// 0 °F in degrees Rankine.
+const FAHRENHEIT_ZERO: Number = Number::from_parts(45967, 0, 0, false, 2);
+
 /// Handler for temperature units with offset support.
 ///
-/// Handles Celsius (°C) and Fahrenheit (°F) conversions which require
-/// both scaling and offset operations.
+/// Handles Celsius (°C), Fahrenheit (°F), Rankine and Réaumur conversions,
+/// which require both scaling and offset operations. The arithmetic is exact
+/// decimal arithmetic, so `20 Cel` is exactly `68 [degF]`.
 pub struct TemperatureHandler;
 
 impl Default for TemperatureHandler {
@@ -203,7 +269,7 @@ impl SpecialUnitHandler for TemperatureHandler {
     }
 
     fn can_handle(&self, unit_code: &str) -> bool {
-        matches!(unit_code, "Cel" | "[degF]" | "[degR]")
+        matches!(unit_code, "Cel" | "[degF]" | "[degR]" | "[degRe]")
     }
 
     fn convert_from(
@@ -215,15 +281,25 @@ impl SpecialUnitHandler for TemperatureHandler {
         match unit_code {
             "Cel" => {
                 // Celsius to Kelvin: K = °C + 273.15
-                Ok(value.add(from_f64(273.15)))
+                Ok(value.add(CELSIUS_ZERO))
             }
             "[degF]" => {
                 // Fahrenheit to Kelvin: K = (°F + 459.67) × 5/9
-                Ok(value.add(from_f64(459.67)).mul(from_f64(5.0 / 9.0)))
+                Ok(value
+                    .add(FAHRENHEIT_ZERO)
+                    .mul(Number::from(5))
+                    .div(Number::from(9)))
             }
             "[degR]" => {
                 // Rankine to Kelvin: K = °R × 5/9
-                Ok(value.mul(from_f64(5.0 / 9.0)))
+                Ok(value.mul(Number::from(5)).div(Number::from(9)))
+            }
+            "[degRe]" => {
+                // Réaumur to Kelvin: K = °Ré × 5/4 + 273.15
+                Ok(value
+                    .mul(Number::from(5))
+                    .div(Number::from(4))
+                    .add(CELSIUS_ZERO))
             }
             _ => Err(UcumError::conversion_error(
                 "temperature unit",
@@ -242,15 +318,25 @@ impl SpecialUnitHandler for TemperatureHandler {
         match unit_code {
             "Cel" => {
                 // Kelvin to Celsius: °C = K - 273.15
-                Ok(value.sub(from_f64(273.15)))
+                Ok(value.sub(CELSIUS_ZERO))
             }
             "[degF]" => {
                 // Kelvin to Fahrenheit: °F = K × 9/5 - 459.67
-                Ok(value.mul(from_f64(9.0 / 5.0)).sub(from_f64(459.67)))
+                Ok(value
+                    .mul(Number::from(9))
+                    .div(Number::from(5))
+                    .sub(FAHRENHEIT_ZERO))
             }
             "[degR]" => {
                 // Kelvin to Rankine: °R = K × 9/5
-                Ok(value.mul(from_f64(9.0 / 5.0)))
+                Ok(value.mul(Number::from(9)).div(Number::from(5)))
+            }
+            "[degRe]" => {
+                // Kelvin to Réaumur: °Ré = (K - 273.15) × 4/5
+                Ok(value
+                    .sub(CELSIUS_ZERO)
+                    .mul(Number::from(4))
+                    .div(Number::from(5)))
             }
             _ => Err(UcumError::conversion_error(
                 "temperature unit",
@@ -269,6 +355,10 @@ impl SpecialUnitHandler for TemperatureHandler {
         // Temperature units don't have a simple linear factor due to offsets
         Number::one()
     }
+
+    fn base_unit(&self, _unit_code: &str) -> &'static str {
+        "K"
+    }
 }
 
 /// Enhanced handler for logarithmic units with comprehensive support.
@@ -276,7 +366,10 @@ impl SpecialUnitHandler for TemperatureHandler {
 /// Supports:
 /// - Bel (B) and decibel (dB) - power ratios
 /// - Neper (Np) - amplitude ratios  
-/// - pH - hydrogen ion concentration
+/// - Bel units with a reference quantity (`B[W]`, `B[SPL]`, `B[V]`, ...),
+///   whose values are in bels; decibels are written with a prefix, e.g. `dB[W]`
+/// - pH (`[pH]`) - hydrogen ion concentration
+/// - homeopathic potencies (`[hp'_X]`, `[hp'_C]`, ...)
 /// - pK values for various chemical equilibria
 /// - Other logarithmic scales commonly used in science
 pub struct LogarithmicHandler {
@@ -307,7 +400,10 @@ impl LogarithmicHandler {
     /// Get the logarithmic base for a given unit
     fn get_base(&self, unit_code: &str) -> f64 {
         match unit_code {
-            "B" | "dB" | "pH" | "pOH" | "pKa" | "pKw" | "pK" => 10.0,
+            "B" | "dB" | "pH" | "[pH]" | "pOH" | "pKa" | "pKw" | "pK" | "[hp'_X]" => 10.0,
+            "[hp'_C]" => 100.0,
+            "[hp'_M]" => 1000.0,
+            "[hp'_Q]" => 50000.0,
             "B[SPL]" | "B[V]" | "B[mV]" | "B[uV]" | "B[10.nV]" | "B[W]" | "B[kW]" => 10.0,
             "Np" => std::f64::consts::E,
             "ln" => std::f64::consts::E,
@@ -320,10 +416,13 @@ impl LogarithmicHandler {
     /// Get the scale factor for a logarithmic unit
     fn get_scale_factor(&self, unit_code: &str) -> f64 {
         match unit_code {
-            "dB" => 10.0,                                               // Decibel: 10 * log10
-            "pH" | "pOH" | "pKa" | "pKw" | "pK" => -1.0,                // p-scales: -log10
-            "B[SPL]" | "B[V]" | "B[mV]" | "B[uV]" | "B[10.nV]" => 20.0, // Field quantities: 20 * log10 (decibel scale)
-            "B[W]" | "B[kW]" => 10.0, // Power quantities: 10 * log10 (decibel scale)
+            "dB" => 10.0, // Decibel: 10 * log10
+            // p-scales and potencies: -log
+            "pH" | "[pH]" | "pOH" | "pKa" | "pKw" | "pK" => -1.0,
+            "[hp'_X]" | "[hp'_C]" | "[hp'_M]" | "[hp'_Q]" => -1.0,
+            // Field quantities: 2 * log10 (the power ratio is the square)
+            "B[SPL]" | "B[V]" | "B[mV]" | "B[uV]" | "B[10.nV]" => 2.0,
+            // Power quantities (B[W], B[kW]) and plain bels: log10
             _ => 1.0,
         }
     }
@@ -363,6 +462,11 @@ impl SpecialUnitHandler for LogarithmicHandler {
             "B" | "dB"
                 | "Np"
                 | "pH"
+                | "[pH]"
+                | "[hp'_X]"
+                | "[hp'_C]"
+                | "[hp'_M]"
+                | "[hp'_Q]"
                 | "pOH"
                 | "pKa"
                 | "pKw"
@@ -393,44 +497,48 @@ impl SpecialUnitHandler for LogarithmicHandler {
         match unit_code {
             "B" => {
                 // Bel: 10^value (power ratio)
-                Ok(from_f64(base.powf(val)))
+                Ok(power(base, val))
             }
             "dB" => {
                 // Decibel: 10^(value/10) (power ratio)
-                Ok(from_f64(base.powf(val / scale)))
+                Ok(power(base, val / scale))
             }
             "Np" => {
                 // Neper: e^value (amplitude ratio)
-                Ok(from_f64(base.powf(val)))
+                Ok(power(base, val))
             }
             "B[SPL]" | "B[V]" | "B[mV]" | "B[uV]" | "B[10.nV]" | "B[W]" | "B[kW]" => {
                 // Specialized bel units: reference_value * 10^(value/scale)
                 let reference = self.get_bel_reference(unit_code);
-                Ok(from_f64(reference * base.powf(val / scale)))
+                Ok(from_f64_shortest(reference).mul(power(base, val / scale)))
             }
-            "pH" => {
+            "pH" | "[pH]" => {
                 // pH to hydrogen ion concentration: [H+] = 10^(-pH)
-                Ok(from_f64(base.powf(scale * val)))
+                Ok(power(base, scale * val))
+            }
+            "[hp'_X]" | "[hp'_C]" | "[hp'_M]" | "[hp'_Q]" => {
+                // Potency to dilution: 10^(-X), 100^(-C), ...
+                Ok(power(base, scale * val))
             }
             "pOH" => {
                 // pOH to hydroxide ion concentration: [OH-] = 10^(-pOH)
-                Ok(from_f64(base.powf(scale * val)))
+                Ok(power(base, scale * val))
             }
             "pKa" | "pKw" | "pK" => {
                 // pK to equilibrium constant: K = 10^(-pK)
-                Ok(from_f64(base.powf(scale * val)))
+                Ok(power(base, scale * val))
             }
             "ln" => {
                 // Natural logarithm: e^value
-                Ok(from_f64(base.powf(val)))
+                Ok(power(base, val))
             }
             "log" => {
                 // Common logarithm: 10^value
-                Ok(from_f64(base.powf(val)))
+                Ok(power(base, val))
             }
             "log2" => {
                 // Binary logarithm: 2^value
-                Ok(from_f64(base.powf(val)))
+                Ok(power(base, val))
             }
             _ => Err(UcumError::conversion_error(
                 "logarithmic unit",
@@ -450,23 +558,22 @@ impl SpecialUnitHandler for LogarithmicHandler {
 
         // Check for valid input ranges
         match unit_code {
-            "pH" | "pOH" | "pKa" | "pKw" | "pK" => {
-                if val <= 0.0 {
-                    return Err(UcumError::conversion_error(
-                        "concentration",
-                        "p-scale",
-                        "Cannot calculate p-scale of non-positive concentration",
-                    ));
-                }
-            }
-            "B" | "dB" | "Np" | "ln" | "log" | "log2" => {
-                if val <= 0.0 {
-                    return Err(UcumError::conversion_error(
-                        "value",
-                        "logarithm",
-                        "Cannot take logarithm of non-positive value",
-                    ));
-                }
+            "pH" | "[pH]" | "pOH" | "pKa" | "pKw" | "pK" if val <= 0.0 => {
+                return Err(UcumError::conversion_error(
+                    "concentration",
+                    "p-scale",
+                    "Cannot calculate p-scale of non-positive concentration",
+                ));
+            }
+            "B" | "dB" | "Np" | "ln" | "log" | "log2" | "[hp'_X]" | "[hp'_C]" | "[hp'_M]"
+            | "[hp'_Q]"
+                if val <= 0.0 =>
+            {
+                return Err(UcumError::conversion_error(
+                    "value",
+                    "logarithm",
+                    "Cannot take logarithm of non-positive value",
+                ));
             }
             _ => {}
         }
@@ -477,11 +584,11 @@ impl SpecialUnitHandler for LogarithmicHandler {
         match unit_code {
             "B" => {
                 // To Bel: log10(value)
-                Ok(from_f64(val.log(base)))
+                Ok(from_f64(log(val, base)))
             }
             "dB" => {
                 // To Decibel: 10 * log10(value)
-                Ok(from_f64(scale * val.log(base)))
+                Ok(from_f64(scale * log(val, base)))
             }
             "Np" => {
                 // To Neper: ln(value)
@@ -490,19 +597,23 @@ impl SpecialUnitHandler for LogarithmicHandler {
             "B[SPL]" | "B[V]" | "B[mV]" | "B[uV]" | "B[10.nV]" | "B[W]" | "B[kW]" => {
                 // To specialized bel units: scale * log10(value/reference)
                 let reference = self.get_bel_reference(unit_code);
-                Ok(from_f64(scale * (val / reference).log(base)))
+                Ok(from_f64(scale * log(val / reference, base)))
             }
-            "pH" => {
+            "pH" | "[pH]" => {
                 // Hydrogen ion concentration to pH: pH = -log10([H+])
-                Ok(from_f64(scale * val.log(base)))
+                Ok(from_f64(scale * log(val, base)))
+            }
+            "[hp'_X]" | "[hp'_C]" | "[hp'_M]" | "[hp'_Q]" => {
+                // Dilution to potency: -log(dilution)
+                Ok(from_f64(scale * log(val, base)))
             }
             "pOH" => {
                 // Hydroxide ion concentration to pOH: pOH = -log10([OH-])
-                Ok(from_f64(scale * val.log(base)))
+                Ok(from_f64(scale * log(val, base)))
             }
             "pKa" | "pKw" | "pK" => {
                 // Equilibrium constant to pK: pK = -log10(K)
-                Ok(from_f64(scale * val.log(base)))
+                Ok(from_f64(scale * log(val, base)))
             }
             "ln" => {
                 // To natural logarithm: ln(value)
@@ -526,10 +637,9 @@ impl SpecialUnitHandler for LogarithmicHandler {
 
     fn get_dimension(&self, unit_code: &str) -> Dimension {
         match unit_code {
-            "pH" | "pOH" => {
-                // pH and pOH represent concentration, but are dimensionless in practice
-                // In UCUM, they're treated as dimensionless logarithmic scales
-                Dimension([0, 0, 0, 0, 0, 0, 0])
+            "pH" | "[pH]" | "pOH" => {
+                // The scales are defined on a concentration [N L^-3]
+                Dimension([0, -3, 0, 0, 0, 1, 0])
             }
             "pKa" | "pKw" | "pK" => {
                 // Equilibrium constants are dimensionless
@@ -557,6 +667,84 @@ impl SpecialUnitHandler for LogarithmicHandler {
     fn get_base_factor(&self, _unit_code: &str) -> Number {
         Number::one()
     }
+
+    fn base_unit(&self, unit_code: &str) -> &'static str {
+        match unit_code {
+            "pH" | "[pH]" | "pOH" => "mol/L",
+            "B[SPL]" => "Pa",
+            "B[V]" | "B[mV]" | "B[uV]" | "B[10.nV]" => "V",
+            "B[W]" | "B[kW]" => "W",
+            _ => "1",
+        }
+    }
+}
+
+/// Handler for angles expressed through their tangent.
+///
+/// The prism diopter `[p'diop]` and the percent of slope `%[slope]` give an
+/// angle θ as `100 tan θ`.
+pub struct TangentHandler;
+
+impl Default for TangentHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TangentHandler {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl SpecialUnitHandler for TangentHandler {
+    fn name(&self) -> &'static str {
+        "Tangent"
+    }
+
+    fn can_handle(&self, unit_code: &str) -> bool {
+        matches!(unit_code, "[p'diop]" | "%[slope]")
+    }
+
+    fn convert_from(
+        &self,
+        value: Number,
+        _unit_code: &str,
+        _context: &ConversionContext,
+    ) -> Result<Number, UcumError> {
+        // θ = atan(value / 100), in radians
+        Ok(from_f64((value.to_f64() / 100.0).atan()))
+    }
+
+    fn convert_to(
+        &self,
+        value: Number,
+        _unit_code: &str,
+        _context: &ConversionContext,
+    ) -> Result<Number, UcumError> {
+        let angle = value.to_f64();
+        if angle.abs() >= std::f64::consts::FRAC_PI_2 {
+            return Err(UcumError::conversion_error(
+                "angle",
+                "tangent",
+                "Angles of 90 degrees or more have no finite tangent",
+            ));
+        }
+        Ok(from_f64(100.0 * angle.tan()))
+    }
+
+    fn get_dimension(&self, _unit_code: &str) -> Dimension {
+        // Plane angles are dimensionless in the SI vector
+        Dimension([0, 0, 0, 0, 0, 0, 0])
+    }
+
+    fn get_base_factor(&self, _unit_code: &str) -> Number {
+        Number::one()
+    }
+
+    fn base_unit(&self, _unit_code: &str) -> &'static str {
+        "rad"
+    }
 }
 
 /// Handler for arbitrary units (square-bracketed units).
@@ -614,6 +802,27 @@ impl SpecialUnitHandler for ArbitraryHandler {
     }
 }
 
+/// `base^exponent`, exact for the integer exponents that decibel and p-scale
+/// values usually are.
+fn power(base: f64, exponent: f64) -> Number {
+    let approx = base.powf(exponent);
+    if exponent.fract() == 0.0 && (1e-20..=1e20).contains(&approx) {
+        from_f64_shortest(base).pow(exponent as i32)
+    } else {
+        from_f64(approx)
+    }
+}
+
+/// `log_base(value)`, going through `log10` so that exact powers of ten give
+/// exact results.
+fn log(value: f64, base: f64) -> f64 {
+    if base == std::f64::consts::E {
+        value.ln()
+    } else {
+        value.log10() / base.log10()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -640,6 +849,13 @@ mod tests {
             .convert_from(from_f64(32.0), "[degF]", &context)
             .unwrap();
         assert!((fahrenheit_to_kelvin.to_f64() - 273.15).abs() < 1e-10);
+
+        // Celsius to Fahrenheit round-trips exactly through Kelvin
+        let kelvin = handler
+            .convert_from(Number::from(20), "Cel", &context)
+            .unwrap();
+        let fahrenheit = handler.convert_to(kelvin, "[degF]", &context).unwrap();
+        assert_eq!(fahrenheit, Number::from(68));
     }
 
     #[test]
@@ -676,14 +892,14 @@ mod tests {
         println!("  Scale: {}", handler.get_scale_factor("B[SPL]"));
         println!("  Reference: {}", handler.get_bel_reference("B[SPL]"));
 
-        // 60 dB SPL should be 20 μPa * 10^(60/20) = 20 μPa * 1000 = 0.02 Pa
+        // 6 B[SPL] (60 dB) should be 20 μPa * 10^(6/2) = 20 μPa * 1000 = 0.02 Pa
         let spl_60db = handler
-            .convert_from(from_f64(60.0), "B[SPL]", &context)
+            .convert_from(from_f64(6.0), "B[SPL]", &context)
             .unwrap();
-        println!("60 dB SPL = {} Pa (expected 0.02)", spl_60db.to_f64());
+        println!("6 B[SPL] = {} Pa (expected 0.02)", spl_60db.to_f64());
 
-        // Manual calculation should be: 2e-5 * 10^(60/20) = 2e-5 * 10^3 = 0.02
-        let manual_calc = 2e-5 * 10f64.powf(60.0 / 20.0);
+        // Manual calculation should be: 2e-5 * 10^(6/2) = 2e-5 * 10^3 = 0.02
+        let manual_calc = 2e-5 * 10f64.powf(6.0 / 2.0);
         println!("Correct manual calc: {manual_calc} Pa");
 
         assert!((spl_0db.to_f64() - 2e-5).abs() < 1e-10);
@@ -696,12 +912,11 @@ mod tests {
             .unwrap();
         assert!((v_0db.to_f64() - 1.0).abs() < 1e-10);
 
-        // 20 dB V should be 1 V * 10^(20/20) = 10 V
+        // 2 B[V] (20 dB) should be 1 V * 10^(2/2) = 10 V
         let v_20db = handler
-            .convert_from(from_f64(20.0), "B[V]", &context)
+            .convert_from(from_f64(2.0), "B[V]", &context)
             .unwrap();
-        println!("20 dB V = {} V (expected 10.0)", v_20db.to_f64());
-        // Actually with our scale of 20, it should be 1 V * 10^(20/20) = 10 V
+        println!("2 B[V] = {} V (expected 10.0)", v_20db.to_f64());
         assert!((v_20db.to_f64() - 10.0).abs() < 1e-10);
 
         // Test B[mV] (millivolt level)
@@ -718,11 +933,11 @@ mod tests {
             .unwrap();
         assert!((w_0db.to_f64() - 1.0).abs() < 1e-10);
 
-        // 10 dB W should be 1 W * 10^(10/10) = 10 W
+        // 1 B[W] (10 dB) should be 1 W * 10^1 = 10 W
         let w_10db = handler
-            .convert_from(from_f64(10.0), "B[W]", &context)
+            .convert_from(from_f64(1.0), "B[W]", &context)
             .unwrap();
-        println!("10 dB W = {} W (expected 10.0)", w_10db.to_f64());
+        println!("1 B[W] = {} W (expected 10.0)", w_10db.to_f64());
         assert!((w_10db.to_f64() - 10.0).abs() < 1e-10);
     }
 
@@ -732,28 +947,28 @@ mod tests {
         let context = ConversionContext::new();
 
         // Test conversion to B[SPL]
-        // 0.02 Pa should be 60 dB SPL: 20*log10(0.02/2e-5) = 20*log10(1000) = 20*3 = 60
+        // 0.02 Pa should be 6 B[SPL]: 2*log10(0.02/2e-5) = 2*log10(1000) = 2*3 = 6
         let pa_to_spl = handler
             .convert_to(from_f64(0.02), "B[SPL]", &context)
             .unwrap();
-        println!("0.02 Pa to dB SPL = {} (expected 60.0)", pa_to_spl.to_f64());
-        assert!((pa_to_spl.to_f64() - 60.0).abs() < 1e-10);
+        println!("0.02 Pa to B[SPL] = {} (expected 6.0)", pa_to_spl.to_f64());
+        assert!((pa_to_spl.to_f64() - 6.0).abs() < 1e-10);
 
         // Test conversion to B[V]
-        // 10 V should be 20 dB V: 20*log10(10/1) = 20*1 = 20
+        // 10 V should be 2 B[V]: 2*log10(10/1) = 2*1 = 2
         let v_to_dbv = handler
             .convert_to(from_f64(10.0), "B[V]", &context)
             .unwrap();
-        println!("10 V to dB V = {} (expected 20.0)", v_to_dbv.to_f64());
-        assert!((v_to_dbv.to_f64() - 20.0).abs() < 1e-10);
+        println!("10 V to B[V] = {} (expected 2.0)", v_to_dbv.to_f64());
+        assert!((v_to_dbv.to_f64() - 2.0).abs() < 1e-10);
 
         // Test conversion to B[W]
-        // 10 W should be 10 dB W: 10*log10(10/1) = 10*1 = 10
+        // 10 W should be 1 B[W]: log10(10/1) = 1
         let w_to_dbw = handler
             .convert_to(from_f64(10.0), "B[W]", &context)
             .unwrap();
-        println!("10 W to dB W = {} (expected 10.0)", w_to_dbw.to_f64());
-        assert!((w_to_dbw.to_f64() - 10.0).abs() < 1e-10);
+        println!("10 W to B[W] = {} (expected 1.0)", w_to_dbw.to_f64());
+        assert!((w_to_dbw.to_f64() - 1.0).abs() < 1e-10);
     }
 
     #[test]
@@ -1,6 +1,6 @@
 //! Suggestion engine for error corrections and unit alternatives (Phase 5).
 
-use crate::{UnitRecord, get_all_units};
+use crate::{UnitRecord, registry};
 use std::collections::HashMap;
 
 /// Suggestion engine for providing error corrections and alternatives
@@ -8,13 +8,18 @@ pub struct SuggestionEngine {
     /// Pre-computed unit similarity map for fast lookups
     unit_similarity: HashMap<String, Vec<String>>,
     /// All available units for fuzzy matching
-    all_units: Vec<&'static UnitRecord>,
+    all_units: Vec<UnitRecord>,
 }
 
 impl SuggestionEngine {
-    /// Create a new suggestion engine
+    /// Create a new suggestion engine over the built-in units
     pub fn new() -> Self {
-        let all_units: Vec<_> = get_all_units().iter().collect();
+        Self::from_units(registry::UNITS)
+    }
+
+    /// Create a suggestion engine over an arbitrary set of units
+    pub fn from_units(units: &[UnitRecord]) -> Self {
+        let all_units = units.to_vec();
         let unit_similarity = Self::build_similarity_map(&all_units);
 
         Self {
@@ -24,7 +29,7 @@ impl SuggestionEngine {
     }
 
     /// Build a similarity map for fast unit lookups
-    fn build_similarity_map(units: &[&'static UnitRecord]) -> HashMap<String, Vec<String>> {
+    fn build_similarity_map(units: &[UnitRecord]) -> HashMap<String, Vec<String>> {
         let mut similarity_map = HashMap::new();
 
         for unit in units {
@@ -33,7 +38,7 @@ impl SuggestionEngine {
             // Find units with similar codes
             for other in units {
                 if unit.code != other.code {
-                    let similarity = Self::string_similarity(unit.code, other.code);
+                    let similarity = Self::string_similarity(&unit.code, &other.code);
                     if similarity > 0.6 {
                         // 60% similarity threshold
                         similar.push(other.code.to_string());
@@ -44,7 +49,8 @@ impl SuggestionEngine {
             // Find units with similar display names
             for other in units {
                 if unit.code != other.code {
-                    let similarity = Self::string_similarity(unit.display_name, other.display_name);
+                    let similarity =
+                        Self::string_similarity(&unit.display_name, &other.display_name);
                     if similarity > 0.7 {
                         // 70% similarity threshold for display names
                         similar.push(other.code.to_string());
@@ -91,8 +97,8 @@ impl SuggestionEngine {
         for i in 0..=len1 {
             matrix[i][0] = i;
         }
-        for j in 0..=len2 {
-            matrix[0][j] = j;
+        for (j, cell) in matrix[0].iter_mut().enumerate() {
+            *cell = j;
         }
 
         let s1_chars: Vec<char> = s1.chars().collect();
@@ -133,7 +139,7 @@ impl SuggestionEngine {
         // Check for common typos and similar units
         let mut scored_suggestions = Vec::new();
         for unit in &self.all_units {
-            let similarity = Self::string_similarity(unit.code, invalid_unit);
+            let similarity = Self::string_similarity(&unit.code, invalid_unit);
             if similarity > 0.5 {
                 // 50% similarity threshold
                 scored_suggestions.push((
@@ -209,7 +215,7 @@ impl SuggestionEngine {
         // Check if this might be a missing or incorrect prefix
         for unit in &self.all_units {
             // Check if the invalid unit ends with a valid unit code
-            if invalid_unit.len() > unit.code.len() && invalid_unit.ends_with(&unit.code) {
+            if invalid_unit.len() > unit.code.len() && invalid_unit.ends_with(&*unit.code) {
                 let potential_prefix = &invalid_unit[..invalid_unit.len() - unit.code.len()];
                 if potential_prefix.len() <= 3 {
                     // Reasonable prefix length
@@ -313,7 +319,7 @@ impl SuggestionEngine {
         // Find units with the expected property
         for unit in &self.all_units {
             if unit.property.to_lowercase() == expected_property.to_lowercase() {
-                let similarity = Self::string_similarity(unit.code, found_unit);
+                let similarity = Self::string_similarity(&unit.code, found_unit);
                 if similarity > 0.3 {
                     suggestions.push(format!(
                         "Use '{}' ({}) for {}",
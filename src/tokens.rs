@@ -0,0 +1,279 @@
+//! Token stream of UCUM expressions for syntax highlighting.
+//!
+//! [`tokenize`] splits an expression into labelled tokens with their byte
+//! ranges: symbols are resolved against the registry so that a prefix and
+//! the atom it prefixes are separate tokens, and exponents, powers of ten
+//! and annotations are told apart from plain numbers. Unlike the parser it
+//! never fails; text that cannot form a token becomes an
+//! [`TokenKind::Error`] token and tokenizing carries on after it.
+
+use core::ops::Range;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::engine::{UcumEngine, atom_start, default_engine};
+
+/// What a [`Token`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TokenKind {
+    /// A unit prefix, `m` in `mg`.
+    Prefix,
+    /// A unit atom, `g` in `mg`.
+    Atom,
+    /// A unit atom written in square brackets, `[IU]` in `m[IU]`.
+    BracketedAtom,
+    /// An exponent, `2` in `s2` or `-1` in `m^-1`.
+    Exponent,
+    /// A number, `4` in `4.h`.
+    Number,
+    /// A power of ten, `10*3` or `10^-2`.
+    TenPower,
+    /// `.`, `/` or `^`.
+    Operator,
+    /// `(`.
+    OpenParen,
+    /// `)`.
+    CloseParen,
+    /// An annotation with its braces, `{cells}`.
+    Annotation,
+    /// Text that is not valid UCUM: an unknown symbol, a stray character or
+    /// an unterminated annotation or bracket.
+    Error,
+}
+
+/// A token of a UCUM expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Token<'a> {
+    /// What the token is.
+    pub kind: TokenKind,
+    /// Byte range of the token in the expression.
+    pub range: Range<usize>,
+    /// The text of the token.
+    pub text: &'a str,
+}
+
+/// Split a UCUM expression into tokens for highlighting, using the default
+/// engine's registry; see [`UcumEngine::tokenize`].
+///
+/// Whitespace is skipped, everything else is covered by a token.
+///
+/// # Examples
+///
+/// ```
+/// use octofhir_ucum::{TokenKind, tokenize};
+///
+/// let kinds: Vec<_> = tokenize("mg/dL{x}").iter().map(|t| (t.kind, t.text)).collect();
+/// assert_eq!(
+///     kinds,
+///     [
+///         (TokenKind::Prefix, "m"),
+///         (TokenKind::Atom, "g"),
+///         (TokenKind::Operator, "/"),
+///         (TokenKind::Prefix, "d"),
+///         (TokenKind::Atom, "L"),
+///         (TokenKind::Annotation, "{x}"),
+///     ]
+/// );
+/// ```
+pub fn tokenize(input: &str) -> Vec<Token<'_>> {
+    default_engine().tokenize(input)
+}
+
+/// Tokenize `input`, resolving symbols against the engine's registry.
+pub(crate) fn tokenize_with<'a>(engine: &UcumEngine, input: &'a str) -> Vec<Token<'a>> {
+    let mut lexer = Lexer {
+        engine,
+        input,
+        pos: 0,
+        tokens: Vec::new(),
+    };
+    lexer.run();
+    lexer.tokens
+}
+
+struct Lexer<'e, 'a> {
+    engine: &'e UcumEngine,
+    input: &'a str,
+    pos: usize,
+    tokens: Vec<Token<'a>>,
+}
+
+impl<'a> Lexer<'_, 'a> {
+    fn run(&mut self) {
+        while let Some(ch) = self.peek() {
+            let start = self.pos;
+            match ch {
+                _ if ch.is_whitespace() => self.pos += ch.len_utf8(),
+                '{' => self.annotation(),
+                '.' | '/' => {
+                    self.pos += 1;
+                    self.push(TokenKind::Operator, start);
+                }
+                '^' => {
+                    self.pos += 1;
+                    self.push(TokenKind::Operator, start);
+                    if !self.exponent() {
+                        self.relabel(TokenKind::Error);
+                    }
+                }
+                '(' => {
+                    self.pos += 1;
+                    self.push(TokenKind::OpenParen, start);
+                }
+                ')' => {
+                    self.pos += 1;
+                    self.push(TokenKind::CloseParen, start);
+                    self.exponent();
+                }
+                '0'..='9' => self.number(),
+                '[' | '%' | '\'' | 'µ' => self.symbol(),
+                _ if ch.is_ascii_alphabetic() => self.symbol(),
+                _ => {
+                    self.pos += ch.len_utf8();
+                    self.push(TokenKind::Error, start);
+                }
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn push(&mut self, kind: TokenKind, start: usize) {
+        self.tokens.push(Token {
+            kind,
+            range: start..self.pos,
+            text: &self.input[start..self.pos],
+        });
+    }
+
+    /// Change the kind of the last token.
+    fn relabel(&mut self, kind: TokenKind) {
+        if let Some(token) = self.tokens.last_mut() {
+            token.kind = kind;
+        }
+    }
+
+    /// Advance over ASCII digits, returning how many there were.
+    fn digits(&mut self) -> usize {
+        let count = self.rest().bytes().take_while(u8::is_ascii_digit).count();
+        self.pos += count;
+        count
+    }
+
+    /// Advance over an optional sign and digits, returning whether there
+    /// were digits; nothing is consumed otherwise.
+    fn signed_digits(&mut self) -> bool {
+        let start = self.pos;
+        if self.rest().starts_with(['+', '-']) {
+            self.pos += 1;
+        }
+        if self.digits() == 0 {
+            self.pos = start;
+            return false;
+        }
+        true
+    }
+
+    /// An exponent right after a term. Returns whether there was one.
+    fn exponent(&mut self) -> bool {
+        let start = self.pos;
+        let found = self.signed_digits();
+        if found {
+            self.push(TokenKind::Exponent, start);
+        }
+        found
+    }
+
+    /// A number or a power of ten.
+    fn number(&mut self) {
+        let start = self.pos;
+        if self.rest().starts_with("10*") || self.rest().starts_with("10^") {
+            self.pos += 3;
+            let kind = if self.signed_digits() {
+                TokenKind::TenPower
+            } else {
+                TokenKind::Error
+            };
+            self.push(kind, start);
+            return;
+        }
+
+        self.digits();
+        // A decimal point only when digits follow; otherwise it is a product
+        let rest = self.rest().as_bytes();
+        if rest.first() == Some(&b'.') && rest.get(1).is_some_and(u8::is_ascii_digit) {
+            self.pos += 1;
+            self.digits();
+        }
+        self.push(TokenKind::Number, start);
+    }
+
+    /// An annotation up to its closing brace.
+    fn annotation(&mut self) {
+        let start = self.pos;
+        match self.rest().find('}') {
+            Some(end) => {
+                self.pos += end + 1;
+                self.push(TokenKind::Annotation, start);
+            }
+            None => {
+                self.pos = self.input.len();
+                self.push(TokenKind::Error, start);
+            }
+        }
+    }
+
+    /// A symbol, split into its prefix and atom, followed by its exponent.
+    fn symbol(&mut self) {
+        let start = self.pos;
+        let mut terminated = true;
+        while let Some(ch) = self.peek() {
+            match ch {
+                '[' => match self.rest().find(']') {
+                    Some(end) => self.pos += end + 1,
+                    None => {
+                        self.pos = self.input.len();
+                        terminated = false;
+                    }
+                },
+                '_' | '\'' | '%' | 'µ' => self.pos += ch.len_utf8(),
+                _ if ch.is_ascii_alphabetic() => self.pos += 1,
+                _ => break,
+            }
+        }
+
+        let text = &self.input[start..self.pos];
+        let resolved = terminated
+            .then(|| self.engine.resolve_symbol(&text.replace('µ', "u")))
+            .flatten();
+        match resolved {
+            Some((_, unit)) => {
+                let atom_start = start + atom_start(text, &unit.code);
+                if atom_start > start {
+                    self.tokens.push(Token {
+                        kind: TokenKind::Prefix,
+                        range: start..atom_start,
+                        text: &self.input[start..atom_start],
+                    });
+                }
+                let kind = if unit.code.starts_with('[') {
+                    TokenKind::BracketedAtom
+                } else {
+                    TokenKind::Atom
+                };
+                self.push(kind, atom_start);
+            }
+            None => self.push(TokenKind::Error, start),
+        }
+        self.exponent();
+    }
+}
@@ -1,13 +1,20 @@
 use core::fmt;
+use std::borrow::Cow;
 
 /// SI prefix such as `k` (kilo) or `m` (milli).
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Prefix {
-    pub symbol: &'static str,
+    pub symbol: Cow<'static, str>,
+    /// Case-insensitive (c/i) form of the symbol, e.g. `MA` for mega.
+    pub ci_symbol: Cow<'static, str>,
     pub factor: f64,
-    pub exponent: i8,
-    pub display_name: &'static str,
+    /// Exact value of the prefix, e.g. `0.001` for milli.
+    pub exact_factor: crate::precision::Number,
+    /// Power of ten of the factor, e.g. `-3` for milli; `None` for a factor
+    /// that is not a power of ten, such as 1024 for `Ki`.
+    pub exponent: Option<i8>,
+    pub display_name: Cow<'static, str>,
 }
 
 /// Dimensional vector (M, L, T, I, Θ, N, J) per UCUM spec.
@@ -40,6 +47,49 @@ impl fmt::Display for Dimension {
     }
 }
 
+/// Base quantities counted by a [`Dimension`] vector.
+///
+/// Both models use the same seven slots for mass, length, time, temperature
+/// and luminous intensity. They differ in slots 3 and 5:
+///
+/// | Model  | Slot 3           | Slot 5               |
+/// |--------|------------------|----------------------|
+/// | `Si`   | electric current | amount of substance  |
+/// | `Ucum` | electric charge  | plane angle          |
+///
+/// Under `Ucum` the vector follows the base units of `ucum-essence.xml`
+/// (`g`, `m`, `s`, `C`, `K`, `rad`, `cd`), so `mol` is a dimensionless count,
+/// `rad` is no longer commensurable with `%`, and canonical forms match other
+/// UCUM implementations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DimensionModel {
+    /// SI base quantities (M, L, T, I, Θ, N, J).
+    #[default]
+    Si,
+    /// UCUM base units (g, m, s, C, K, rad, cd).
+    Ucum,
+}
+
+impl DimensionModel {
+    /// Symbols of the base units for each slot of a [`Dimension`].
+    pub const fn base_units(self) -> [&'static str; 7] {
+        match self {
+            DimensionModel::Si => ["kg", "m", "s", "A", "K", "mol", "cd"],
+            DimensionModel::Ucum => ["g", "m", "s", "C", "K", "rad", "cd"],
+        }
+    }
+
+    /// Symbols written in canonical unit strings for each slot of a
+    /// [`Dimension`]: the base units, with mass in gram as UCUM writes it.
+    pub const fn canonical_units(self) -> [&'static str; 7] {
+        match self {
+            DimensionModel::Si => ["g", "m", "s", "A", "K", "mol", "cd"],
+            DimensionModel::Ucum => self.base_units(),
+        }
+    }
+}
+
 /// Base unit record (e.g. metre, second, kelvin).
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -61,6 +111,10 @@ pub struct DerivedUnit {
 }
 
 /// Numerical quantity paired with a unit expression.
+///
+/// Arithmetic (`+ - * /`) is checked and returns `Result`; `<`, `<=`, `>`
+/// and `>=` compare across commensurable units, as do `approx_eq` and
+/// `compare` (see the `quantity` module).
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Quantity {
@@ -129,11 +183,21 @@ impl SpecialKind {
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnitRecord {
-    pub code: &'static str,
+    pub code: Cow<'static, str>,
+    /// Case-insensitive (c/i) form of the code, e.g. `[IU]` or `MOL`.
+    pub ci_code: Cow<'static, str>,
+    /// Whether the unit may be combined with a prefix.
+    pub is_metric: bool,
     pub dim: Dimension,
+    /// Dimension over UCUM's own base units, see [`DimensionModel::Ucum`].
+    pub ucum_dim: Dimension,
     pub factor: f64,
+    /// Exact factor relative to the base units, expanded from the definition.
+    pub exact_factor: crate::precision::Number,
     pub offset: f64,
+    /// Exact offset in the base units, e.g. `273.15` for `Cel`.
+    pub exact_offset: crate::precision::Number,
     pub special: SpecialKind,
-    pub property: &'static str,
-    pub display_name: &'static str,
+    pub property: Cow<'static, str>,
+    pub display_name: Cow<'static, str>,
 }
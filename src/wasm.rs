@@ -4,9 +4,9 @@
 //! It's only available when the "wasm" feature is enabled.
 
 use crate::{
-    UcumError, UnitRecord, analyse, evaluate_owned, find_unit, get_canonical_units, is_comparable,
-    parse_expression, precision::to_f64, search_units as core_search_units,
-    validate as core_validate,
+    ErrorKind, UcumError, UnitRecord, analyse, complete as core_complete,
+    complete_as as core_complete_as, default_engine, find_unit, get_canonical_units, is_comparable,
+    search_units as core_search_units, tokenize as core_tokenize, validate as core_validate,
 };
 use serde::{Deserialize, Serialize};
 use serde_wasm_bindgen::to_value;
@@ -47,6 +47,7 @@ pub struct JsCanonicalUnit {
     pub factor: f64,
     pub offset: f64,
     pub dimension: [i8; 7],
+    pub is_nonlinear: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -117,6 +118,7 @@ pub fn get_canonical(expression: &str) -> Result<JsValue, JsValue> {
                 factor: canonical.factor,
                 offset: canonical.offset,
                 dimension: canonical.dimension.0,
+                is_nonlinear: canonical.is_nonlinear,
             };
             Ok(to_value(&js_canonical)?)
         }
@@ -149,11 +151,40 @@ pub fn search(query: &str) -> JsValue {
     to_value(&search_result).unwrap_or(JsValue::NULL)
 }
 
+/// Tokens of an expression for syntax highlighting: an array of
+/// `{kind, range: {start, end}, text}`, with byte offsets into the UTF-8
+/// expression. Invalid text gives `Error` tokens rather than failing.
+#[wasm_bindgen]
+pub fn tokenize(expression: &str) -> JsValue {
+    to_value(&core_tokenize(expression)).unwrap_or(JsValue::NULL)
+}
+
+/// Completion candidates for the symbol or annotation at byte offset
+/// `cursor`, best first: an array of `{kind, text, display, range: {start,
+/// end}, score}`, where `range` is the text each candidate replaces.
+#[wasm_bindgen]
+pub fn complete(expression: &str, cursor: usize) -> JsValue {
+    to_value(&core_complete(expression, cursor)).unwrap_or(JsValue::NULL)
+}
+
+/// Completion candidates that keep `expression` commensurable with `unit`,
+/// as for a field bound to a kind of quantity.
+#[wasm_bindgen]
+pub fn complete_as(expression: &str, cursor: usize, unit: &str) -> Result<JsValue, JsValue> {
+    match core_complete_as(expression, cursor, unit) {
+        Ok(candidates) => Ok(to_value(&candidates)?),
+        Err(err) => {
+            let js_error = convert_error(err);
+            Err(to_value(&js_error)?)
+        }
+    }
+}
+
 #[wasm_bindgen]
 pub fn get_unit_info(code: &str) -> Result<JsValue, JsValue> {
     match find_unit(code) {
         Some(unit) => {
-            let unit_info = convert_unit_record(&unit);
+            let unit_info = convert_unit_record(unit);
             Ok(to_value(&unit_info)?)
         }
         None => {
@@ -227,50 +258,21 @@ pub fn evaluate_expression(expression: &str) -> Result<JsValue, JsValue> {
 
 #[wasm_bindgen]
 pub fn convert(value: f64, from_unit: &str, to_unit: &str) -> Result<f64, JsValue> {
-    let from_expr = match parse_expression(from_unit) {
-        Ok(expr) => expr,
-        Err(err) => {
-            let js_error = convert_error(err);
-            return Err(to_value(&js_error)?);
-        }
-    };
-
-    let to_expr = match parse_expression(to_unit) {
-        Ok(expr) => expr,
-        Err(err) => {
-            let js_error = convert_error(err);
-            return Err(to_value(&js_error)?);
-        }
-    };
-
-    let from_res = match evaluate_owned(&from_expr) {
-        Ok(res) => res,
-        Err(err) => {
-            let js_error = convert_error(err);
-            return Err(to_value(&js_error)?);
+    match default_engine().convert(value, from_unit, to_unit) {
+        Ok(result) => Ok(result),
+        Err(err) if matches!(err.kind, ErrorKind::DimensionMismatch { .. }) => {
+            let js_error = create_simple_js_error(
+                format!(
+                    "Incompatible dimensions between '{}' and '{}'",
+                    from_unit, to_unit
+                ),
+                "IncompatibleDimensions".to_string(),
+            );
+            Err(to_value(&js_error)?)
         }
-    };
-
-    let to_res = match evaluate_owned(&to_expr) {
-        Ok(res) => res,
         Err(err) => {
             let js_error = convert_error(err);
-            return Err(to_value(&js_error)?);
+            Err(to_value(&js_error)?)
         }
-    };
-
-    if from_res.dim != to_res.dim {
-        let js_error = create_simple_js_error(
-            format!(
-                "Incompatible dimensions between '{}' and '{}'",
-                from_unit, to_unit
-            ),
-            "IncompatibleDimensions".to_string(),
-        );
-        return Err(to_value(&js_error)?);
     }
-
-    let canonical = value * to_f64(from_res.factor) + to_f64(from_res.offset);
-    let result = (canonical - to_f64(to_res.offset)) / to_f64(to_res.factor);
-    Ok(result)
 }
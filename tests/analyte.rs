@@ -0,0 +1,107 @@
+use octofhir_ucum::precision::{Number, to_f64};
+use octofhir_ucum::{
+    CustomUnit, ErrorKind, UcumEngine, analytes, convert_for_analyte, find_analyte, validate,
+};
+
+fn convert(value: f64, from: &str, to: &str, analyte: &str) -> f64 {
+    let value = Number::try_from(value).unwrap();
+    to_f64(convert_for_analyte(value, from, to, analyte).unwrap().value)
+}
+
+#[test]
+fn catalogue_units_convert() {
+    for analyte in analytes() {
+        assert!(
+            validate(analyte.conventional_unit).is_ok(),
+            "{}",
+            analyte.code
+        );
+        assert!(validate(analyte.si_unit).is_ok(), "{}", analyte.code);
+        let result = analyte.convert(Number::ONE, analyte.conventional_unit, analyte.si_unit);
+        assert!(result.is_ok(), "{}: {:?}", analyte.code, result);
+        assert_eq!(find_analyte(analyte.name), Some(analyte));
+    }
+}
+
+#[test]
+fn mass_and_amount_of_substance() {
+    assert!((convert(1.0, "mg/dL", "umol/L", "creatinine") - 88.4017).abs() < 1e-4);
+    assert!((convert(200.0, "mg/dL", "mmol/L", "cholesterol") - 5.1726).abs() < 1e-4);
+    assert!((convert(10.0, "mg/dL", "mmol/L", "calcium") - 2.4951).abs() < 1e-4);
+
+    let result = convert_for_analyte(Number::ONE, "mmol/L", "mg/dL", "glucose").unwrap();
+    assert_eq!(result.value, Number::new(18016, 3));
+    assert_eq!(result.molar_mass, Some(Number::new(18016, 2)));
+    assert!(result.charge.is_none());
+}
+
+#[test]
+fn equivalents() {
+    assert_eq!(convert(140.0, "meq/L", "mmol/L", "sodium"), 140.0);
+    assert_eq!(convert(2.5, "mmol/L", "meq/L", "calcium"), 5.0);
+    assert!((convert(10.0, "mg/dL", "meq/L", "calcium") - 4.9903).abs() < 1e-4);
+
+    let result = convert_for_analyte(Number::ONE, "meq/L", "mmol/L", "chloride").unwrap();
+    assert_eq!(result.charge, Some(Number::NEGATIVE_ONE));
+}
+
+#[test]
+fn international_units() {
+    assert_eq!(convert(1000.0, "[IU]", "ug", "vitamin-d3"), 25.0);
+    assert_eq!(convert(5000.0, "[IU]", "mg", "heparin"), 50.0);
+    assert!((convert(10.0, "u[IU]/mL", "pmol/L", "insulin") - 59.745).abs() < 1e-3);
+
+    let result = convert_for_analyte(Number::TEN, "u[IU]/mL", "pmol/L", "insulin").unwrap();
+    assert!(result.grams_per_iu.is_some());
+    assert!(result.molar_mass.is_some());
+
+    // Same power of [IU] on both sides needs no factor
+    let result = convert_for_analyte(Number::ONE, "[IU]/mL", "[IU]/L", "heparin").unwrap();
+    assert_eq!(result.value, Number::ONE_THOUSAND);
+    assert!(!result.is_substance_specific());
+}
+
+#[test]
+fn missing_factors_are_errors() {
+    let error = convert_for_analyte(Number::ONE, "[IU]", "mg", "glucose").unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::ConversionError { .. }));
+
+    let error = convert_for_analyte(Number::ONE, "g/dL", "mmol/L", "albumin").unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::ConversionError { .. }));
+
+    let error = convert_for_analyte(Number::ONE, "mg", "g", "unobtainium").unwrap_err();
+    assert!(error.message.contains("Unknown analyte 'unobtainium'"));
+}
+
+#[test]
+fn overflowing_conversions_are_errors() {
+    for (from, to) in [("mg", "[IU]"), ("[IU]", "ug"), ("mg", "nmol")] {
+        let error = convert_for_analyte(Number::MAX, from, to, "insulin").unwrap_err();
+        assert!(
+            matches!(error.kind, ErrorKind::PrecisionOverflow { .. }),
+            "{from} to {to}: {error}"
+        );
+    }
+}
+
+#[test]
+fn engine_converts_analytes_with_its_units() {
+    let mut engine = UcumEngine::new();
+    engine
+        .register_unit(CustomUnit::new("deciliter", "dL"))
+        .unwrap();
+    let glucose = engine
+        .convert_for_analyte(Number::new(9008, 2), "mg/deciliter", "mmol/L", "glucose")
+        .unwrap();
+    assert_eq!(glucose.value, Number::from(5));
+    let insulin = engine
+        .convert_analyte(
+            find_analyte("insulin").unwrap(),
+            Number::ONE,
+            "[IU]/deciliter",
+            "mg/dL",
+        )
+        .unwrap();
+    assert_eq!(insulin.value, Number::new(347, 4));
+    assert!(convert_for_analyte(Number::ONE, "mg/deciliter", "mmol/L", "glucose").is_err());
+}
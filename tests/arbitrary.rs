@@ -1,6 +1,7 @@
 use octofhir_ucum::precision::{NumericOps, from_f64};
 use octofhir_ucum::{Dimension, EvalResult, UcumError, evaluate_owned, parse_expression};
 
+#[allow(clippy::result_large_err)]
 fn eval(expr: &str) -> Result<EvalResult, UcumError> {
     let ast = parse_expression(expr).expect("parse ok");
     println!("AST for {expr}: {ast:?}");
@@ -51,8 +52,8 @@ fn arbitrary_unit_multiplication() {
     let iu_per_ml = eval("[IU]/mL").unwrap();
     // Should have dimension of 1/volume (L^-3)
     assert_eq!(iu_per_ml.dim, Dimension([0, -3, 0, 0, 0, 0, 0]));
-    // 1 / (0.001 L) = 1000
-    assert!((iu_per_ml.factor.sub(from_f64(1000.0))).abs() < from_f64(1e-12));
+    // 1 / mL = 1 / 1e-6 m3 = 1e6
+    assert_eq!(iu_per_ml.factor, from_f64(1e6));
 }
 
 #[test]
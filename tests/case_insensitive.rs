@@ -0,0 +1,104 @@
+use octofhir_ucum::{
+    CustomUnit, ErrorKind, UcumEngine, evaluate_owned, find_prefix, find_unit, parse_expression,
+    parse_expression_ci, validate_ci,
+};
+
+fn ci(input: &str) -> String {
+    parse_expression_ci(input)
+        .expect("valid c/i expression")
+        .code
+}
+
+#[test]
+fn registry_carries_ci_codes() {
+    assert_eq!(find_unit("Pa").unwrap().ci_code, "PAL");
+    assert_eq!(find_unit("mol").unwrap().ci_code, "MOL");
+    assert!(find_unit("m").unwrap().is_metric);
+    assert!(!find_unit("[in_i]").unwrap().is_metric);
+    assert_eq!(find_prefix("M").unwrap().ci_symbol, "MA");
+    assert_eq!(find_prefix("m").unwrap().ci_symbol, "M");
+}
+
+#[test]
+fn maps_to_case_sensitive_codes() {
+    assert_eq!(ci("MG/DL"), "mg/dL");
+    assert_eq!(ci("[IU]/ML"), "[IU]/mL");
+    assert_eq!(ci("KG.M/S2"), "kg.m/s2");
+    assert_eq!(ci("MMOL/L"), "mmol/L");
+    assert_eq!(ci("MM[HG]"), "mm[Hg]");
+    assert_eq!(ci("PAL"), "Pa");
+    assert_eq!(ci("MAL"), "ML");
+    assert_eq!(ci("CEL"), "Cel");
+    assert_eq!(ci("[DEGF]"), "[degF]");
+}
+
+#[test]
+fn lower_case_input_is_accepted() {
+    assert_eq!(ci("pal"), "Pa");
+    assert_eq!(ci("Mmol/l"), "mmol/l");
+    assert_eq!(ci("mg/dl"), "mg/dl");
+}
+
+#[test]
+fn numbers_exponents_and_annotations_are_preserved() {
+    assert_eq!(ci("10*3/UL"), "10*3/uL");
+    assert_eq!(ci("M-1"), "m-1");
+    assert_eq!(ci("G/(24.HR)"), "g/(24.h)");
+    assert_eq!(ci("{CELLS}/UL"), "{CELLS}/uL");
+}
+
+#[test]
+fn exact_atom_wins_over_prefix_split() {
+    // `CD` is candela, not centi-day (day is not metric anyway).
+    let parsed = parse_expression_ci("CD").unwrap();
+    assert_eq!(parsed.code, "cd");
+    assert!(parsed.ambiguities.is_empty());
+}
+
+#[test]
+fn equivalent_readings_are_reported() {
+    let parsed = parse_expression_ci("L").unwrap();
+    assert_eq!(parsed.code, "L");
+    assert_eq!(parsed.ambiguities.len(), 1);
+    let ambiguity = &parsed.ambiguities[0];
+    assert_eq!(ambiguity.chosen, "L");
+    assert_eq!(ambiguity.alternatives, vec!["l".to_string()]);
+    assert!(ambiguity.equivalent);
+    assert!(!parsed.is_ambiguous());
+
+    // Verbatim spelling is preferred when it matches a reading.
+    assert_eq!(ci("l"), "l");
+}
+
+#[test]
+fn ci_and_cs_evaluate_identically() {
+    let from_ci = validate_ci("MG/DL").unwrap();
+    let a = evaluate_owned(&from_ci.expression).unwrap();
+    let b = evaluate_owned(&parse_expression("mg/dL").unwrap()).unwrap();
+    assert_eq!(a.dim, b.dim);
+    assert_eq!(a.factor, b.factor);
+}
+
+#[test]
+fn unknown_symbol_reports_span() {
+    let err = parse_expression_ci("MG/FOO").unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::UnitNotFound { ref unit, .. } if unit == "FOO"));
+    let span = err.span.expect("span");
+    assert_eq!((span.start, span.end), (3, 6));
+    assert!(validate_ci("FOO").is_err());
+}
+
+#[test]
+fn engine_registry_units_resolve_case_insensitively() {
+    let mut engine = UcumEngine::new();
+    engine
+        .register_unit(CustomUnit::new("tablet", "1").metric())
+        .unwrap();
+
+    assert_eq!(engine.parse_ci("TABLET/D").unwrap().code, "tablet/d");
+    assert_eq!(engine.validate_ci("KTABLET").unwrap().code, "ktablet");
+    assert_eq!(engine.registry().find_units_ci("TABLET").len(), 1);
+
+    // Registration is local to the engine
+    assert!(parse_expression_ci("TABLET").is_err());
+}
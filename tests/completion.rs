@@ -0,0 +1,72 @@
+use octofhir_ucum::{CompletionKind, complete, complete_as};
+
+fn texts(candidates: &[octofhir_ucum::Completion]) -> Vec<&str> {
+    candidates.iter().map(|c| c.text.as_str()).collect()
+}
+
+#[test]
+fn candidates_for_the_text_under_the_cursor() {
+    let candidates = complete("mg/d.s", 4);
+    assert_eq!(
+        (candidates[0].kind, candidates[0].text.as_str()),
+        (CompletionKind::Atom, "d")
+    );
+    assert!(candidates.iter().all(|c| c.range == (3..4)));
+    let found = |text: &str, kind| candidates.iter().any(|c| c.text == text && c.kind == kind);
+    assert!(found("dL", CompletionKind::Atom));
+    assert!(found("d", CompletionKind::Prefix));
+    assert!(found("deg", CompletionKind::Atom));
+
+    // A prefix and its atom are replaced together, up to the brackets
+    let candidates = complete("kg.m[H", 6);
+    assert_eq!(texts(&candidates), ["m[Hg]", "m[H2O]"]);
+    assert_eq!(candidates[0].range, 3..6);
+    assert_eq!(texts(&complete("[lb_a", 5)), ["[lb_ap]", "[lb_av]"]);
+    assert_eq!(complete("[lb_a", 5)[0].kind, CompletionKind::BracketedAtom);
+    assert_eq!(texts(&complete("µ", 2))[0], "u");
+
+    // Similar atoms when nothing starts with the typed text
+    assert!(texts(&complete("mgg", 3)).contains(&"mg"));
+
+    // Annotations inside braces and after a complete term
+    let candidates = complete("mg{cel}", 6);
+    assert_eq!(texts(&candidates), ["{cells}"]);
+    assert_eq!(candidates[0].range, 2..7);
+    let candidates = complete("mg2", 3);
+    assert!(
+        candidates
+            .iter()
+            .all(|c| c.kind == CompletionKind::Annotation && c.range == (3..3))
+    );
+
+    assert!(complete("4.5", 2).is_empty());
+}
+
+#[test]
+fn candidates_fit_the_expected_dimension() {
+    // After mg/, only a volume gives a mass concentration
+    let candidates = complete_as("mg/", 3, "g/L").unwrap();
+    let found = |text: &str, kind| candidates.iter().any(|c| c.text == text && c.kind == kind);
+    assert!(found("L", CompletionKind::Atom));
+    assert!(found("[gal_us]", CompletionKind::BracketedAtom));
+    assert!(!found("m", CompletionKind::Atom) && !found("h", CompletionKind::Atom));
+    assert!(
+        found("d", CompletionKind::Prefix),
+        "a prefix of a volume fits"
+    );
+
+    let candidates = complete_as("/L", 0, "g/L").unwrap();
+    assert!(candidates.iter().any(|c| c.text == "g"));
+    assert!(candidates.iter().all(|c| c.text != "mol"));
+    // An exponent divides the dimension left for the symbol
+    assert!(
+        complete_as("m/s2", 3, "m/s2")
+            .unwrap()
+            .iter()
+            .any(|c| c.text == "s")
+    );
+
+    // A lone symbol does not pin down a dimension yet
+    assert_eq!(complete_as("m", 1, "g/L").unwrap(), complete("m", 1));
+    assert!(complete_as("m", 1, "not_a_unit").is_err());
+}
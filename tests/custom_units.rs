@@ -0,0 +1,126 @@
+use octofhir_ucum::{CustomUnit, ErrorKind, UcumEngine, default_engine};
+
+#[test]
+fn custom_unit_converts_and_searches() {
+    let mut engine = UcumEngine::new();
+    engine
+        .register_unit(
+            CustomUnit::new("[CFU_loc]", "10*3.[CFU]")
+                .with_property("number")
+                .with_display_name("local colony forming units"),
+        )
+        .unwrap();
+
+    assert!(engine.validate("[CFU_loc]/mL").is_ok());
+    assert!(engine.is_comparable("[CFU_loc]", "[CFU]").unwrap());
+    assert!((engine.convert(2.0, "[CFU_loc]", "[CFU]").unwrap() - 2000.0).abs() < 1e-9);
+
+    let results = engine.search_units("local colony");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].code, "[CFU_loc]");
+
+    // Registration is local to the engine
+    assert!(default_engine().find_unit("[CFU_loc]").is_none());
+}
+
+#[test]
+fn custom_unit_takes_part_in_suggestions() {
+    let mut engine = UcumEngine::new();
+    // Build the suggestion engine before registering to check it gets rebuilt
+    engine.validate("xyz").unwrap_err();
+    engine
+        .register_unit(CustomUnit::new("tablet", "1").with_display_name("tablet"))
+        .unwrap();
+
+    let err = engine.validate("tablett").unwrap_err();
+    assert!(err.suggestions.iter().any(|s| s.contains("'tablet'")));
+}
+
+#[test]
+fn annotation_code_and_prefixes() {
+    let mut engine = UcumEngine::new();
+    engine
+        .register_unit(CustomUnit::new("{tbl}", "1").with_display_name("tablet"))
+        .unwrap();
+    engine
+        .register_unit(CustomUnit::new("{pack}", "10.{tbl}"))
+        .unwrap();
+    assert!((engine.convert(3.0, "{pack}", "{tbl}").unwrap() - 30.0).abs() < 1e-9);
+
+    engine.register_prefix("R", 1e27, "ronna").unwrap();
+    assert!((engine.convert(1.0, "Rg", "g").unwrap() - 1e27).abs() / 1e27 < 1e-9);
+}
+
+#[test]
+fn invalid_definitions_are_rejected() {
+    let mut engine = UcumEngine::new();
+
+    let duplicate = engine
+        .register_unit(CustomUnit::new("mg", "g"))
+        .unwrap_err();
+    assert!(matches!(
+        duplicate.kind,
+        ErrorKind::InvalidDefinition { .. }
+    ));
+
+    let unknown = engine
+        .register_unit(CustomUnit::new("[foo]", "bogus_unit"))
+        .unwrap_err();
+    assert!(matches!(unknown.kind, ErrorKind::InvalidDefinition { .. }));
+
+    let not_a_symbol = engine
+        .register_unit(CustomUnit::new("m/s", "m"))
+        .unwrap_err();
+    assert!(matches!(
+        not_a_symbol.kind,
+        ErrorKind::InvalidDefinition { .. }
+    ));
+
+    let offset = engine
+        .register_unit(CustomUnit::new("[degC_loc]", "Cel"))
+        .unwrap_err();
+    assert!(matches!(offset.kind, ErrorKind::InvalidDefinition { .. }));
+
+    assert!(engine.register_prefix("k", 1e3, "kilo").is_err());
+    assert!(engine.register_prefix("Q", -1.0, "negative").is_err());
+    assert!(engine.register_prefix("Q", 0.0, "zero").is_err());
+    assert!(engine.register_prefix("Q", f64::NAN, "nan").is_err());
+    assert!(engine.register_prefix("Q", 1e40, "huge").is_err());
+    assert!(engine.find_prefix("Q").is_none());
+}
+
+#[test]
+fn prefix_exponents_are_exact_powers_of_ten() {
+    let mut engine = UcumEngine::new();
+    assert_eq!(engine.find_prefix("m").unwrap().exponent, Some(-3));
+    assert_eq!(engine.find_prefix("Y").unwrap().exponent, Some(24));
+    assert_eq!(engine.find_prefix("Ki").unwrap().exponent, None);
+
+    engine.register_prefix("R", 1e27, "ronna").unwrap();
+    engine.register_prefix("q", 1e-27, "quecto").unwrap();
+    engine.register_prefix("H", 0.5, "half").unwrap();
+    assert_eq!(engine.find_prefix("R").unwrap().exponent, Some(27));
+    assert_eq!(engine.find_prefix("q").unwrap().exponent, Some(-27));
+    assert_eq!(engine.find_prefix("H").unwrap().exponent, None);
+}
+
+#[test]
+fn arbitrary_custom_units() {
+    let mut engine = UcumEngine::new();
+    let unit = engine
+        .register_unit(CustomUnit::new("[AU_loc]", "1").arbitrary())
+        .unwrap();
+    assert!(unit.special.is_arbitrary());
+    assert!(engine.validate("[AU_loc]/mL").is_ok());
+
+    // Only commensurable with itself, whatever the definition
+    assert!(!engine.is_comparable("[AU_loc]", "1").unwrap());
+    assert!(!engine.is_comparable("[AU_loc]", "[IU]").unwrap());
+    assert!(engine.is_comparable("[AU_loc]/mL", "[AU_loc]/L").unwrap());
+    assert!(engine.convert(1.0, "[AU_loc]", "%").is_err());
+
+    engine
+        .register_unit(CustomUnit::new("[mAU_loc]", "mg").arbitrary())
+        .unwrap();
+    assert!(!engine.is_comparable("[mAU_loc]", "g").unwrap());
+}
@@ -0,0 +1,93 @@
+use octofhir_ucum::{ErrorKind, Severity, parse_expression_spanned, parse_lenient};
+
+fn problems(input: &str) -> Vec<(Severity, &str, String)> {
+    let parsed = parse_lenient(input);
+    parsed
+        .diagnostics
+        .iter()
+        .map(|d| {
+            let range = d.span.start..d.span.end;
+            (d.severity, &input[range], d.error.message.clone())
+        })
+        .collect()
+}
+
+#[test]
+fn every_problem_is_reported() {
+    let parsed = parse_lenient("mg/dll.sec2/");
+    assert!(!parsed.is_valid());
+    let spans: Vec<_> = parsed.diagnostics.iter().map(|d| d.span.text()).collect();
+    assert_eq!(spans, ["dll", "sec", "/"]);
+    assert!(matches!(
+        parsed.diagnostics[0].error.kind,
+        ErrorKind::UnitNotFound { .. }
+    ));
+    assert!(!parsed.diagnostics[0].error.suggestions.is_empty());
+    assert_eq!(
+        parsed.diagnostics[0].error.span,
+        Some(parsed.diagnostics[0].span.clone())
+    );
+
+    // The terms that parsed still form an expression
+    let expr = parsed.expr.unwrap();
+    assert_eq!(expr.expr.to_string(), "mg/(dll.sec2)");
+    assert_eq!(expr.symbol_range("sec"), Some(7..10));
+
+    assert_eq!(
+        problems("m!.s^/(g"),
+        [
+            (
+                Severity::Error,
+                "!",
+                "Invalid UCUM expression: Unexpected '!'".to_string()
+            ),
+            (
+                Severity::Error,
+                "^",
+                "Invalid UCUM expression: Missing exponent after '^'".to_string()
+            ),
+            (
+                Severity::Error,
+                "(g",
+                "Invalid UCUM expression: Missing closing parenthesis".to_string()
+            ),
+        ]
+    );
+    assert_eq!(
+        problems("m..s")[0].2,
+        "Invalid UCUM expression: Missing term after '.'"
+    );
+    assert_eq!(problems(" m")[0].0, Severity::Warning);
+    assert!(parse_lenient(" m").is_valid());
+}
+
+#[test]
+fn valid_expressions_parse_as_strictly() {
+    for code in [
+        "kg.m/s2",
+        "/min",
+        "/m.s",
+        "4.[pi].10*-7.N/A2",
+        "mg{creat}/(24.h)",
+        "(m.s)2.kg",
+        "µg/mL",
+        "m[IU]/L",
+        "",
+    ] {
+        let parsed = parse_lenient(code);
+        assert!(
+            parsed.diagnostics.is_empty(),
+            "{code}: {:?}",
+            parsed.diagnostics
+        );
+        assert_eq!(parsed.expr, parse_expression_spanned(code).ok(), "{code}");
+    }
+
+    // Errors found only by evaluating the whole expression are reported too
+    let parsed = parse_lenient("Cel2/s");
+    assert!(!parsed.is_valid());
+    assert!(matches!(
+        parsed.diagnostics[0].error.kind,
+        ErrorKind::ConversionError { .. }
+    ));
+}
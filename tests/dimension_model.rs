@@ -0,0 +1,150 @@
+use octofhir_ucum::{
+    CustomUnit, Dimension, DimensionModel, EngineConfig, UcumEngine, UnitRegistry, default_engine,
+};
+
+fn ucum_engine() -> UcumEngine {
+    UcumEngine::new().with_dimension_model(DimensionModel::Ucum)
+}
+
+#[test]
+fn ucum_base_vector() {
+    let engine = ucum_engine();
+
+    assert!(!engine.is_comparable("rad", "%").unwrap());
+    assert!(engine.is_comparable("deg", "rad").unwrap());
+    assert!(engine.is_comparable("[p'diop]", "deg").unwrap());
+    assert!(engine.is_comparable("mol", "1").unwrap());
+    assert!(engine.is_comparable("mmol/L", "/L").unwrap());
+    assert!(engine.is_comparable("A", "C/s").unwrap());
+
+    // The SI vector treats angles as plain numbers and counts moles
+    assert!(default_engine().is_comparable("rad", "%").unwrap());
+    assert!(!default_engine().is_comparable("mol", "1").unwrap());
+}
+
+#[test]
+fn ucum_canonical_units() {
+    let engine = ucum_engine();
+
+    let newton = engine.get_canonical_units("N").unwrap();
+    assert_eq!(newton.unit, "g.m.s-2");
+    assert!((newton.factor - 1000.0).abs() < 1e-9);
+
+    assert_eq!(engine.get_canonical_units("A").unwrap().unit, "C.s-1");
+    assert_eq!(engine.get_canonical_units("sr").unwrap().unit, "rad2");
+    assert_eq!(engine.get_canonical_units("Cel").unwrap().unit, "K");
+    assert_eq!(engine.get_canonical_units("mmol/L").unwrap().unit, "m-3");
+    assert_eq!(
+        engine.get_canonical_units("N").unwrap().dimension,
+        Dimension([1, 1, -2, 0, 0, 0, 0])
+    );
+}
+
+#[test]
+fn canonical_units_are_spec_conformant() {
+    // Mass is written in gram and arbitrary units are kept whatever the model
+    for engine in [default_engine(), &ucum_engine()] {
+        let canonical = engine.get_canonical_units("[IU]/mL").unwrap();
+        assert_eq!(canonical.unit, "[IU].m-3");
+        assert!((canonical.factor - 1e6).abs() < 1e-6);
+
+        let canonical = engine.get_canonical_units("mg/dL").unwrap();
+        assert_eq!(canonical.unit, "g.m-3");
+        assert!((canonical.factor - 10.0).abs() < 1e-12);
+
+        assert_eq!(engine.get_canonical_units("kg").unwrap().factor, 1000.0);
+        assert_eq!(engine.get_canonical_units("%").unwrap().factor, 0.01);
+        assert_eq!(
+            engine.get_canonical_units("[CFU]/[IU]2{x}").unwrap().unit,
+            "[CFU].[IU]-2"
+        );
+        assert_eq!(engine.get_canonical_units("k[IU]/[IU]").unwrap().unit, "1");
+    }
+
+    // Otherwise the terms follow the model: UCUM counts charge, angles and
+    // plain numbers where SI counts current and moles
+    let ucum = ucum_engine();
+    assert_eq!(ucum.get_canonical_units("A").unwrap().unit, "C.s-1");
+    assert_eq!(ucum.get_canonical_units("deg").unwrap().unit, "rad");
+    assert_eq!(ucum.get_canonical_units("mol/L").unwrap().unit, "m-3");
+
+    let si = default_engine();
+    assert_eq!(si.get_canonical_units("A").unwrap().unit, "A");
+    assert_eq!(si.get_canonical_units("deg").unwrap().unit, "1");
+    let mol = si.get_canonical_units("mol").unwrap();
+    assert_eq!(mol.unit, "mol");
+    assert_eq!(mol.factor, 1.0);
+    assert_eq!(mol.dimension, Dimension([0, 0, 0, 0, 0, 1, 0]));
+    assert_eq!(si.get_canonical_units("mmol/L").unwrap().unit, "m-3.mol");
+    assert_eq!(si.get_canonical_units("kat").unwrap().unit, "mol.s-1");
+}
+
+#[test]
+fn values_convert_to_canonical_units() {
+    for engine in [default_engine(), &ucum_engine()] {
+        for unit in ["mol", "mmol/L", "kat", "eq", "[pH]", "Cel", "kg", "B[W]"] {
+            let canonical = engine.get_canonical_units(unit).unwrap();
+            let value = engine.convert(7.0, unit, &canonical.unit).unwrap();
+            if canonical.is_nonlinear {
+                assert!(canonical.factor.is_nan(), "{unit}");
+            } else {
+                let expected = 7.0 * canonical.factor + canonical.offset;
+                assert!((value - expected).abs() <= expected.abs() * 1e-12, "{unit}");
+            }
+        }
+    }
+
+    let ph = default_engine().get_canonical_units("[pH]").unwrap();
+    assert_eq!(ph.unit, "m-3.mol");
+    assert!(ph.is_nonlinear);
+    let concentration = default_engine().convert(7.0, "[pH]", &ph.unit).unwrap();
+    assert!((concentration - 1e-4).abs() < 1e-16);
+    assert!(
+        default_engine()
+            .get_canonical_units("B[W]")
+            .unwrap()
+            .is_nonlinear
+    );
+    assert!(
+        !default_engine()
+            .get_canonical_units("Cel")
+            .unwrap()
+            .is_nonlinear
+    );
+}
+
+#[test]
+fn model_follows_config_and_registry() {
+    let engine = UcumEngine::new().with_config(EngineConfig {
+        dimension_model: DimensionModel::Ucum,
+        ..Default::default()
+    });
+    assert_eq!(engine.registry().dimension_model(), DimensionModel::Ucum);
+    // Evaluated before switching back, so the cache must not leak across models
+    assert!(engine.is_comparable("mol", "1").unwrap());
+
+    let engine = engine
+        .with_registry(UnitRegistry::builtin())
+        .with_dimension_model(DimensionModel::Si);
+    assert_eq!(engine.registry().dimension_model(), DimensionModel::Si);
+    assert!(!engine.is_comparable("mol", "1").unwrap());
+
+    // A replacement registry keeps its own model and the config follows it
+    let engine = UcumEngine::new()
+        .with_registry(UnitRegistry::builtin().with_dimension_model(DimensionModel::Ucum));
+    assert_eq!(engine.registry().dimension_model(), DimensionModel::Ucum);
+    assert_eq!(engine.config().dimension_model, DimensionModel::Ucum);
+    assert!(engine.is_comparable("mol", "1").unwrap());
+}
+
+#[test]
+fn custom_units_get_both_dimensions() {
+    let mut engine = ucum_engine();
+    let unit = engine
+        .register_unit(CustomUnit::new("[gon_loc]", "deg.9/10"))
+        .unwrap();
+    assert_eq!(unit.dim, Dimension::zero());
+    assert_eq!(unit.ucum_dim, Dimension([0, 0, 0, 0, 0, 1, 0]));
+    assert!(engine.is_comparable("[gon_loc]", "rad").unwrap());
+    assert!(!engine.is_comparable("[gon_loc]", "%").unwrap());
+}
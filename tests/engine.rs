@@ -0,0 +1,178 @@
+use octofhir_ucum::precision::Number;
+use octofhir_ucum::{
+    AdvancedConversionContext, ConversionContext, DimensionModel, EngineConfig, ErrorKind,
+    UcumEngine, UnitRegistry, convert_with_context, default_engine, get_all_units,
+};
+
+fn registry_without(code: &str) -> UnitRegistry {
+    let builtin = UnitRegistry::builtin();
+    let units = builtin
+        .units()
+        .iter()
+        .filter(|u| u.code != code)
+        .cloned()
+        .collect();
+    UnitRegistry::new(builtin.prefixes().to_vec(), units)
+}
+
+#[test]
+fn engines_are_independent() {
+    let restricted = UcumEngine::new().with_registry(registry_without("bar"));
+
+    assert!(restricted.validate("bar").is_err());
+    assert!(restricted.find_unit("bar").is_none());
+    assert!(restricted.validate("kPa").is_ok());
+
+    // The default engine still knows the unit
+    assert!(default_engine().validate("bar").is_ok());
+    assert_eq!(default_engine().units().len(), get_all_units().len());
+}
+
+#[test]
+fn engine_operations() {
+    let engine = UcumEngine::new();
+
+    assert!((engine.convert(1.0, "km", "m").unwrap() - 1000.0).abs() < 1e-9);
+    assert!((engine.convert(0.0, "Cel", "K").unwrap() - 273.15).abs() < 1e-9);
+    assert!(engine.convert(1.0, "m", "s").is_err());
+
+    assert!(engine.is_comparable("mg/dL", "g/L").unwrap());
+    assert_eq!(engine.analyse("N").unwrap().factor, 1000.0);
+
+    let results = engine.search_units("meter");
+    assert!(results.iter().any(|u| u.code == "m"));
+}
+
+#[test]
+fn cache_is_per_engine() {
+    let cached = UcumEngine::new();
+    cached.validate("kg.m/s2").unwrap();
+    cached.validate("kg.m/s2").unwrap();
+    let stats = cached.cache_stats();
+    assert!(stats.expression_hits >= 1);
+    assert!(stats.expression_misses >= 1);
+
+    cached.clear_cache();
+    assert_eq!(cached.cache_sizes(), (0, 0, 0));
+
+    let uncached = UcumEngine::new().with_config(EngineConfig {
+        enable_cache: false,
+        ..Default::default()
+    });
+    uncached.validate("kg.m/s2").unwrap();
+    uncached.validate("kg.m/s2").unwrap();
+    assert_eq!(uncached.cache_stats().expression_misses, 0);
+}
+
+#[test]
+fn suggestions_can_be_disabled() {
+    let engine = UcumEngine::new().with_config(EngineConfig {
+        enable_suggestions: false,
+        ..Default::default()
+    });
+    let err = engine.validate("metr").unwrap_err();
+    assert!(err.suggestions.is_empty());
+
+    let err = default_engine().validate("metr").unwrap_err();
+    assert!(!err.suggestions.is_empty());
+}
+
+#[test]
+fn substance_conversions() {
+    let glucose = ConversionContext::new().with_molar_mass(Number::new(18016, 2));
+    let ucum_model = UcumEngine::new().with_dimension_model(DimensionModel::Ucum);
+    for engine in [default_engine(), &ucum_model] {
+        let result = engine
+            .convert_substance(Number::new(9008, 2), "mg/dL", "mmol/L", &glucose)
+            .unwrap();
+        assert_eq!(result.value, Number::new(5, 0));
+        assert_eq!(result.molar_mass, Some(Number::new(18016, 2)));
+        assert_eq!(result.charge, None);
+
+        let result = engine
+            .convert_substance(Number::new(5, 0), "mmol/L", "mg/dL", &glucose)
+            .unwrap();
+        assert_eq!(result.value, Number::new(9008, 2));
+    }
+
+    let calcium = ConversionContext::new().with_charge(Number::new(2, 0));
+    let result = default_engine()
+        .convert_substance(Number::new(25, 1), "mmol/L", "meq/L", &calcium)
+        .unwrap();
+    assert_eq!(result.value, Number::new(5, 0));
+    assert!(result.molar_mass.is_none());
+
+    let calcium = calcium.with_molar_mass(Number::new(40078, 3));
+    let result = default_engine()
+        .convert_substance(Number::new(5, 0), "meq/L", "mg/dL", &calcium)
+        .unwrap();
+    assert_eq!(result.value, Number::new(100195, 4));
+
+    // Plain conversions are not substance specific
+    let result = default_engine()
+        .convert_substance(Number::ONE, "mg", "g", &glucose)
+        .unwrap();
+    assert_eq!(result.value, Number::new(1, 3));
+    assert!(!result.is_substance_specific());
+}
+
+#[test]
+fn substance_conversions_need_their_factors() {
+    let engine = default_engine();
+    let none = ConversionContext::new();
+    let missing = |from, to| {
+        let error = engine
+            .convert_substance(Number::ONE, from, to, &none)
+            .unwrap_err();
+        matches!(error.kind, ErrorKind::ConversionError { .. })
+    };
+    assert!(missing("mg/dL", "mmol/L"));
+    assert!(missing("mmol/L", "meq/L"));
+
+    let error = engine
+        .convert_substance(Number::ONE, "g", "m", &none)
+        .unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::DimensionMismatch { .. }));
+}
+
+#[test]
+fn substance_conversions_that_overflow() {
+    let engine = default_engine();
+    let overflows = |from, to, context: &ConversionContext| {
+        let error = engine
+            .convert_substance(Number::MAX, from, to, context)
+            .unwrap_err();
+        matches!(error.kind, ErrorKind::PrecisionOverflow { .. })
+    };
+    let calcium = ConversionContext::new()
+        .with_molar_mass(Number::new(40078, 3))
+        .with_charge(Number::TWO);
+    assert!(overflows("mmol/L", "meq/L", &calcium));
+    assert!(overflows("g", "umol", &calcium));
+    assert!(overflows("mol", "ug", &calcium));
+
+    let large_charge = ConversionContext::new().with_charge(Number::from(10_i64.pow(15)));
+    let error = engine
+        .convert_substance(Number::ONE, "mol2", "eq2", &large_charge)
+        .unwrap_err();
+    assert!(matches!(error.kind, ErrorKind::PrecisionOverflow { .. }));
+}
+
+#[test]
+fn conversions_beyond_decimal_range() {
+    let engine = default_engine();
+    let close = |actual: f64, expected: f64| ((actual - expected) / expected).abs() < 1e-12;
+
+    assert!(close(engine.convert(1e20, "Gm", "pm").unwrap(), 1e41));
+    assert!(close(engine.convert(1e-30, "m", "km").unwrap(), 1e-33));
+    assert!(close(engine.convert(1e300, "m", "km").unwrap(), 1e297));
+    assert_eq!(engine.convert(-273.15, "Cel", "K").unwrap(), 0.0);
+
+    let result =
+        convert_with_context(1e300, "m", "km", &AdvancedConversionContext::default()).unwrap();
+    assert!(close(result.value, 1e297));
+    assert!(close(result.factor, 1e-3));
+
+    assert!(engine.convert(f64::NAN, "m", "km").is_err());
+    assert!(engine.convert(f64::INFINITY, "m", "km").is_err());
+}
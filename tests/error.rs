@@ -1,4 +1,7 @@
-use octofhir_ucum::{ErrorKind, OwnedUnitExpr, parse_expression};
+use octofhir_ucum::{
+    ErrorKind, OwnedUnitExpr, evaluate_spanned, parse_expression, parse_expression_spanned,
+    validate,
+};
 
 #[test]
 fn multiple_slash_allowed() {
@@ -16,3 +19,19 @@ fn invalid_percent_error() {
         ErrorKind::InvalidPercentPlacement { .. }
     ));
 }
+
+#[test]
+fn evaluation_errors_point_at_the_symbol() {
+    let spanned = parse_expression_spanned("kg.mgs/s").unwrap();
+    let err = evaluate_spanned(&spanned).unwrap_err();
+    let span = err.span.unwrap();
+    assert_eq!((span.start, span.end), (3, 6));
+    assert_eq!(
+        span.display_error(&err.message),
+        "Unknown unit: 'mgs'\nkg.mgs/s\n   ^~~"
+    );
+
+    // Validation keeps the span along with its suggestions
+    let err = validate("mg/dll").unwrap_err();
+    assert_eq!(err.span.unwrap().text(), "dll");
+}
@@ -0,0 +1,117 @@
+use octofhir_ucum::{Dimension, ErrorKind, UcumEngine, UnitRegistry, default_engine};
+
+const ESSENCE: &str = include_str!("../ucum-essence.xml");
+
+#[test]
+fn runtime_load_matches_builtin() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/ucum-essence.xml");
+    let loaded = UnitRegistry::from_essence_file(path).unwrap();
+    let builtin = UnitRegistry::builtin();
+
+    assert_eq!(loaded.prefixes(), builtin.prefixes());
+    assert_eq!(loaded.units(), builtin.units());
+}
+
+#[test]
+fn revised_essence_side_by_side() {
+    let revised = ESSENCE.replacen(
+        "<unit ",
+        r#"<unit Code="[stride_loc]" CODE="[STRIDE_LOC]" isMetric="no" class="misc">
+      <name>stride</name>
+      <printSymbol>stride</printSymbol>
+      <property>length</property>
+      <value Unit="cm" UNIT="CM" value="75">75</value>
+   </unit>
+   <unit "#,
+        1,
+    );
+    let engine = UcumEngine::new()
+        .with_registry(UnitRegistry::from_essence_bytes(revised.as_bytes()).unwrap());
+
+    assert!((engine.convert(1.0, "[stride_loc]", "m").unwrap() - 0.75).abs() < 1e-9);
+    assert!(default_engine().find_unit("[stride_loc]").is_none());
+}
+
+#[test]
+fn malformed_definitions_are_reported() {
+    let bad_value = ESSENCE.replacen(r#"value="1e24""#, r#"value="lots""#, 1);
+    let err = UnitRegistry::from_essence_xml(&bad_value).unwrap_err();
+    match err.kind {
+        ErrorKind::InvalidDefinition { code, reason } => {
+            assert_eq!(code, "Y");
+            assert!(reason.contains("lots"));
+        }
+        other => panic!("unexpected error kind: {other:?}"),
+    }
+
+    let duplicate = ESSENCE.replacen(r#"Code="Z" "#, r#"Code="Y" "#, 1);
+    let err = UnitRegistry::from_essence_xml(&duplicate).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::InvalidDefinition { ref code, .. } if code == "Y"));
+
+    let missing_code = ESSENCE.replacen(r#"<unit Code="#, "<unit Cod=", 1);
+    let err = UnitRegistry::from_essence_xml(&missing_code).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::InvalidEssence { .. }));
+}
+
+#[test]
+fn special_units_follow_their_definition() {
+    // Scales and base quantities come from the `<function>` and the
+    // property, not from the unit's code
+    let revised = ESSENCE.replacen(
+        "<unit ",
+        r#"<unit Code="[degF_loc]" CODE="[DEGF_LOC]" isMetric="no" isSpecial="yes" class="misc">
+      <name>local Fahrenheit</name>
+      <property>temperature</property>
+      <value Unit="degf(5 K/9)" UNIT="DEGF(5 K/9)">
+         <function name="degF" value="5" Unit="K/9"/>
+      </value>
+   </unit>
+   <unit "#,
+        1,
+    );
+    let registry = UnitRegistry::from_essence_xml(&revised).unwrap();
+    let local = registry.find_unit("[degF_loc]").unwrap();
+    let builtin = registry.find_unit("[degF]").unwrap();
+    assert_eq!(local.special, builtin.special);
+    assert_eq!(local.exact_factor, builtin.exact_factor);
+    assert_eq!(local.offset, builtin.offset);
+    assert_eq!(local.exact_offset, builtin.exact_offset);
+    assert_eq!(local.dim, builtin.dim);
+
+    let mol = registry.find_unit("mol").unwrap();
+    assert_eq!(mol.dim, Dimension([0, 0, 0, 0, 0, 1, 0]));
+    assert!(mol.ucum_dim.is_dimensionless());
+    let engine = UcumEngine::new().with_registry(registry);
+    assert!(!engine.is_comparable("mol", "1").unwrap());
+}
+
+#[test]
+fn out_of_range_definitions_are_reported() {
+    for definition in ["m200", "10*200", "(m127).m", "10*99999999999"] {
+        let revised = ESSENCE.replacen(
+            r#"<value Unit="10*23" UNIT="10*23" value="6.02214076">"#,
+            &format!(r#"<value Unit="{definition}" UNIT="X" value="1">"#),
+            1,
+        );
+        let err = UnitRegistry::from_essence_xml(&revised).unwrap_err();
+        assert!(
+            matches!(err.kind, ErrorKind::InvalidDefinition { ref code, .. } if code == "mol"),
+            "{definition}: {err:?}"
+        );
+    }
+}
+
+#[test]
+fn unreadable_documents_are_reported() {
+    let err = UnitRegistry::from_essence_xml("<root><unit></root>").unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::InvalidEssence { .. }));
+
+    let err = UnitRegistry::from_essence_xml("<root/>").unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::InvalidEssence { .. }));
+
+    let err = UnitRegistry::from_essence_bytes(&[0xff, 0xfe]).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::InvalidEssence { .. }));
+
+    let err = UnitRegistry::from_essence_file("does/not/exist.xml").unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::InvalidEssence { .. }));
+}
@@ -1,4 +1,4 @@
-use octofhir_ucum::precision::{NumericOps, from_f64};
+use octofhir_ucum::precision::{Number, NumericOps, from_f64};
 use octofhir_ucum::{Dimension, EvalResult, evaluate_owned, parse_expression};
 
 fn eval(expr: &str) -> EvalResult {
@@ -30,6 +30,26 @@ fn celsius_offset() {
     assert!((cel.offset.sub(from_f64(273.15))).abs() < from_f64(1e-2));
 }
 
+#[test]
+fn offsets_and_numeric_factors_are_exact() {
+    assert_eq!(eval("Cel").offset, Number::new(27315, 2));
+    assert_eq!(eval("[degRe]").offset, Number::new(27315, 2));
+    let degf = eval("[degF]");
+    assert_eq!(degf.offset, Number::new(45967, 2) * degf.factor);
+    assert_eq!(eval("10*-3.g").factor, Number::new(1, 3));
+    assert_eq!(eval("10*-23").factor, Number::new(1, 23));
+    assert_eq!(eval("0.1.g").factor, Number::new(1, 1));
+
+    let engine = octofhir_ucum::default_engine();
+    let convert = |value: Number, from: &str, to: &str| {
+        let (from, to) = (engine.parse(from).unwrap(), engine.parse(to).unwrap());
+        engine.convert_expr(value, &from, &to).unwrap()
+    };
+    assert_eq!(convert(Number::ONE, "10*-3.g", "mg"), Number::ONE);
+    assert_eq!(convert(Number::ZERO, "Cel", "K"), Number::new(27315, 2));
+    assert_eq!(convert(Number::from(32), "[degF]", "Cel"), Number::ZERO);
+}
+
 #[test]
 fn product_and_power() {
     let m2 = eval("m2"); // implicit exponent 2
@@ -37,3 +57,30 @@ fn product_and_power() {
     assert_eq!(m2.dim, m_pow.dim);
     assert!((m2.factor.sub(m_pow.factor)).abs() < from_f64(1e-12));
 }
+
+#[test]
+fn annotations_evaluate_as_unity() {
+    let cells = eval("{cells}/uL");
+    let plain = eval("/uL");
+    assert_eq!(cells, plain);
+    assert_eq!(eval("mg{creat}"), eval("mg"));
+}
+
+#[test]
+fn numeric_factor_keeps_prefix() {
+    let cm15 = eval("15.cm");
+    assert_eq!(cm15.dim, eval("m").dim);
+    assert!((cm15.factor.sub(from_f64(0.15))).abs() < from_f64(1e-12));
+    assert!((eval("10*3.mL").factor.sub(eval("L").factor)).abs() < from_f64(1e-12));
+}
+
+#[test]
+fn customary_units_are_exact() {
+    let exact = |s: &str| s.parse::<Number>().unwrap();
+    let inch = eval("[in_i]").factor.div(eval("cm").factor);
+    assert_eq!(inch, exact("2.54"));
+    let pound = eval("[lb_av]").factor.div(eval("g").factor);
+    assert_eq!(pound, exact("453.59237"));
+    assert_eq!(eval("[ft_i]").factor, exact("0.3048"));
+    assert_eq!(eval("[gal_us]").factor, exact("0.003785411784"));
+}
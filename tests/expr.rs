@@ -1,4 +1,6 @@
-use octofhir_ucum::{OwnedUnitExpr, OwnedUnitFactor, parse_expression};
+use octofhir_ucum::{
+    AnnotationMode, OwnedUnitExpr, OwnedUnitFactor, parse_expression, parse_expression_spanned,
+};
 
 fn sym<S: Into<String>>(s: S) -> OwnedUnitExpr {
     OwnedUnitExpr::Symbol(s.into())
@@ -74,9 +76,57 @@ fn parentheses_and_power() {
 }
 
 #[test]
-fn annotation_ignored() {
+fn annotation_preserved() {
     let expr = parse_expression("m{steel}").unwrap();
-    assert_eq!(expr, sym("m"));
+    assert_eq!(
+        expr,
+        OwnedUnitExpr::Annotated(Box::new(sym("m")), "steel".to_string())
+    );
+    assert_eq!(expr.to_string(), "m{steel}");
+    assert_eq!(expr.strip_annotations(), sym("m"));
+}
+
+#[test]
+fn standalone_annotation() {
+    let expr = parse_expression("{cells}/uL").unwrap();
+    let expected = OwnedUnitExpr::Quotient(
+        Box::new(OwnedUnitExpr::Annotation("cells".to_string())),
+        Box::new(sym("uL")),
+    );
+    assert_eq!(expr, expected);
+    assert_eq!(expr.to_string(), "{cells}/uL");
+
+    let plain = parse_expression("/uL").unwrap();
+    assert_ne!(expr, plain);
+    assert!(expr.eq_with(&plain, AnnotationMode::Ignore));
+    assert!(!expr.eq_with(&plain, AnnotationMode::Consider));
+}
+
+#[test]
+fn annotation_factor_in_product() {
+    let plain = parse_expression("mg").unwrap();
+    for annotated in ["{a}.mg", "mg.{a}"] {
+        let expr = parse_expression(annotated).unwrap();
+        assert_eq!(expr.strip_annotations(), plain, "{annotated}");
+        assert!(expr.eq_with(&plain, AnnotationMode::Ignore), "{annotated}");
+    }
+
+    let expr = parse_expression("{a}.m2").unwrap();
+    assert_eq!(expr.strip_annotations(), parse_expression("m2").unwrap());
+    let expr = parse_expression("{a}.m.s").unwrap();
+    assert_eq!(expr.strip_annotations(), parse_expression("m.s").unwrap());
+}
+
+#[test]
+fn annotation_applies_to_exponent() {
+    let expr = parse_expression("m2{x}").unwrap();
+    assert_eq!(
+        expr,
+        OwnedUnitExpr::Annotated(
+            Box::new(OwnedUnitExpr::Power(Box::new(sym("m")), 2)),
+            "x".to_string()
+        )
+    );
 }
 
 #[test]
@@ -88,7 +138,10 @@ fn arbitrary_unit_brackets() {
 #[test]
 fn semicolon_annotation() {
     let expr = parse_expression("m{len;NIST}").unwrap();
-    assert_eq!(expr, sym("m"));
+    assert_eq!(
+        expr,
+        OwnedUnitExpr::Annotated(Box::new(sym("m")), "len;NIST".to_string())
+    );
 }
 
 #[test]
@@ -195,3 +248,44 @@ fn complex_expression_test() {
     let expected = OwnedUnitExpr::Quotient(Box::new(numerator), Box::new(denominator));
     assert_eq!(expr, expected);
 }
+
+#[test]
+fn spans_cover_every_node() {
+    let spanned = parse_expression_spanned("kg.m/s^-2").unwrap();
+    assert_eq!(spanned.expr, parse_expression("kg.m/s^-2").unwrap());
+    let [num, den] = &spanned.spans.children[..] else {
+        panic!("expected a quotient: {:?}", spanned.spans);
+    };
+    assert_eq!(spanned.spans.range, 0..9);
+    // Product factors wrap the spans of their expression
+    assert_eq!(num.range, 0..4);
+    assert_eq!(num.children[0].children[0].range, 0..2);
+    assert_eq!(num.children[0].children[0].prefix, Some(0..1));
+    assert_eq!(num.children[1].children[0].range, 3..4);
+    assert_eq!(den.range, 5..9);
+    assert_eq!(den.exponent, Some(7..9));
+    assert_eq!(den.children[0].range, 5..6);
+
+    let spanned = parse_expression_spanned(" µg2{dry}").unwrap();
+    let annotated = &spanned.spans;
+    assert_eq!(annotated.range, 1..10);
+    assert_eq!(annotated.annotation, Some(5..10));
+    let power = &annotated.children[0];
+    assert_eq!(power.exponent, Some(4..5));
+    assert_eq!(power.children[0].prefix, Some(1..3));
+    assert_eq!(spanned.span(power.children[0].range.clone()).text(), "µg");
+
+    let spanned = parse_expression_spanned("/min").unwrap();
+    assert_eq!(spanned.spans.children[0].range, 0..0);
+    assert_eq!(spanned.spans.children[1].range, 1..4);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn annotation_serde_roundtrip() {
+    let expr = parse_expression("10*3{cells}/uL").unwrap();
+    let json = serde_json::to_string(&expr).unwrap();
+    let back: OwnedUnitExpr = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, expr);
+    assert!(back.has_annotations());
+}
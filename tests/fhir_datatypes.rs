@@ -0,0 +1,185 @@
+#![cfg(feature = "fhir")]
+
+use octofhir_ucum::fhir::{
+    CURRENCY_SYSTEM, FhirError, FhirQuantity, FhirRange, FhirRatio, QuantityKind,
+};
+use rust_decimal::Decimal;
+
+fn ucum(value: f64, code: &str) -> FhirQuantity {
+    FhirQuantity::with_ucum_code(value, code)
+}
+
+fn is_invalid(result: Result<(), FhirError>, datatype: &str) -> bool {
+    matches!(result, Err(FhirError::InvalidDatatype(name, _)) if name == datatype)
+}
+
+#[test]
+fn quantity_profiles() {
+    let mut less_than = ucum(5.0, "mg");
+    less_than.comparator = Some("<".to_string());
+    assert!(QuantityKind::Quantity.validate(&less_than).is_ok());
+    assert!(is_invalid(
+        QuantityKind::SimpleQuantity.validate(&less_than),
+        "SimpleQuantity"
+    ));
+
+    let no_system = FhirQuantity {
+        system: None,
+        ..ucum(5.0, "mg")
+    };
+    assert!(is_invalid(
+        QuantityKind::Quantity.validate(&no_system),
+        "Quantity"
+    ));
+
+    assert!(QuantityKind::Age.validate(&ucum(3.0, "mo")).is_ok());
+    assert!(is_invalid(
+        QuantityKind::Age.validate(&ucum(0.0, "a")),
+        "Age"
+    ));
+    assert!(is_invalid(
+        QuantityKind::Age.validate(&ucum(3.0, "m")),
+        "Age"
+    ));
+    assert!(QuantityKind::Duration.validate(&ucum(90.0, "min")).is_ok());
+    assert!(is_invalid(
+        QuantityKind::Duration.validate(&ucum(90.0, "mg")),
+        "Duration"
+    ));
+    assert!(QuantityKind::Distance.validate(&ucum(1.5, "km")).is_ok());
+    assert!(
+        QuantityKind::Distance
+            .validate(&ucum(3.0, "[ft_i]"))
+            .is_ok()
+    );
+    assert!(is_invalid(
+        QuantityKind::Distance.validate(&ucum(1.5, "s")),
+        "Distance"
+    ));
+
+    // Units of time and length must be UCUM-coded
+    let local = FhirQuantity {
+        system: Some("http://example.org".to_string()),
+        ..ucum(3.0, "a")
+    };
+    assert!(matches!(
+        QuantityKind::Age.validate(&local),
+        Err(FhirError::InvalidSystem(_))
+    ));
+
+    // Calendar keywords stand for their UCUM units of time
+    let keyword = |unit: &str| FhirQuantity {
+        value: Decimal::from(3),
+        unit: Some(unit.to_string()),
+        ..FhirQuantity::default()
+    };
+    assert!(QuantityKind::Duration.validate(&keyword("weeks")).is_ok());
+    assert!(QuantityKind::Age.validate(&keyword("year")).is_ok());
+    assert!(is_invalid(
+        QuantityKind::Distance.validate(&keyword("days")),
+        "Distance"
+    ));
+}
+
+#[test]
+fn counts_and_money() {
+    assert!(QuantityKind::Count.validate(&ucum(3.0, "1")).is_ok());
+    assert!(is_invalid(
+        QuantityKind::Count.validate(&ucum(3.0, "{tablets}")),
+        "Count"
+    ));
+    let written_with_decimal = FhirQuantity::with_ucum_decimal(Decimal::new(30, 1), "1");
+    assert!(is_invalid(
+        QuantityKind::Count.validate(&written_with_decimal),
+        "Count"
+    ));
+
+    let money = |code: &str| FhirQuantity {
+        system: Some(CURRENCY_SYSTEM.to_string()),
+        ..ucum(12.5, code)
+    };
+    assert!(QuantityKind::MoneyQuantity.validate(&money("EUR")).is_ok());
+    assert!(is_invalid(
+        QuantityKind::MoneyQuantity.validate(&money("euro")),
+        "MoneyQuantity"
+    ));
+    assert!(is_invalid(
+        QuantityKind::MoneyQuantity.validate(&ucum(12.5, "EUR")),
+        "MoneyQuantity"
+    ));
+    let without_system = FhirQuantity {
+        system: None,
+        ..ucum(12.5, "EUR")
+    };
+    assert!(
+        QuantityKind::MoneyQuantity
+            .validate(&without_system)
+            .is_ok()
+    );
+    let without_code = FhirQuantity {
+        code: None,
+        ..money("EUR")
+    };
+    assert!(is_invalid(
+        QuantityKind::MoneyQuantity.validate(&without_code),
+        "MoneyQuantity"
+    ));
+}
+
+#[test]
+fn ranges() {
+    let range = |low: Option<FhirQuantity>, high: Option<FhirQuantity>| FhirRange { low, high };
+
+    assert!(
+        range(Some(ucum(1.0, "g")), Some(ucum(1500.0, "mg")))
+            .validate()
+            .is_ok()
+    );
+    assert!(range(Some(ucum(1.0, "g")), None).validate().is_ok());
+    assert!(range(None, None).validate().is_ok());
+    assert!(is_invalid(
+        range(Some(ucum(2.0, "g")), Some(ucum(1500.0, "mg"))).validate(),
+        "Range"
+    ));
+    assert!(is_invalid(
+        range(Some(ucum(1.0, "g")), Some(ucum(2.0, "m"))).validate(),
+        "Range"
+    ));
+
+    let mut open_high = ucum(5.0, "g");
+    open_high.comparator = Some("<".to_string());
+    assert!(is_invalid(
+        range(Some(ucum(1.0, "g")), Some(open_high)).validate(),
+        "SimpleQuantity"
+    ));
+}
+
+#[test]
+fn ratios() {
+    let ratio = FhirRatio {
+        numerator: Some(ucum(1.0, "mg")),
+        denominator: Some(ucum(1.0, "mL")),
+    };
+    assert!(ratio.validate().is_ok());
+
+    let ratio = FhirRatio {
+        denominator: None,
+        ..ratio
+    };
+    assert!(is_invalid(ratio.validate(), "Ratio"));
+    let ratio = FhirRatio {
+        numerator: None,
+        denominator: Some(ucum(1.0, "mL")),
+    };
+    assert!(is_invalid(ratio.validate(), "Ratio"));
+    // Both parts may be absent, e.g. when an extension carries the ratio
+    assert!(FhirRatio::default().validate().is_ok());
+    assert!(matches!(
+        FhirRatio {
+            numerator: Some(ucum(1.0, "notaunit")),
+            denominator: Some(ucum(1.0, "mL")),
+        }
+        .validate(),
+        Err(FhirError::UcumError(_))
+    ));
+}
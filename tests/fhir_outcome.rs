@@ -0,0 +1,92 @@
+#![cfg(feature = "fhir")]
+
+use octofhir_ucum::fhir::{operation_outcome, validate_resource};
+use octofhir_ucum::{Dimension, Span, UcumError, validate};
+use serde_json::json;
+
+#[test]
+fn issue_codes_follow_error_kinds() {
+    let cases = [
+        (UcumError::parse_error("unit", "/"), "invalid"),
+        (UcumError::multiple_slash(), "invalid"),
+        (UcumError::unit_not_found("mgg"), "code-invalid"),
+        (
+            UcumError::dimension_mismatch(Dimension::zero(), Dimension::zero(), "convert"),
+            "business-rule",
+        ),
+        (UcumError::conversion_error("g", "m", "no"), "processing"),
+        (UcumError::invalid_property("colour"), "not-supported"),
+        (UcumError::invalid_essence("truncated"), "exception"),
+    ];
+    for (error, code) in cases {
+        let outcome = error.to_operation_outcome();
+        assert_eq!(outcome["resourceType"], "OperationOutcome");
+        assert_eq!(outcome["issue"][0]["code"], code, "{}", error.message);
+        assert_eq!(outcome["issue"][0]["details"]["text"], error.message);
+    }
+
+    let essence = UcumError::invalid_essence("truncated").to_outcome_issue();
+    assert_eq!(essence["severity"], "fatal");
+    assert_eq!(
+        UcumError::multiple_slash().to_outcome_issue()["severity"],
+        "error"
+    );
+}
+
+#[test]
+fn suggestions_and_location() {
+    let issue = validate("[IU").unwrap_err().to_outcome_issue();
+    assert!(
+        issue["diagnostics"]
+            .as_str()
+            .unwrap()
+            .contains("Did you mean '[IU]'?")
+    );
+    // Validation locates the unknown unit
+    assert_eq!(issue["extension"][1]["valueInteger"], 1);
+
+    let issue = UcumError::unit_not_found("dll")
+        .with_span(Span::new(3, 6, "mg/dll".to_string()))
+        .to_outcome_issue();
+    assert!(issue.get("diagnostics").is_none());
+    assert_eq!(
+        issue["extension"],
+        json!([
+            {"url": "http://hl7.org/fhir/StructureDefinition/operationoutcome-issue-line", "valueInteger": 1},
+            {"url": "http://hl7.org/fhir/StructureDefinition/operationoutcome-issue-col", "valueInteger": 4},
+        ])
+    );
+
+    // Columns count characters
+    let issue = UcumError::unit_not_found("x")
+        .with_span(Span::new(7, 8, "µg{é}x".to_string()))
+        .to_outcome_issue();
+    assert_eq!(issue["extension"][1]["valueInteger"], 6);
+
+    // Spans that do not fit their source are left out
+    for span in [
+        Span::new(9, 12, "mg".to_string()),
+        Span::new(1, 2, "µg".to_string()),
+    ] {
+        let issue = UcumError::unit_not_found("x")
+            .with_span(span)
+            .to_outcome_issue();
+        assert!(issue.get("extension").is_none());
+    }
+}
+
+#[test]
+fn resource_issues_carry_their_path() {
+    let observation = json!({
+        "resourceType": "Observation",
+        "valueQuantity": {"system": "http://unitsofmeasure.org", "code": "mgg"}
+    });
+    let outcome = operation_outcome(&validate_resource(&observation));
+    assert_eq!(
+        outcome["issue"][0]["expression"],
+        json!(["Observation.valueQuantity"])
+    );
+
+    let outcome = operation_outcome(&[]);
+    assert_eq!(outcome["issue"][0]["severity"], "information");
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "fhir")]
+
+use octofhir_ucum::fhir::{FhirError, validate_ndjson, validate_resource};
+use serde_json::json;
+
+#[test]
+fn bundle_quantities_are_found() {
+    let bundle = json!({
+        "resourceType": "Bundle",
+        "entry": [
+            {"resource": {
+                "resourceType": "Observation",
+                "valueQuantity": {"value": 5.4, "system": "http://unitsofmeasure.org", "code": "mg/dL"}
+            }},
+            {"resource": {
+                "resourceType": "Observation",
+                "component": [
+                    {"valueQuantity": {"value": 1, "system": "http://unitsofmeasure.org", "code": "mmol/L"}},
+                    {"valueQuantity": {"value": 2, "system": "http://unitsofmeasure.org", "code": "kilogram"}}
+                ],
+                "referenceRange": [{
+                    "low": {"value": 1, "system": "http://unitsofmeasure.org", "code": "[IU"},
+                    "high": {"value": 2, "system": "http://example.org", "code": "kilogram"}
+                }]
+            }}
+        ]
+    });
+
+    let issues = validate_resource(&bundle);
+    let paths: Vec<&str> = issues.iter().map(|issue| issue.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        [
+            "Bundle.entry[1].resource.component[1].valueQuantity",
+            "Bundle.entry[1].resource.referenceRange[0].low",
+        ]
+    );
+    assert_eq!(issues[0].code, "kilogram");
+    assert!(
+        issues[1]
+            .error
+            .suggestions
+            .iter()
+            .any(|suggestion| suggestion.contains("[IU]"))
+    );
+}
+
+#[test]
+fn elements_without_a_code_are_skipped() {
+    let observation = json!({
+        "resourceType": "Observation",
+        "valueQuantity": {"value": 5.4, "system": "http://unitsofmeasure.org"}
+    });
+    assert!(validate_resource(&observation).is_empty());
+    assert!(validate_resource(&json!({"value": 1})).is_empty());
+}
+
+#[test]
+fn ndjson_lines_are_numbered() {
+    let ndjson = concat!(
+        r#"{"resourceType":"Observation","valueQuantity":{"system":"http://unitsofmeasure.org","code":"mg"}}"#,
+        "\n\n",
+        r#"{"resourceType":"Observation","valueQuantity":{"system":"http://unitsofmeasure.org","code":"mgg"}}"#,
+        "\n",
+    );
+    let issues = validate_ndjson(ndjson).unwrap();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].0, 3);
+    assert_eq!(issues[0].1.path, "Observation.valueQuantity");
+
+    assert!(matches!(
+        validate_ndjson("{}\n{not json"),
+        Err(FhirError::InvalidJson(2, _))
+    ));
+}
@@ -0,0 +1,195 @@
+#![cfg(feature = "fhir")]
+
+use octofhir_ucum::fhir::{
+    FhirError, FhirQuantity, QuantityIndex, QuantityRange, QuantitySearch, SearchPrefix,
+};
+use octofhir_ucum::{CustomUnit, ErrorKind, UcumEngine};
+use rust_decimal::Decimal;
+
+fn matches(token: &str, value: f64, code: &str) -> bool {
+    QuantitySearch::parse(token)
+        .unwrap()
+        .matches(&FhirQuantity::with_ucum_code(value, code))
+        .unwrap()
+}
+
+#[test]
+fn index_entries_are_canonical() {
+    let index = QuantityIndex::new(&FhirQuantity::with_ucum_code(5.4, "mg")).unwrap();
+    assert_eq!(index.code, "g");
+    assert_eq!(index.canonical.low, Decimal::new(535, 5));
+    assert_eq!(index.canonical.value, Decimal::new(54, 4));
+    assert_eq!(index.canonical.high, Decimal::new(545, 5));
+    assert_eq!(index.stated.value, Decimal::new(54, 1));
+
+    // Arbitrary units stay part of the code
+    let index = QuantityIndex::new(&FhirQuantity::with_ucum_code(2.0, "[IU]/mL")).unwrap();
+    assert_eq!(index.code, "[IU].m-3");
+
+    let other = FhirQuantity {
+        system: Some("http://example.org".to_string()),
+        ..FhirQuantity::with_ucum_code(1.0, "mg")
+    };
+    assert!(matches!(
+        QuantityIndex::new(&other),
+        Err(FhirError::InvalidSystem(_))
+    ));
+
+    // A value with all 28 decimals has no representable precision range
+    assert_eq!(QuantityRange::from_precision(Decimal::new(1, 28)), None);
+    let finest = FhirQuantity {
+        value: Decimal::new(1, 28),
+        ..FhirQuantity::with_ucum_code(0.0, "g")
+    };
+    let error = QuantityIndex::new(&finest).unwrap_err();
+    assert!(matches!(
+        error,
+        FhirError::UcumError(ref e) if matches!(e.kind, ErrorKind::PrecisionOverflow { .. })
+    ));
+}
+
+#[test]
+fn mole_units_are_indexed() {
+    let index = QuantityIndex::new(&FhirQuantity::with_ucum_code(5.4, "mmol/L")).unwrap();
+    assert_eq!(index.code, "m-3.mol");
+    assert_eq!(index.canonical.value, Decimal::new(54, 1));
+
+    let index = QuantityIndex::new(&FhirQuantity::with_ucum_code(2.5, "umol/L")).unwrap();
+    assert_eq!(index.code, "m-3.mol");
+    assert_eq!(index.canonical.value.normalize(), Decimal::new(25, 4));
+
+    let token = "5.4|http://unitsofmeasure.org|mmol/L";
+    let search = QuantitySearch::parse(token).unwrap();
+    assert_eq!(search.code.as_deref(), Some("mmol/L"));
+    assert!(matches(token, 5.4, "mmol/L"));
+    assert!(matches(token, 5400.0, "umol/L"));
+    assert!(!matches(token, 5.5, "mmol/L"));
+    assert!(!matches(token, 5.4, "umol/L"));
+    assert!(matches(
+        "gt5000|http://unitsofmeasure.org|umol/L",
+        5.4,
+        "mmol/L"
+    ));
+}
+
+#[test]
+fn tokens_parse() {
+    let search = QuantitySearch::parse("ge5.40e-3|http://unitsofmeasure.org|g").unwrap();
+    assert_eq!(search.prefix, SearchPrefix::Ge);
+    assert_eq!(search.value.value, Decimal::new(540, 5));
+    assert_eq!(search.value.low, Decimal::new(5395, 6));
+    assert_eq!(search.code.as_deref(), Some("g"));
+
+    let search: QuantitySearch = "100".parse().unwrap();
+    assert_eq!(search.prefix, SearchPrefix::Eq);
+    assert_eq!(search.system, None);
+    assert_eq!(search.code, None);
+
+    for bad in ["", "xx5", "5|mg", "5.4|a|b|c", "gt"] {
+        assert!(
+            matches!(
+                QuantitySearch::parse(bad),
+                Err(FhirError::InvalidSearchToken(_))
+            ),
+            "{bad}"
+        );
+    }
+    assert!(QuantitySearch::parse("5|http://unitsofmeasure.org|notaunit").is_err());
+}
+
+#[test]
+fn search_in_other_units() {
+    let token = "5.4|http://unitsofmeasure.org|g";
+    assert!(matches(token, 5400.0, "mg"));
+    assert!(matches(token, 5.41, "g"));
+    assert!(!matches(token, 5.5, "g"));
+    // A stored value less precise than the search is not contained
+    assert!(!matches(token, 5.0, "g"));
+    assert!(!matches(token, 5.4, "m"));
+
+    assert!(matches("5.4||mg", 0.0054, "g"));
+    assert!(!matches("5.4|http://example.org|mg", 5.4, "mg"));
+    assert!(matches("5.4", 5.4, "kg"));
+}
+
+#[test]
+fn prefixes() {
+    let q = |prefix: &str| format!("{prefix}5.4|http://unitsofmeasure.org|g");
+
+    assert!(matches(&q("ne"), 5.6, "g"));
+    assert!(!matches(&q("ne"), 5400.0, "mg"));
+
+    assert!(matches(&q("gt"), 5500.0, "mg"));
+    assert!(!matches(&q("gt"), 5.4, "g"));
+    assert!(matches(&q("lt"), 5.3, "g"));
+    assert!(!matches(&q("lt"), 5.4, "g"));
+
+    assert!(matches(&q("ge"), 5.4, "g"));
+    assert!(matches(&q("ge"), 5.38, "g"));
+    assert!(!matches(&q("ge"), 5.3, "g"));
+    assert!(matches(&q("le"), 5.42, "g"));
+    assert!(!matches(&q("le"), 5.5, "g"));
+
+    assert!(matches(&q("sa"), 5.5, "g"));
+    assert!(!matches(&q("sa"), 5.44, "g"));
+    assert!(matches(&q("eb"), 5.3, "g"));
+    assert!(!matches(&q("eb"), 5.36, "g"));
+
+    assert!(matches(&q("ap"), 5.9, "g"));
+    assert!(matches(&q("ap"), 4900.0, "mg"));
+    assert!(!matches(&q("ap"), 6.5, "g"));
+}
+
+#[test]
+fn approximate_bounds_beyond_the_decimal_range() {
+    let far = |sign: &str| {
+        let value: Decimal = format!("{sign}70000000000000000000000000000")
+            .parse()
+            .unwrap();
+        FhirQuantity::with_ucum_decimal(value, "g")
+    };
+    for (token, sign) in [
+        ("ap-75000000000000000000000000000", "-"),
+        ("ap75000000000000000000000000000", ""),
+    ] {
+        let search = QuantitySearch::parse(token).unwrap();
+        assert!(
+            !search
+                .matches(&FhirQuantity::with_ucum_code(1.0, "g"))
+                .unwrap()
+        );
+        assert!(search.matches(&far(sign)).unwrap());
+    }
+}
+
+#[test]
+fn special_units_are_searchable() {
+    // Temperatures are indexed in kelvin
+    assert!(matches(
+        "37.0|http://unitsofmeasure.org|Cel",
+        98.6,
+        "[degF]"
+    ));
+    assert!(matches("gt300|http://unitsofmeasure.org|K", 30.0, "Cel"));
+}
+
+#[test]
+fn engine_units_are_indexed_and_searched() {
+    let mut engine = UcumEngine::new();
+    engine
+        .register_unit(CustomUnit::new("tabs", "250.mg"))
+        .unwrap();
+    let stored = FhirQuantity::with_ucum_code(4.0, "tabs");
+    assert!(QuantityIndex::new(&stored).is_err());
+
+    let index = QuantityIndex::new_with(&engine, &stored).unwrap();
+    assert_eq!(index.code, "g");
+    assert_eq!(index.canonical.value, Decimal::ONE);
+    let search =
+        QuantitySearch::parse_with(&engine, "ap1000|http://unitsofmeasure.org|mg").unwrap();
+    assert!(search.matches_index(&index));
+    let search = QuantitySearch::parse_with(&engine, "gt2||tabs").unwrap();
+    let stored = QuantityIndex::new_with(&engine, &FhirQuantity::with_ucum_code(1.0, "g")).unwrap();
+    assert!(search.matches_index(&stored));
+    assert!(QuantitySearch::parse("gt2||tabs").is_err());
+}
@@ -0,0 +1,205 @@
+#![cfg(feature = "fhir")]
+
+use octofhir_ucum::fhir::{
+    FhirError, lookup, lookup_with, translate, translate_with, validate_code, validate_code_with,
+};
+use octofhir_ucum::{CustomUnit, UcumEngine};
+use serde_json::{Value, json};
+
+fn request(parameters: &[(&str, &str)]) -> Value {
+    let parameter: Vec<Value> = parameters
+        .iter()
+        .map(|(name, value)| json!({"name": name, "valueCode": value}))
+        .collect();
+    json!({"resourceType": "Parameters", "parameter": parameter})
+}
+
+fn param<'a>(response: &'a Value, name: &str) -> Option<&'a Value> {
+    response["parameter"]
+        .as_array()?
+        .iter()
+        .find(|p| p["name"] == name)
+}
+
+fn property<'a>(response: &'a Value, code: &str) -> Option<&'a Value> {
+    response["parameter"]
+        .as_array()?
+        .iter()
+        .filter(|p| p["name"] == "property")
+        .find(|p| p["part"][0]["valueCode"] == code)
+        .map(|p| &p["part"][1]["valueString"])
+}
+
+#[test]
+fn validate_code_results() {
+    let response = validate_code(&request(&[
+        ("system", "http://unitsofmeasure.org"),
+        ("code", "mg"),
+    ]))
+    .unwrap();
+    assert_eq!(param(&response, "result").unwrap()["valueBoolean"], true);
+    assert_eq!(
+        param(&response, "display").unwrap()["valueString"],
+        "(milligram)"
+    );
+
+    let response = validate_code(&request(&[("code", "mgg")])).unwrap();
+    assert_eq!(param(&response, "result").unwrap()["valueBoolean"], false);
+    assert!(param(&response, "message").is_some());
+
+    let response = validate_code(&request(&[("code", "mg"), ("display", "gram")])).unwrap();
+    assert_eq!(param(&response, "result").unwrap()["valueBoolean"], false);
+
+    let response =
+        validate_code(&request(&[("system", "http://loinc.org"), ("code", "mg")])).unwrap();
+    assert_eq!(param(&response, "result").unwrap()["valueBoolean"], false);
+
+    // A coding works in place of code and system
+    let coding = json!({"resourceType": "Parameters", "parameter": [
+        {"name": "coding", "valueCoding": {"system": "http://unitsofmeasure.org", "code": "kg"}}
+    ]});
+    let response = validate_code(&coding).unwrap();
+    assert_eq!(param(&response, "result").unwrap()["valueBoolean"], true);
+}
+
+#[test]
+fn malformed_requests() {
+    assert!(matches!(
+        validate_code(&json!({"resourceType": "Observation"})),
+        Err(FhirError::InvalidParameters(_))
+    ));
+    assert!(matches!(
+        validate_code(&request(&[])),
+        Err(FhirError::MissingField("code"))
+    ));
+    assert!(matches!(
+        translate(&request(&[("code", "mg")])),
+        Err(FhirError::MissingField("targetCode"))
+    ));
+}
+
+#[test]
+fn lookup_properties() {
+    let response = lookup(&request(&[("code", "mL")])).unwrap();
+    assert_eq!(param(&response, "name").unwrap()["valueString"], "UCUM");
+    assert_eq!(
+        param(&response, "display").unwrap()["valueString"],
+        "(milliliter)"
+    );
+    assert_eq!(property(&response, "property").unwrap(), "volume");
+    assert_eq!(property(&response, "canonical").unwrap(), "m3");
+    assert_eq!(property(&response, "definition").unwrap(), "0.000001 m3");
+
+    let response = lookup(&request(&[("code", "Cel")])).unwrap();
+    assert_eq!(property(&response, "definition").unwrap(), "1 K + 273.15 K");
+    let response = lookup(&request(&[("code", "[degF]")])).unwrap();
+    assert_eq!(
+        property(&response, "definition").unwrap(),
+        "0.55555555555555555555555556 K + 255.37222222222222222222222222 K"
+    );
+
+    // Compound codes have no kind of quantity of their own
+    let response = lookup(&request(&[("code", "mg/dL")])).unwrap();
+    assert!(property(&response, "property").is_none());
+    assert_eq!(property(&response, "canonical").unwrap(), "g.m-3");
+
+    // An invalid code gives an OperationOutcome, not an error
+    let response = lookup(&request(&[("code", "mgg")])).unwrap();
+    assert_eq!(response["resourceType"], "OperationOutcome");
+    assert_eq!(response["issue"][0]["code"], "code-invalid");
+    assert!(matches!(
+        lookup(&request(&[("system", "http://loinc.org"), ("code", "mg")])),
+        Err(FhirError::InvalidSystem(_))
+    ));
+}
+
+#[test]
+fn lookup_mole_units() {
+    let lookup_property = |code: &str, name: &str| {
+        let response = lookup(&request(&[("code", code)])).unwrap();
+        property(&response, name).unwrap().clone()
+    };
+    assert_eq!(lookup_property("mol", "canonical"), "mol");
+    assert_eq!(lookup_property("mol", "definition"), "1 mol");
+    assert_eq!(lookup_property("mmol/L", "canonical"), "m-3.mol");
+    assert_eq!(lookup_property("mmol/L", "definition"), "1 m-3.mol");
+    assert_eq!(lookup_property("umol/L", "definition"), "0.001 m-3.mol");
+    assert_eq!(lookup_property("kat", "property"), "catalytic activity");
+    assert_eq!(lookup_property("kat", "definition"), "1 mol.s-1");
+    assert_eq!(lookup_property("meq", "definition"), "0.001 mol");
+
+    // [pH] is a function of a concentration, not a plain number
+    assert_eq!(lookup_property("[pH]", "canonical"), "m-3.mol");
+    assert_eq!(
+        lookup_property("[pH]", "definition"),
+        "a logarithmic or trigonometric function of m-3.mol"
+    );
+}
+
+#[test]
+fn translate_between_commensurable_units() {
+    let response = translate(&request(&[("code", "Cel"), ("targetCode", "[degF]")])).unwrap();
+    assert_eq!(param(&response, "result").unwrap()["valueBoolean"], true);
+    let parts = &param(&response, "match").unwrap()["part"];
+    assert_eq!(parts[1]["valueCoding"]["code"], "[degF]");
+    assert_eq!(parts[2]["valueDecimal"].to_string(), "1.8");
+    assert_eq!(parts[3]["valueDecimal"].to_string(), "32");
+
+    let response = translate(&request(&[("code", "[degF]"), ("targetCode", "Cel")])).unwrap();
+    let parts = &param(&response, "match").unwrap()["part"];
+    if cfg!(feature = "arbitrary_precision") {
+        assert_eq!(
+            parts[2]["valueDecimal"].to_string(),
+            "0.55555555555555555555555556"
+        );
+        assert_eq!(
+            parts[3]["valueDecimal"].to_string(),
+            "-17.77777777777777777777777778"
+        );
+    } else {
+        assert_eq!(parts[3]["valueDecimal"].as_f64(), Some(-160.0 / 9.0));
+    }
+
+    // Logarithmic units have no factor
+    let response = translate(&request(&[("code", "[pH]"), ("targetCode", "mol/L")])).unwrap();
+    let parts = param(&response, "match").unwrap()["part"]
+        .as_array()
+        .unwrap();
+    assert_eq!(parts.len(), 2);
+
+    let response = translate(&request(&[("code", "g"), ("targetCode", "m")])).unwrap();
+    assert_eq!(param(&response, "result").unwrap()["valueBoolean"], false);
+    assert!(param(&response, "match").is_none());
+}
+
+#[test]
+fn engine_units_are_known_to_the_service() {
+    let mut engine = UcumEngine::new();
+    engine
+        .register_unit(CustomUnit::new("tabs", "250.mg").with_property("mass"))
+        .unwrap();
+    let code = [("code", "tabs")];
+
+    let response = validate_code_with(&engine, &request(&code)).unwrap();
+    assert_eq!(response["parameter"][0]["valueBoolean"], true);
+    let response = validate_code(&request(&code)).unwrap();
+    assert_eq!(response["parameter"][0]["valueBoolean"], false);
+
+    let response = lookup_with(&engine, &request(&code)).unwrap();
+    assert_eq!(response["resourceType"], "Parameters");
+    assert_eq!(response["parameter"][3]["part"][1]["valueString"], "mass");
+    assert_eq!(response["parameter"][5]["part"][1]["valueString"], "0.25 g");
+    assert_eq!(
+        lookup(&request(&code)).unwrap()["resourceType"],
+        "OperationOutcome"
+    );
+
+    let response =
+        translate_with(&engine, &request(&[("code", "tabs"), ("targetCode", "mg")])).unwrap();
+    assert_eq!(
+        response["parameter"][1]["part"][2]["valueDecimal"],
+        json!(250)
+    );
+    let response = translate(&request(&[("code", "tabs"), ("targetCode", "mg")])).unwrap();
+    assert_eq!(response["parameter"][0]["valueBoolean"], false);
+}
@@ -0,0 +1,137 @@
+#![cfg(feature = "fhir")]
+
+use octofhir_ucum::fhir::{
+    CalendarDuration, FhirError, FhirQuantity, QuantityUnit, convert_quantity, equals, equivalent,
+};
+use rust_decimal::Decimal;
+
+fn ucum(value: f64, code: &str) -> FhirQuantity {
+    FhirQuantity::with_ucum_code(value, code)
+}
+
+fn calendar(value: i64, keyword: &str) -> FhirQuantity {
+    FhirQuantity {
+        value: Decimal::from(value),
+        unit: Some(keyword.to_string()),
+        system: None,
+        code: None,
+        comparator: None,
+    }
+}
+
+#[test]
+fn equality_uses_least_precision() {
+    assert_eq!(equals(&ucum(5.4, "g"), &ucum(5400.0, "mg")), Some(true));
+    assert_eq!(equals(&ucum(5.4, "g"), &ucum(5.4, "g")), Some(true));
+    assert_eq!(equals(&ucum(5.4, "g"), &ucum(5.5, "g")), Some(false));
+    assert_eq!(equals(&ucum(1.0, "[in_i]"), &ucum(2.54, "cm")), Some(true));
+    // 5.43 g is 5.4 g at one decimal
+    assert_eq!(equals(&ucum(5.4, "g"), &ucum(5.43, "g")), Some(true));
+    assert_eq!(equals(&ucum(5.4, "g"), &ucum(5.46, "g")), Some(false));
+    assert_eq!(
+        equals(&ucum(37.0, "Cel"), &ucum(98.6, "[degF]")),
+        Some(true)
+    );
+}
+
+#[test]
+fn equality_at_the_edge_of_the_decimal_range() {
+    let max = FhirQuantity::with_ucum_decimal(Decimal::MAX, "g");
+    assert_eq!(equals(&max, &max), Some(true));
+    assert_eq!(equivalent(&max, &max), Some(true));
+    let below = FhirQuantity::with_ucum_decimal(Decimal::MAX - Decimal::TEN, "g");
+    assert_eq!(equals(&max, &below), Some(false));
+}
+
+#[test]
+fn equality_of_incomparable_units_is_empty() {
+    assert_eq!(equals(&ucum(1.0, "g"), &ucum(1.0, "s")), None);
+    assert_eq!(equals(&ucum(1.0, "g"), &ucum(1.0, "notaunit")), None);
+    assert_eq!(equivalent(&ucum(1.0, "g"), &ucum(1.0, "s")), Some(false));
+    assert_eq!(
+        equivalent(&ucum(1.0, "g"), &ucum(1.0, "notaunit")),
+        Some(false)
+    );
+
+    let other = |value| FhirQuantity {
+        system: Some("http://example.org".to_string()),
+        ..ucum(value, "tab")
+    };
+    assert_eq!(equals(&other(2.0), &other(2.0)), Some(true));
+    assert_eq!(equals(&other(2.0), &ucum(2.0, "g")), None);
+    assert_eq!(equivalent(&other(2.0), &other(3.0)), Some(false));
+}
+
+#[test]
+fn calendar_durations() {
+    // Years and months are not comparable with definite durations for `=`
+    assert_eq!(equals(&calendar(1, "year"), &ucum(1.0, "a")), None);
+    assert_eq!(equals(&ucum(1.0, "mo"), &calendar(1, "month")), None);
+    assert_eq!(
+        equivalent(&calendar(1, "year"), &ucum(1.0, "a")),
+        Some(true)
+    );
+    assert_eq!(
+        equivalent(&ucum(1.0, "mo"), &calendar(1, "months")),
+        Some(true)
+    );
+
+    // Weeks and shorter compare either way
+    assert_eq!(equals(&calendar(1, "week"), &ucum(7.0, "d")), Some(true));
+    assert_eq!(equals(&calendar(1, "second"), &ucum(1.0, "s")), Some(true));
+    assert_eq!(equals(&calendar(2, "days"), &ucum(48.0, "h")), Some(true));
+
+    // Calendar durations compare among themselves
+    assert_eq!(
+        equals(&calendar(1, "year"), &calendar(12, "months")),
+        Some(true)
+    );
+}
+
+#[test]
+fn quantity_units_resolve() {
+    assert_eq!(
+        QuantityUnit::resolve("weeks").unwrap(),
+        QuantityUnit::Calendar(CalendarDuration::Week)
+    );
+    assert_eq!(
+        QuantityUnit::resolve("'wk'").unwrap(),
+        QuantityUnit::Ucum("wk".to_string())
+    );
+    assert_eq!(
+        QuantityUnit::resolve("mg").unwrap(),
+        QuantityUnit::Ucum("mg".to_string())
+    );
+    // Quoted keywords are UCUM codes, which `year` is not
+    assert!(matches!(
+        QuantityUnit::resolve("'year'"),
+        Err(FhirError::UcumError(_))
+    ));
+
+    let year = QuantityUnit::resolve("year").unwrap();
+    let day = QuantityUnit::resolve("day").unwrap();
+    let annum = QuantityUnit::resolve("'a'").unwrap();
+    assert!(!year.is_comparable_for_equality(&annum));
+    assert!(year.is_comparable_for_equality(&day));
+    assert!(day.is_comparable_for_equality(&QuantityUnit::resolve("'d'").unwrap()));
+}
+
+#[test]
+fn calendar_quantities_are_fhir_quantities() {
+    let quantity = QuantityUnit::resolve("days")
+        .unwrap()
+        .to_fhir_quantity(Decimal::new(20, 1));
+    assert_eq!(quantity, calendar(2, "day"));
+    assert_eq!(
+        QuantityUnit::of(&quantity),
+        Some(QuantityUnit::Calendar(CalendarDuration::Day))
+    );
+    assert_eq!(
+        QuantityUnit::of(&ucum(1.0, "d")),
+        Some(QuantityUnit::Ucum("d".to_string()))
+    );
+
+    // 2.0 days is precise to 2.4 hours
+    let converted = convert_quantity(&quantity, "h").unwrap();
+    assert_eq!(converted.value.to_string(), "48");
+}
@@ -70,18 +70,14 @@ fn special_units_lookup() {
     }
 
     // Test specific units that should exist
-    let pi_unit = find_unit("[pi]");
-    if pi_unit.is_some() {
-        let pi = pi_unit.unwrap();
+    if let Some(pi) = find_unit("[pi]") {
         println!(
             "  [pi] details: factor={}, dim={:?}, code={}",
             pi.factor, pi.dim, pi.code
         );
     }
 
-    let in_i_unit = find_unit("[in_i]");
-    if in_i_unit.is_some() {
-        let in_i = in_i_unit.unwrap();
+    if let Some(in_i) = find_unit("[in_i]") {
         println!(
             "  [in_i] details: factor={}, dim={:?}, code={}",
             in_i.factor, in_i.dim, in_i.code
@@ -1,4 +1,4 @@
-use octofhir_ucum::{OwnedUnitExpr, parse_expression};
+use octofhir_ucum::{OwnedUnitExpr, TokenKind, default_engine, parse_expression, tokenize};
 
 fn sym(s: &str) -> OwnedUnitExpr {
     OwnedUnitExpr::Symbol(s.to_string())
@@ -15,3 +15,24 @@ fn unicode_micro_alias_complex() {
     let expected = OwnedUnitExpr::Quotient(Box::new(sym("umol")), Box::new(sym("L")));
     assert_eq!(expr, expected);
 }
+
+#[test]
+fn unicode_micro_spans() {
+    // Byte ranges around the two-byte `µ`, which alone reads as the atom `u`
+    let spanned = default_engine().parse_spanned("µg").unwrap();
+    assert_eq!(spanned.spans.prefix, Some(0..2));
+    let spanned = default_engine().parse_spanned("µ").unwrap();
+    assert_eq!((spanned.expr, spanned.spans.prefix), (sym("u"), None));
+
+    let tokens = tokenize("µ/µu");
+    let kinds: Vec<_> = tokens.iter().map(|t| (t.kind, t.text)).collect();
+    assert_eq!(
+        kinds,
+        [
+            (TokenKind::Atom, "µ"),
+            (TokenKind::Operator, "/"),
+            (TokenKind::Prefix, "µ"),
+            (TokenKind::Atom, "u"),
+        ]
+    );
+}
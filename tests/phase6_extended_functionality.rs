@@ -31,24 +31,27 @@ fn test_optimize_expression() {
 #[test]
 fn test_canonicalize_expression() {
     // Test canonicalization of derived units
-    assert_eq!(canonicalize_expression("N").unwrap(), "kg.m.s-2");
-    assert_eq!(canonicalize_expression("J").unwrap(), "kg.m2.s-2");
-    assert_eq!(canonicalize_expression("W").unwrap(), "kg.m2.s-3");
-    assert_eq!(canonicalize_expression("Pa").unwrap(), "kg.m-1.s-2");
+    assert_eq!(canonicalize_expression("N").unwrap(), "g.m.s-2");
+    assert_eq!(canonicalize_expression("J").unwrap(), "g.m2.s-2");
+    assert_eq!(canonicalize_expression("W").unwrap(), "g.m2.s-3");
+    assert_eq!(canonicalize_expression("Pa").unwrap(), "g.m-1.s-2");
     assert_eq!(canonicalize_expression("Hz").unwrap(), "s-1");
 
     // Test canonicalization of prefixed units
     assert_eq!(canonicalize_expression("km").unwrap(), "m");
-    assert_eq!(canonicalize_expression("mg").unwrap(), "kg");
+    assert_eq!(canonicalize_expression("mg").unwrap(), "g");
 
     // Test that base units remain in canonical form
     assert_eq!(canonicalize_expression("m").unwrap(), "m");
-    assert_eq!(canonicalize_expression("kg").unwrap(), "kg");
+    assert_eq!(canonicalize_expression("kg").unwrap(), "g");
     assert_eq!(canonicalize_expression("s").unwrap(), "s");
 
     // Test dimensionless units
     assert_eq!(canonicalize_expression("1").unwrap(), "1");
 
+    // Arbitrary units are kept as terms of their own
+    assert_eq!(canonicalize_expression("[IU]/mL").unwrap(), "[IU].m-3");
+
     // Test error handling
     assert!(canonicalize_expression("invalid_unit").is_err());
 }
@@ -79,15 +82,14 @@ fn test_expression_optimization_equivalence() {
     let test_cases = vec!["m2", "kg.m/s2", "kg.m2/s2", "/s", "kg/(m.s2)"];
 
     for expr in test_cases {
-        if let Ok(original_analysis) = analyse(expr) {
-            if let Ok(optimized) = optimize_expression(expr) {
-                if let Ok(optimized_analysis) = analyse(&optimized) {
-                    assert_eq!(
-                        original_analysis.dimension, optimized_analysis.dimension,
-                        "Optimization changed dimensions for expression: {expr}"
-                    );
-                }
-            }
+        if let Ok(original_analysis) = analyse(expr)
+            && let Ok(optimized) = optimize_expression(expr)
+            && let Ok(optimized_analysis) = analyse(&optimized)
+        {
+            assert_eq!(
+                original_analysis.dimension, optimized_analysis.dimension,
+                "Optimization changed dimensions for expression: {expr}"
+            );
         }
     }
 }
@@ -98,15 +100,14 @@ fn test_simplification_equivalence() {
     let test_cases = vec!["m.s/s", "kg.m/m", "kg.m2/s2"];
 
     for expr in test_cases {
-        if let Ok(original_analysis) = analyse(expr) {
-            if let Ok(simplified) = simplify_expression(expr) {
-                if let Ok(simplified_analysis) = analyse(&simplified) {
-                    assert_eq!(
-                        original_analysis.dimension, simplified_analysis.dimension,
-                        "Simplification changed dimensions for expression: {expr}"
-                    );
-                }
-            }
+        if let Ok(original_analysis) = analyse(expr)
+            && let Ok(simplified) = simplify_expression(expr)
+            && let Ok(simplified_analysis) = analyse(&simplified)
+        {
+            assert_eq!(
+                original_analysis.dimension, simplified_analysis.dimension,
+                "Simplification changed dimensions for expression: {expr}"
+            );
         }
     }
 }
@@ -0,0 +1,138 @@
+use std::cmp::Ordering;
+
+use octofhir_ucum::{CustomUnit, ErrorKind, Quantity, UcumEngine};
+
+fn q(value: f64, unit: &str) -> Quantity {
+    Quantity::new(value, unit).expect("valid quantity")
+}
+
+#[test]
+fn add_converts_to_left_unit() {
+    let sum = (q(1.0, "kg") + q(250.0, "g")).unwrap();
+    assert!((sum.value - 1.25).abs() < 1e-9);
+    assert_eq!(sum.unit.to_string(), "kg");
+
+    let sum = (q(250.0, "g") + q(1.0, "kg")).unwrap();
+    assert!((sum.value - 1250.0).abs() < 1e-9);
+    assert_eq!(sum.unit.to_string(), "g");
+}
+
+#[test]
+fn sub_converts_to_left_unit() {
+    let diff = (&q(2.0, "h") - &q(30.0, "min")).unwrap();
+    assert!((diff.value - 1.5).abs() < 1e-9);
+    assert_eq!(diff.unit.to_string(), "h");
+}
+
+#[test]
+fn add_incommensurable_fails() {
+    let err = (q(1.0, "m") + q(1.0, "s")).unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::DimensionMismatch { .. }));
+}
+
+#[test]
+fn offset_units_rejected_in_arithmetic() {
+    assert!((q(20.0, "Cel") + q(5.0, "Cel")).is_err());
+    assert!((q(20.0, "K") - q(5.0, "Cel")).is_err());
+    assert!((q(20.0, "Cel") * q(2.0, "m")).is_err());
+    assert!((q(20.0, "m") / q(2.0, "Cel")).is_err());
+}
+
+#[test]
+fn offset_units_convert_and_compare() {
+    let k = q(25.0, "Cel").convert_to("K").unwrap();
+    assert!((k.value - 298.15).abs() < 1e-9);
+    assert!(q(0.0, "Cel").approx_eq(&q(273.15, "K")));
+    assert_eq!(
+        q(30.0, "Cel").compare(&q(300.0, "K")),
+        Some(Ordering::Greater)
+    );
+}
+
+#[test]
+fn mul_and_div_combine_units() {
+    let area = (q(2.0, "m") * q(3.0, "m")).unwrap();
+    assert!((area.value - 6.0).abs() < 1e-12);
+    assert!(area.approx_eq(&q(6.0, "m2")));
+
+    let speed = (q(100.0, "km") / q(2.0, "h")).unwrap();
+    assert!((speed.value - 50.0).abs() < 1e-12);
+    assert!(speed.approx_eq(&q(50.0, "km/h")));
+
+    assert!((q(1.0, "m") / q(0.0, "s")).is_err());
+}
+
+#[test]
+fn neg_and_scalar_ops() {
+    let neg = -q(5.0, "mg");
+    assert_eq!(neg.value, -5.0);
+    assert_eq!((q(5.0, "mg") * 2.0).value, 10.0);
+    assert_eq!((q(5.0, "mg") / 2.0).value, 2.5);
+}
+
+#[test]
+fn equality_and_ordering_across_units() {
+    assert!(q(1.0, "m").approx_eq(&q(100.0, "cm")));
+    assert!(!q(1.0, "m").approx_eq(&q(1.0, "cm")));
+    assert_eq!(
+        q(1.0, "kg").compare(&q(999.0, "g")),
+        Some(Ordering::Greater)
+    );
+    assert_eq!(q(1.0, "mg").compare(&q(1.0, "g")), Some(Ordering::Less));
+
+    // The comparison operators convert too
+    assert!(q(1.0, "kg") > q(999.0, "g"));
+    assert!(q(1.0, "mg") < q(1.0, "g"));
+    assert!(q(1.0, "m") <= q(100.0, "cm") && q(1.0, "m") >= q(100.0, "cm"));
+    assert_eq!(
+        q(0.0, "Cel").partial_cmp(&q(273.15, "K")),
+        Some(Ordering::Equal)
+    );
+
+    // Incommensurable quantities are neither equal nor ordered.
+    assert!(!q(1.0, "m").approx_eq(&q(1.0, "s")));
+    assert_eq!(q(1.0, "m").compare(&q(1.0, "s")), None);
+    let (m, s) = (q(1.0, "m"), q(1.0, "s"));
+    assert_eq!([m < s, m <= s, m > s, m >= s], [false; 4]);
+}
+
+#[test]
+fn partial_eq_is_structural() {
+    assert_eq!(q(1.0, "m"), q(1.0, "m"));
+    assert_ne!(q(1.0, "m"), q(100.0, "cm"));
+}
+
+#[test]
+fn invalid_unit_rejected() {
+    assert!(Quantity::new(1.0, "not_a_unit").is_err());
+}
+
+#[test]
+fn engine_quantities_use_its_units() {
+    let mut engine = UcumEngine::new();
+    engine
+        .register_unit(CustomUnit::new("tabs", "250.mg"))
+        .unwrap();
+    assert!(Quantity::new(1.0, "tabs").is_err());
+
+    let tablets = engine.quantity(2.0, "tabs").unwrap();
+    let grams = engine.quantity(1.0, "g").unwrap();
+    let sum = engine.add_quantities(&grams, &tablets).unwrap();
+    assert!((sum.value - 1.5).abs() < 1e-9);
+    let difference = engine.subtract_quantities(&grams, &tablets).unwrap();
+    assert!((difference.value - 0.5).abs() < 1e-9);
+    assert_eq!(
+        engine.compare_quantities(&tablets, &grams),
+        Some(Ordering::Less)
+    );
+    let per_day = engine
+        .divide_quantities(&tablets, &engine.quantity(1.0, "d").unwrap())
+        .unwrap();
+    assert_eq!(per_day.unit.to_string(), "tabs/d");
+    let doubled = engine
+        .multiply_quantities(&tablets, &engine.quantity(2.0, "1").unwrap())
+        .unwrap();
+    assert_eq!(doubled.value, 4.0);
+    let mg = engine.convert_quantity(&tablets, "mg").unwrap();
+    assert!((mg.value - 500.0).abs() < 1e-9);
+}
@@ -0,0 +1,163 @@
+use octofhir_ucum::{
+    AnnotationMode, OwnedUnitExpr, SerializeStyle, evaluate_owned, parse_expression,
+};
+use rust_decimal::Decimal;
+
+const STYLES: [SerializeStyle; 3] = [
+    SerializeStyle::Preserving,
+    SerializeStyle::Minimal,
+    SerializeStyle::Normalized,
+];
+
+/// Serialize in `style` and check that the code parses to an equivalent unit.
+fn round_trip(code: &str, style: SerializeStyle) -> String {
+    let expr = parse_expression(code).unwrap();
+    let text = expr.to_ucum(style);
+    let reparsed = parse_expression(&text)
+        .unwrap_or_else(|e| panic!("{code} as {style:?} gave {text}: {}", e.message));
+    let (a, b) = (
+        evaluate_owned(&expr).unwrap(),
+        evaluate_owned(&reparsed).unwrap(),
+    );
+    assert_eq!(a.dim, b.dim, "{code} as {style:?} gave {text}");
+    // Factors keep 28 decimal places, so `mol-1` (1.6605e-24) carries only a
+    // few significant digits and differs from the same unit written `/mol`
+    let tolerance = a.factor.abs() * Decimal::new(1, 4);
+    assert!(
+        (a.factor - b.factor).abs() <= tolerance,
+        "{code} as {style:?} gave {text}"
+    );
+    assert_eq!(
+        reparsed.to_ucum(style),
+        text,
+        "{code} as {style:?} is not stable"
+    );
+    if style == SerializeStyle::Preserving && !has_group_power(&expr) {
+        assert_eq!(reparsed, expr, "{code} gave {text}");
+    }
+    text
+}
+
+/// Whether `expr` has an exponent or annotation on something other than an
+/// atom, which serializing moves onto the atoms.
+fn has_group_power(expr: &OwnedUnitExpr) -> bool {
+    let atom = |expr: &OwnedUnitExpr| matches!(expr, OwnedUnitExpr::Symbol(_));
+    match expr {
+        OwnedUnitExpr::Product(factors) => factors
+            .iter()
+            .any(|f| (f.exponent != 1 && !atom(&f.expr)) || has_group_power(&f.expr)),
+        OwnedUnitExpr::Quotient(num, den) => has_group_power(num) || has_group_power(den),
+        OwnedUnitExpr::Power(inner, _) => !atom(inner),
+        OwnedUnitExpr::Annotated(inner, _) => match &**inner {
+            OwnedUnitExpr::Symbol(_) | OwnedUnitExpr::Numeric(_) => false,
+            OwnedUnitExpr::Power(inner, _) => !atom(inner),
+            _ => true,
+        },
+        _ => false,
+    }
+}
+
+#[test]
+fn display_is_valid_ucum() {
+    let cases = [
+        ("kg.m/s2", "kg.m/s2"),
+        ("m3.kg-1.s-2", "m3.kg-1.s-2"),
+        ("(kg.m)2", "kg2.m2"),
+        ("(m/s)2", "m2.s-2"),
+        ("(/s)-1", "s"),
+        ("(10*3)2", "10*6"),
+        ("mg{x}.((m.s)2{y})-1", "mg{x}.m-2.s-2.{y}"),
+        ("(m.s){y}", "(m.s).{y}"),
+        ("kg/(m.s2)", "kg/(m.s2)"),
+        ("10*3/uL", "10*3/uL"),
+        ("10*-7", "10*-7"),
+        ("mg{creat}", "mg{creat}"),
+        ("m2{a}", "m2{a}"),
+        ("{cells}/uL", "{cells}/uL"),
+        ("/min", "1/min"),
+        ("kg.(m/s)", "kg.(m/s)"),
+        ("m2.10*3", "m2.10*3"),
+    ];
+    for (code, expected) in cases {
+        assert_eq!(parse_expression(code).unwrap().to_string(), expected);
+        assert_eq!(round_trip(code, SerializeStyle::Preserving), expected);
+    }
+}
+
+#[test]
+fn styles() {
+    let cases = [
+        ("1/min", "/min", "min-1"),
+        ("m/s/s", "m/s/s", "m.s-2"),
+        ("m.s/s", "m.s/s", "m"),
+        ("(/s)/m", "1/s/m", "s-1.m-1"),
+        ("1/(s.kg)", "/s.kg", "s-1.kg-1"),
+        ("1/min{beats}", "/min{beats}", "min-1{beats}"),
+        (
+            "4.[pi].10*-7.N/A2",
+            "4.[pi].10*-7.N/A2",
+            "10*-7.4.[pi].N.A-2",
+        ),
+        ("(10*3)2", "10*6", "10*6"),
+        ("(kg/m)2", "kg2.m-2", "kg2.m-2"),
+        (
+            "mg{x}.((m.s)2{y})-1",
+            "mg{x}.m-2.s-2.{y}",
+            "mg{x}.{y}.m-2.s-2",
+        ),
+        ("(m)1.1", "m", "m"),
+    ];
+    for (code, minimal, normalized) in cases {
+        assert_eq!(round_trip(code, SerializeStyle::Minimal), minimal);
+        assert_eq!(round_trip(code, SerializeStyle::Normalized), normalized);
+    }
+}
+
+#[test]
+fn numbers_stay_integers() {
+    let cases = [
+        (1000.0, "10*3"),
+        (1500.0, "1500"),
+        (2.5, "10*-1.25"),
+        (0.001, "10*-3"),
+        (6.02e23, "10*21.602"),
+    ];
+    for (value, expected) in cases {
+        let expr = OwnedUnitExpr::Numeric(value);
+        assert_eq!(expr.to_string(), expected);
+        let reparsed = evaluate_owned(&parse_expression(expected).unwrap()).unwrap();
+        let value = Decimal::try_from(value).unwrap();
+        assert!((reparsed.factor - value).abs() <= value * Decimal::new(1, 12));
+    }
+
+    // A number after a number is kept apart from it
+    let product = parse_expression("4.(10)").unwrap();
+    assert_eq!(round_trip("4.(10)", SerializeStyle::Preserving), "4.(10)");
+    assert!(product.eq_with(
+        &parse_expression(&product.to_string()).unwrap(),
+        AnnotationMode::Consider
+    ));
+}
+
+#[test]
+fn official_valid_units_round_trip() {
+    let xml = std::fs::read_to_string("tests/official/UcumFunctionalTests.xml").unwrap();
+    let units = xml
+        .lines()
+        .filter(|line| line.contains("<case") && line.contains("valid=\"true\""))
+        .filter_map(|line| line.split("unit=\"").nth(1)?.split('"').next())
+        .map(|unit| unit.replace("&quot;", "\"").replace("&amp;", "&"));
+    let mut count = 0;
+    for unit in units {
+        if parse_expression(&unit).is_err()
+            || evaluate_owned(&parse_expression(&unit).unwrap()).is_err()
+        {
+            continue;
+        }
+        for style in STYLES {
+            round_trip(&unit, style);
+        }
+        count += 1;
+    }
+    assert!(count > 100, "only {count} units were checked");
+}
@@ -1,5 +1,7 @@
 use octofhir_ucum::precision::{NumericOps, from_f64, to_f64};
-use octofhir_ucum::{EvalResult, OwnedUnitExpr, OwnedUnitFactor, evaluate_owned};
+use octofhir_ucum::{
+    ErrorKind, EvalResult, OwnedUnitExpr, OwnedUnitFactor, Quantity, default_engine, evaluate_owned,
+};
 
 fn eval_ratio(expr: OwnedUnitExpr) -> f64 {
     to_f64(evaluate_owned(&expr).unwrap().factor)
@@ -225,3 +227,61 @@ fn test_zero_special_units() {
         );
     }
 }
+
+#[test]
+fn special_unit_conversions_use_handlers() {
+    let engine = default_engine();
+    let close = |value: f64, from: &str, to: &str, expected: f64| {
+        let got = engine.convert(value, from, to).unwrap();
+        assert!(
+            (got - expected).abs() <= 1e-9 * expected.abs().max(1.0),
+            "{value} {from} -> {to}: expected {expected}, got {got}"
+        );
+    };
+
+    // Bel units hold values in bels; decibels are the d-prefixed form
+    close(30.0, "dB[W]", "B[W]", 3.0);
+    close(1.0, "B[W]", "W", 10.0);
+    close(20.0, "dB[W]", "mW", 100_000.0);
+    close(0.02, "Pa", "B[SPL]", 6.0);
+    close(20.0, "dB[V]", "V", 10.0);
+    close(1.0, "Np", "1", std::f64::consts::E);
+
+    close(7.0, "[pH]", "mol/L", 1e-7);
+    close(1e-3, "mol/L", "[pH]", 3.0);
+    close(2.0, "[hp'_C]", "1", 1e-4);
+
+    close(100.0, "[p'diop]", "deg", 45.0);
+    close(45.0, "deg", "[p'diop]", 100.0);
+
+    assert_eq!(engine.convert(20.0, "Cel", "[degF]").unwrap(), 68.0);
+    close(0.0, "[degRe]", "Cel", 0.0);
+    close(80.0, "[degRe]", "K", 373.15);
+}
+
+#[test]
+fn special_unit_conversion_errors() {
+    let engine = default_engine();
+
+    // A plain bel is a ratio, not a power level
+    let err = engine.convert(10.0, "dB", "B[W]").unwrap_err();
+    assert!(matches!(err.kind, ErrorKind::DimensionMismatch { .. }));
+
+    // Logarithmic units cannot be scaled as part of a larger expression
+    assert!(engine.convert(1.0, "B[W]/s", "W/s").is_err());
+
+    // The p-scale of a non-positive concentration is undefined
+    assert!(engine.convert(0.0, "mol/L", "[pH]").is_err());
+}
+
+#[test]
+fn quantities_convert_special_units() {
+    let power = Quantity::new(30.0, "dB[W]").unwrap();
+    let watts = power.convert_to("W").unwrap();
+    assert!((watts.value - 1000.0).abs() < 1e-9);
+    assert!(
+        Quantity::new(1.0, "B[W]")
+            .unwrap()
+            .approx_eq(&Quantity::new(10.0, "W").unwrap())
+    );
+}
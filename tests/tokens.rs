@@ -0,0 +1,67 @@
+use octofhir_ucum::{TokenKind, tokenize};
+
+fn kinds(input: &str) -> Vec<(TokenKind, &str)> {
+    tokenize(input).iter().map(|t| (t.kind, t.text)).collect()
+}
+
+#[test]
+fn tokens_are_labelled() {
+    use TokenKind::*;
+    assert_eq!(
+        kinds("10*3.m[IU]/(µg.s^-2){dry}"),
+        [
+            (TenPower, "10*3"),
+            (Operator, "."),
+            (Prefix, "m"),
+            (BracketedAtom, "[IU]"),
+            (Operator, "/"),
+            (OpenParen, "("),
+            (Prefix, "µ"),
+            (Atom, "g"),
+            (Operator, "."),
+            (Atom, "s"),
+            (Operator, "^"),
+            (Exponent, "-2"),
+            (CloseParen, ")"),
+            (Annotation, "{dry}"),
+        ]
+    );
+    assert_eq!(
+        kinds("4.5.mm[Hg]2"),
+        [
+            (Number, "4.5"),
+            (Operator, "."),
+            (Prefix, "m"),
+            (Atom, "m[Hg]"),
+            (Exponent, "2"),
+        ]
+    );
+
+    let tokens = tokenize(" kg");
+    assert_eq!(tokens[0].range, 1..2);
+    assert_eq!(tokens[1].range, 2..3);
+}
+
+#[test]
+fn errors_do_not_stop_tokenizing() {
+    use TokenKind::*;
+    assert_eq!(
+        kinds("mgx/dl.sec2/"),
+        [
+            (Error, "mgx"),
+            (Operator, "/"),
+            (Prefix, "d"),
+            (Atom, "l"),
+            (Operator, "."),
+            (Error, "sec"),
+            (Exponent, "2"),
+            (Operator, "/"),
+        ]
+    );
+    assert_eq!(kinds("m^+"), [(Atom, "m"), (Error, "^"), (Error, "+")]);
+    assert_eq!(
+        kinds("10*.{cells"),
+        [(Error, "10*"), (Operator, "."), (Error, "{cells")]
+    );
+    assert_eq!(kinds("[ft_i"), [(Error, "[ft_i")]);
+}